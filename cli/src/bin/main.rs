@@ -1,14 +1,20 @@
 use anyhow::Result;
 use joule_profiler_cli::{
-    CliArgs, ProfilerCommand, RaplBackend, init_logging, output_format_to_displayer,
-    parse_sockets_spec,
+    BUDGET_EXCEEDED_EXIT_CODE, CliArgs, ProfilerCommand, RaplBackend, budgets, check_polling,
+    compare::compare, footprint, init_logging, output_format_to_displayer, parse_sockets_spec,
+    report::apply_filter,
 };
 use joule_profiler_core::JouleProfiler;
+use joule_profiler_core::budget::{Budget, BudgetReport};
 use joule_profiler_core::config::{Command, Config};
+use joule_profiler_core::recording::Recording;
+use joule_profiler_core::source::{BlockingAdapter, BlockingMetricReader};
+use joule_profiler_core::types::{Phase, ProfilerResults, SavedResults};
 use joule_profiler_source_nvml::Nvml;
 use joule_profiler_source_perf_event::PerfEvent;
+use joule_profiler_source_proc::CpuTime;
 use joule_profiler_source_rapl::{perf, powercap};
-use log::{trace, warn};
+use log::{error, trace, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,33 +22,155 @@ async fn main() -> Result<()> {
     init_logging(cli.verbose);
 
     let mut displayer = output_format_to_displayer(&cli)?;
+    let footprint = footprint(&cli)?;
+    let budgets = budgets(&cli)?;
     let mut profiler = JouleProfiler::new();
 
+    if matches!(
+        cli.command,
+        ProfilerCommand::Profile(_) | ProfilerCommand::ListSensors
+    ) {
+        add_sources(&mut profiler, &cli)?;
+    }
+
+    let config = Config::from(cli);
+
+    match config.command {
+        Command::Profile(profile_config) => {
+            if let Some(mut listener) =
+                displayer.phase_listener(&profile_config.cmd, &profile_config.token_pattern)?
+            {
+                let footprint = footprint.clone();
+                profiler.set_phase_listener(move |phase: &Phase| match &footprint {
+                    Some(footprint) => {
+                        let mut phase = phase.clone();
+                        footprint.apply_to_phase(&mut phase);
+                        listener(&phase);
+                    }
+                    None => listener(phase),
+                });
+            }
+            let mut results = profiler.profile(&profile_config).await?;
+            if let Some(footprint) = &footprint {
+                footprint.apply(&mut results);
+            }
+            let report = check_budgets(&budgets, &results);
+            displayer.display_results(
+                &profile_config.cmd,
+                &profile_config.token_pattern,
+                &results,
+                report.as_ref(),
+            )?;
+            exit_on_violation(report.as_ref());
+        }
+        Command::ListSensors => {
+            let sensors = profiler.list_sensors()?;
+            displayer.list_sensors(&sensors)?;
+        }
+        Command::Replay(replay_config) => {
+            let recording = Recording::from_file(&replay_config.recording_file)?;
+            let mut results = profiler.replay(&recording).await?;
+            if let Some(footprint) = &footprint {
+                footprint.apply(&mut results);
+            }
+            let report = check_budgets(&budgets, &results);
+            displayer.display_results(
+                &recording.command,
+                &recording.token_pattern,
+                &results,
+                report.as_ref(),
+            )?;
+            exit_on_violation(report.as_ref());
+        }
+        Command::Compare(compare_config) => {
+            let comparison = compare(
+                &compare_config.results_files,
+                compare_config.significance_level,
+                &compare_config.outlier_filter,
+            )?;
+            displayer.display_comparison(&comparison)?;
+        }
+        Command::Report(report_config) => {
+            let saved = SavedResults::from_file(&report_config.results_file)?;
+            let cmd = [saved.command.clone()];
+            let token_pattern = saved.token_pattern.clone();
+            let saved_report = saved.budgets.clone();
+            let mut results = ProfilerResults::from(saved);
+            apply_filter(&report_config.filter, &mut results);
+            // Budgets given on the command line are checked again, otherwise the saved outcome is used.
+            let report = check_budgets(&budgets, &results).or(saved_report);
+            displayer.display_results(&cmd, &token_pattern, &results, report.as_ref())?;
+            exit_on_violation(report.as_ref());
+        }
+        Command::Schema => {
+            let schema = serde_json::to_string_pretty(&SavedResults::schema())?;
+            println!("{schema}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the budgets against the results, `None` if no budget is set.
+fn check_budgets(budgets: &[Budget], results: &ProfilerResults) -> Option<BudgetReport> {
+    (!budgets.is_empty()).then(|| BudgetReport::check(budgets, results))
+}
+
+/// Exits with [`BUDGET_EXCEEDED_EXIT_CODE`] if a budget is exceeded.
+fn exit_on_violation(report: Option<&BudgetReport>) {
+    if let Some(report) = report
+        && !report.passed()
+    {
+        error!("{} budget(s) exceeded", report.violations().count());
+        std::process::exit(BUDGET_EXCEEDED_EXIT_CODE);
+    }
+}
+
+/// Registers a RAPL source, polled by the profiler every `polling_rate_s` seconds if the source
+/// supports it, so that the polled readings give the power samples of the phases.
+fn add_rapl<R: BlockingMetricReader>(
+    profiler: &mut JouleProfiler,
+    reader: R,
+    polling_rate_s: Option<f64>,
+) -> Result<()> {
+    let reader = BlockingAdapter::new(reader);
+    match check_polling(&reader, polling_rate_s)? {
+        Some(interval) => profiler.add_source_with_polling(reader, interval),
+        None => profiler.add_source(reader),
+    }
+    Ok(())
+}
+
+/// Registers the metric sources enabled by the CLI arguments.
+fn add_sources(profiler: &mut JouleProfiler, cli: &CliArgs) -> Result<()> {
     let rapl_path = cli.rapl_path.as_deref();
     let rapl_sockets_spec = parse_sockets_spec(cli.sockets.as_deref());
-    let rapl_polling = match &cli.command {
-        ProfilerCommand::Profile(profile_args) => profile_args.rapl_polling,
-        ProfilerCommand::ListSensors => None,
+    let (rapl_polling, attribute, power_histogram) = match &cli.command {
+        ProfilerCommand::Profile(profile_args) => (
+            profile_args.rapl_polling,
+            profile_args.attribute,
+            profile_args.power_histogram,
+        ),
+        _ => (None, false, None),
     };
+    profiler.set_power_histogram_buckets(power_histogram);
 
     match cli.rapl_backend {
         RaplBackend::Perf => {
             if let Err(err) = perf::Rapl::check_perf_access() {
                 warn!("Cannot initialize RAPL with perf_event, switching to powercap: {err}");
-                let rapl_powercap =
-                    powercap::Rapl::new(rapl_path, rapl_sockets_spec.as_ref(), rapl_polling)?;
-                profiler.add_source(rapl_powercap);
+                let rapl_powercap = powercap::Rapl::new(rapl_path, rapl_sockets_spec.as_ref())?;
+                add_rapl(profiler, rapl_powercap, rapl_polling)?;
             } else {
                 trace!("Using perf_event for RAPL profiling");
                 let perf_rapl = perf::Rapl::new(rapl_sockets_spec.as_ref())?;
-                profiler.add_source(perf_rapl);
+                add_rapl(profiler, perf_rapl, rapl_polling)?;
             }
         }
         RaplBackend::Powercap => {
             trace!("Using Powercap for RAPL profiling");
-            let rapl_powercap =
-                powercap::Rapl::new(rapl_path, rapl_sockets_spec.as_ref(), rapl_polling)?;
-            profiler.add_source(rapl_powercap);
+            let rapl_powercap = powercap::Rapl::new(rapl_path, rapl_sockets_spec.as_ref())?;
+            add_rapl(profiler, rapl_powercap, rapl_polling)?;
         }
     }
 
@@ -50,7 +178,7 @@ async fn main() -> Result<()> {
         match Nvml::new() {
             Ok(nvml) => {
                 trace!("Using NVML for Nvidia GPU profiling");
-                profiler.add_source(nvml);
+                profiler.add_source(BlockingAdapter::new(nvml));
             }
             Err(err) => warn!("{err}"),
         }
@@ -59,24 +187,13 @@ async fn main() -> Result<()> {
     if cli.perf {
         trace!("Initializing perf_event source");
         let perf_event = PerfEvent::new()?;
-        profiler.add_source(perf_event);
+        profiler.add_source(BlockingAdapter::new(perf_event));
     }
 
-    let config = Config::from(cli);
-
-    match config.command {
-        Command::Profile(profile_config) => {
-            let results = profiler.profile(&profile_config).await?;
-            displayer.display_results(
-                &profile_config.cmd,
-                &profile_config.token_pattern,
-                &results,
-            )?;
-        }
-        Command::ListSensors => {
-            let sensors = profiler.list_sensors()?;
-            displayer.list_sensors(&sensors)?;
-        }
+    if attribute {
+        trace!("Initializing proc source for energy attribution");
+        let cpu_time = CpuTime::new()?;
+        profiler.add_source(BlockingAdapter::new(cpu_time));
     }
 
     Ok(())
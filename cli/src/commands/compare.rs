@@ -0,0 +1,57 @@
+use clap::{Parser, ValueEnum};
+use joule_profiler_core::config::OutlierMethod;
+
+/// Arguments for comparison mode.
+#[derive(Parser, Debug)]
+pub struct CompareArgs {
+    /// JSON results files to compare, the first one being the baseline.
+    ///
+    /// Phases are matched by name and metrics by source and name. Phases repeated
+    /// in a file are considered as iterations, used to test the significance of the differences.
+    /// A quoted glob pattern (e.g. 'runs/baseline-*.json') pools the iterations of separate runs.
+    #[arg(value_name = "FILES", num_args = 2.., required = true)]
+    pub results_files: Vec<String>,
+
+    /// Significance level of the Welch's t-tests, strictly between 0 and 1.
+    #[arg(long = "alpha", default_value_t = 0.05)]
+    pub significance_level: f64,
+
+    /// Number of first iterations of each phase to drop, e.g. cold-cache runs.
+    #[arg(long = "warmup", value_name = "N", default_value_t = 0)]
+    pub warmup: usize,
+
+    /// Statistical filter excluding the outlier iterations of each phase.
+    #[arg(long = "outliers", value_enum, value_name = "METHOD")]
+    pub outliers: Option<Outliers>,
+
+    /// Keep only the K iterations of each phase with the lowest filter metric.
+    #[arg(long = "keep-best", value_name = "K")]
+    pub keep_best: Option<usize>,
+
+    /// Metric deciding which iterations are outliers.
+    #[arg(
+        long = "outlier-metric",
+        value_name = "METRIC",
+        default_value = "TOTAL"
+    )]
+    pub outlier_metric: String,
+}
+
+/// Statistical filters of the outlier iterations.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Outliers {
+    /// Excludes the iterations outside of the interquartile range fences (1.5 IQR).
+    Iqr,
+
+    /// Excludes the iterations whose modified z-score, based on the median absolute deviation, exceeds 3.5.
+    Mad,
+}
+
+impl From<Outliers> for OutlierMethod {
+    fn from(outliers: Outliers) -> Self {
+        match outliers {
+            Outliers::Iqr => OutlierMethod::Iqr,
+            Outliers::Mad => OutlierMethod::Mad,
+        }
+    }
+}
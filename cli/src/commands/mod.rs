@@ -1,7 +1,12 @@
-use crate::commands::profile::ProfileArgs;
+use crate::commands::{
+    compare::CompareArgs, profile::ProfileArgs, replay::ReplayArgs, report::ReportArgs,
+};
 use clap::Subcommand;
 
+pub mod compare;
 pub mod profile;
+pub mod replay;
+pub mod report;
 
 /// Subcommands of joule-profiler.
 #[derive(Subcommand, Debug)]
@@ -11,4 +16,16 @@ pub enum ProfilerCommand {
 
     /// List available sensors.
     ListSensors,
+
+    /// Replay a recorded profiling run, without measuring the hardware.
+    Replay(ReplayArgs),
+
+    /// Compare saved JSON results against a baseline.
+    Compare(CompareArgs),
+
+    /// Render saved JSON results with the selected outputs, without profiling again.
+    Report(ReportArgs),
+
+    /// Print the JSON Schema of the JSON results files.
+    Schema,
 }
@@ -27,10 +27,37 @@ pub struct ProfileArgs {
     pub cmd: Vec<String>,
 
     /// Rapl polling frequency in second.
+    ///
+    /// Periodically measures RAPL counters to prevent them from wrapping during long phases.
     #[arg(long = "rapl-polling")]
     pub rapl_polling: Option<f64>,
 
+    /// Number of buckets of the power histograms of the polled energy sensors.
+    ///
+    /// Polling gives power samples, whose percentiles, min, max and standard deviation are
+    /// reported for each phase. This option adds a histogram of the samples.
+    #[arg(
+        long = "power-histogram",
+        value_name = "BUCKETS",
+        requires = "rapl_polling"
+    )]
+    pub power_histogram: Option<usize>,
+
+    /// Attribute to the profiled command its CPU time share of the package and core energy.
+    ///
+    /// Samples the CPU time of the command and its descendants, and the busy CPU time of the machine,
+    /// from procfs. The attributed energies are reported next to the raw ones.
+    #[arg(long = "attribute")]
+    pub attribute: bool,
+
     /// Executes the profiled command with root privileges if true and Joule Profiler is launched as root.
     #[arg(long = "use-root")]
     pub use_root: bool,
+
+    /// Record the metrics converted by the sources into this file, to regenerate the results with the `replay` command.
+    ///
+    /// Only the converted metrics are recorded, not the raw readings of the sources: a replay gives back the
+    /// conversions of the recorded run.
+    #[arg(long = "record-metrics", value_name = "FILE")]
+    pub metrics_record_file: Option<String>,
 }
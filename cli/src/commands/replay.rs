@@ -0,0 +1,9 @@
+use clap::Parser;
+
+/// Arguments for replay mode.
+#[derive(Parser, Debug)]
+pub struct ReplayArgs {
+    /// Recording file produced by `profile --record-metrics`.
+    #[arg(value_name = "FILE")]
+    pub recording_file: String,
+}
@@ -0,0 +1,39 @@
+use clap::Parser;
+use joule_profiler_core::JouleProfilerError;
+use joule_profiler_core::unit::MetricUnit;
+
+/// Arguments for report mode.
+#[derive(Parser, Debug)]
+pub struct ReportArgs {
+    /// JSON results file written by the JSON output.
+    #[arg(value_name = "FILE")]
+    pub results_file: String,
+
+    /// Render only the phases matching a name (e.g. `__A__ -> __B__`) or a start token.
+    ///
+    /// Can be repeated, all the phases are rendered by default.
+    #[arg(long = "phase", value_name = "NAME")]
+    pub phases: Vec<String>,
+
+    /// Render only the metrics and power of a sensor, by name (e.g. `PACKAGE-0`) or as
+    /// `SOURCE/NAME`.
+    ///
+    /// Can be repeated, all the sensors are rendered by default.
+    #[arg(long = "sensor", value_name = "NAME")]
+    pub sensors: Vec<String>,
+
+    /// Convert the metrics of the same base unit into UNIT (e.g. `J`, `mW`, `kB`).
+    ///
+    /// Can be repeated for different base units.
+    #[arg(long = "unit", value_name = "UNIT", value_parser = parse_unit)]
+    pub units: Vec<MetricUnit>,
+
+    /// Output file of the format flag, as `--output-file` before the subcommand.
+    #[arg(short = 'o', long = "output-file", conflicts_with = "outputs")]
+    pub output_file: Option<String>,
+}
+
+/// Parses a metric unit with an optional prefix.
+fn parse_unit(s: &str) -> Result<MetricUnit, JouleProfilerError> {
+    MetricUnit::try_from(s)
+}
@@ -0,0 +1,706 @@
+//! Comparison of saved profiling results.
+//!
+//! Results files written by the JSON output are loaded and compared against the first one,
+//! the baseline. Phases are matched by name and metrics by source and name.
+//!
+//! Phases sharing the same name in a file (e.g. a phase token repeated in a loop) are considered
+//! as iterations of the same phase. A side of the comparison may also be a glob pattern, the
+//! iterations of the matched files, separate runs of the same command, being pooled in the
+//! order of the runs timestamps. When both sides have at least two iterations of a phase,
+//! a Welch's t-test tells whether the difference between the means is significant.
+//!
+//! Warm-up and outlier iterations can be excluded beforehand with an [`OutlierFilter`], deciding
+//! on the value of a single metric so that whole iterations are excluded. Excluded iterations are
+//! reported with their file and reason.
+
+use std::fmt::Display;
+use std::path::Path;
+
+use joule_profiler_core::config::OutlierFilter;
+use joule_profiler_core::types::{Phase, SavedResults};
+use joule_profiler_core::unit::MetricUnit;
+use log::{debug, warn};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::compare::outliers::{ExclusionReason, exclude_iterations};
+use crate::compare::stats::{WelchTest, mean, welch_t_test};
+
+pub mod outliers;
+pub mod stats;
+
+/// Errors of the results comparison.
+#[derive(Debug, Error)]
+pub enum CompareError {
+    /// Less than two results files were given.
+    #[error("At least two results files are needed for a comparison")]
+    NotEnoughFiles,
+
+    /// A results file is not a JSON file produced by the JSON output.
+    #[error("Invalid results file {0}: {1}")]
+    InvalidResultsFile(String, String),
+
+    /// A glob pattern of results files is malformed.
+    #[error("Invalid results files pattern {0}: {1}")]
+    InvalidPattern(String, String),
+
+    /// No results file matches a path or pattern.
+    #[error("No results file matches {0}")]
+    NoMatchingFile(String),
+
+    /// The significance level is not a probability strictly between 0 and 1.
+    #[error("The significance level must be between 0 and 1 exclusive, got {0}")]
+    InvalidSignificanceLevel(f64),
+}
+
+type Result<T> = std::result::Result<T, CompareError>;
+
+/// Loads results saved by the JSON output.
+fn load(path: &str) -> Result<SavedResults> {
+    debug!("Loading results from {path}");
+    SavedResults::from_file(path)
+        .map_err(|err| CompareError::InvalidResultsFile(path.to_string(), err.to_string()))
+}
+
+/// Loads the pooled results files of a side of the comparison, sorted by the timestamps of their
+/// runs so that the warm-up iterations are the first ones run.
+fn load_all(paths: &[String]) -> Result<Vec<(&str, SavedResults)>> {
+    let mut results = paths
+        .iter()
+        .map(|path| Ok((path.as_str(), load(path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    results.sort_by_key(|(_, results)| results.timestamp);
+    Ok(results)
+}
+
+/// Lists the files of loaded results, in their pooling order.
+fn files(results: &[(&str, SavedResults)]) -> Vec<String> {
+    results
+        .iter()
+        .map(|(file, _)| (*file).to_string())
+        .collect()
+}
+
+/// Lists the results files matched by a path or a glob pattern.
+fn resolve(pattern: &str) -> Result<Vec<String>> {
+    if Path::new(pattern).exists() {
+        return Ok(vec![pattern.to_string()]);
+    }
+
+    let files = glob::glob(pattern)
+        .map_err(|err| CompareError::InvalidPattern(pattern.to_string(), err.to_string()))?
+        .map(|entry| {
+            entry.map(|path| path.display().to_string()).map_err(|err| {
+                CompareError::InvalidResultsFile(
+                    err.path().display().to_string(),
+                    err.error().to_string(),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if files.is_empty() {
+        return Err(CompareError::NoMatchingFile(pattern.to_string()));
+    }
+    debug!("Pooling {} results file(s) matching {pattern}", files.len());
+    Ok(files)
+}
+
+/// Outcome of the comparison of a metric.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    /// The candidate is significantly lower than the baseline.
+    Lower,
+
+    /// The candidate is significantly higher than the baseline.
+    Higher,
+
+    /// The difference is not significant.
+    NoSignificantDifference,
+
+    /// Not enough iterations to run a significance test.
+    NotTested,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Verdict::Lower => "lower",
+            Verdict::Higher => "higher",
+            Verdict::NoSignificantDifference => "no significant difference",
+            Verdict::NotTested => "not tested",
+        })
+    }
+}
+
+/// Comparison of a metric between the baseline and a candidate.
+#[derive(Debug, Serialize)]
+pub struct MetricComparison {
+    /// The metric source.
+    pub source: String,
+
+    /// The metric name.
+    pub name: String,
+
+    /// The metric unit.
+    pub unit: MetricUnit,
+
+    /// Mean value of the baseline iterations.
+    pub baseline: f64,
+
+    /// Mean value of the candidate iterations.
+    pub candidate: f64,
+
+    /// Difference between the candidate and the baseline.
+    pub absolute_difference: f64,
+
+    /// Difference relative to the baseline, `None` if the baseline is zero.
+    pub relative_difference: Option<f64>,
+
+    /// Number of iterations of the baseline.
+    pub baseline_iterations: usize,
+
+    /// Number of iterations of the candidate.
+    pub candidate_iterations: usize,
+
+    /// Significance test, if both sides have at least two iterations.
+    pub test: Option<WelchTest>,
+
+    /// Outcome of the comparison.
+    pub verdict: Verdict,
+}
+
+/// An iteration of a phase excluded from the comparison.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ExcludedRun {
+    /// The results file of the iteration.
+    pub file: String,
+
+    /// Index of the iteration among the iterations of the phase in its file, starting at 0.
+    pub iteration: usize,
+
+    /// Why the iteration is excluded.
+    #[serde(flatten)]
+    pub reason: ExclusionReason,
+}
+
+/// Comparison of a phase between the baseline and a candidate.
+#[derive(Debug, Serialize)]
+pub struct PhaseComparison {
+    /// The phase name.
+    pub name: String,
+
+    /// The metrics present in both results.
+    pub metrics: Vec<MetricComparison>,
+
+    /// Iterations of the baseline excluded by the outlier filter.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub baseline_excluded: Vec<ExcludedRun>,
+
+    /// Iterations of the candidate excluded by the outlier filter.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidate_excluded: Vec<ExcludedRun>,
+}
+
+/// Comparison of a candidate with the baseline.
+#[derive(Debug, Serialize)]
+pub struct CandidateComparison {
+    /// The candidate results file, or the pattern of its pooled files.
+    pub file: String,
+
+    /// The candidate results files pooled together, in the order of their runs.
+    pub files: Vec<String>,
+
+    /// The phases present in both results.
+    pub phases: Vec<PhaseComparison>,
+}
+
+/// Comparison of results files against a baseline.
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    /// The baseline results file, or the pattern of its pooled files.
+    pub baseline: String,
+
+    /// The baseline results files pooled together, in the order of their runs.
+    pub baseline_files: Vec<String>,
+
+    /// Significance level of the tests.
+    pub significance_level: f64,
+
+    /// Handling of the warm-up and outlier iterations.
+    pub outlier_filter: OutlierFilter,
+
+    /// The comparison of each candidate with the baseline.
+    pub candidates: Vec<CandidateComparison>,
+}
+
+/// Compares results files, the first one being the baseline.
+///
+/// Each of `results_files` is a path or a glob pattern, the files matched by a pattern being
+/// pooled together.
+pub fn compare(
+    results_files: &[String],
+    significance_level: f64,
+    outlier_filter: &OutlierFilter,
+) -> Result<Comparison> {
+    if !(significance_level > 0.0 && significance_level < 1.0) {
+        return Err(CompareError::InvalidSignificanceLevel(significance_level));
+    }
+    let [baseline_pattern, candidate_patterns @ ..] = results_files else {
+        return Err(CompareError::NotEnoughFiles);
+    };
+    if candidate_patterns.is_empty() {
+        return Err(CompareError::NotEnoughFiles);
+    }
+
+    let baseline_files = resolve(baseline_pattern)?;
+    let baseline_results = load_all(&baseline_files)?;
+    let baseline = PhaseSamples::from_results(&baseline_results, outlier_filter);
+    let candidates = candidate_patterns
+        .iter()
+        .map(|pattern| {
+            let candidate_files = resolve(pattern)?;
+            let candidate_results = load_all(&candidate_files)?;
+            let candidate = PhaseSamples::from_results(&candidate_results, outlier_filter);
+            Ok(CandidateComparison {
+                file: pattern.clone(),
+                files: files(&candidate_results),
+                phases: compare_phases(&baseline, &candidate, significance_level),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Comparison {
+        baseline: baseline_pattern.clone(),
+        baseline_files: files(&baseline_results),
+        significance_level,
+        outlier_filter: outlier_filter.clone(),
+        candidates,
+    })
+}
+
+/// Values of a metric across the iterations of a phase.
+#[derive(Debug)]
+struct MetricSamples {
+    source: String,
+    name: String,
+    unit: MetricUnit,
+    values: Vec<f64>,
+}
+
+/// An iteration of a phase in a results file.
+#[derive(Debug, Clone, Copy)]
+struct Iteration<'a> {
+    file: &'a str,
+
+    /// Index of the iteration among the iterations of the phase in its file.
+    index: usize,
+
+    phase: &'a Phase,
+}
+
+/// Metrics samples of the kept iterations of a phase.
+#[derive(Debug)]
+struct PhaseIterations {
+    name: String,
+    samples: Vec<MetricSamples>,
+    excluded: Vec<ExcludedRun>,
+}
+
+/// Metrics samples of each phase, in order of appearance.
+#[derive(Debug)]
+struct PhaseSamples(Vec<PhaseIterations>);
+
+impl PhaseSamples {
+    /// Groups the metrics values of the phases sharing the same name in the pooled results,
+    /// excluding the iterations filtered out by `filter`.
+    fn from_results(results: &[(&str, SavedResults)], filter: &OutlierFilter) -> Self {
+        let mut phases: Vec<(String, Vec<Iteration>)> = Vec::new();
+        for (file, results) in results {
+            for phase in &results.phases {
+                let name = phase.get_name();
+                let position = phases
+                    .iter()
+                    .position(|(phase_name, _)| *phase_name == name)
+                    .unwrap_or_else(|| {
+                        phases.push((name, Vec::new()));
+                        phases.len() - 1
+                    });
+                let iterations = &mut phases[position].1;
+                let index = iterations.iter().filter(|it| it.file == *file).count();
+                iterations.push(Iteration { file, index, phase });
+            }
+        }
+
+        Self(
+            phases
+                .into_iter()
+                .map(|(name, iterations)| Self::filter_iterations(name, &iterations, filter))
+                .collect(),
+        )
+    }
+
+    /// Collects the samples of the iterations of a phase kept by the filter.
+    fn filter_iterations(
+        name: String,
+        iterations: &[Iteration],
+        filter: &OutlierFilter,
+    ) -> PhaseIterations {
+        let values: Vec<Option<f64>> = iterations
+            .iter()
+            .map(|iteration| {
+                iteration
+                    .phase
+                    .metrics
+                    .iter()
+                    .find(|metric| metric.name == filter.metric)
+                    .map(|metric| metric.value.as_f64())
+            })
+            .collect();
+        if !filter.is_disabled() && values.iter().all(Option::is_none) {
+            warn!(
+                "Phase {name} has no {} metric, only the warm-up iterations are excluded",
+                filter.metric
+            );
+        }
+        let excluded = exclude_iterations(filter, &values);
+
+        let mut samples: Vec<MetricSamples> = Vec::new();
+        let kept = iterations
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !excluded.iter().any(|e| e.iteration == *i));
+        for (_, iteration) in kept {
+            for metric in &iteration.phase.metrics {
+                let value = metric.value.as_f64();
+                if let Some(metric_samples) = samples
+                    .iter_mut()
+                    .find(|s| s.source == metric.source && s.name == metric.name)
+                {
+                    metric_samples.values.push(value);
+                } else {
+                    samples.push(MetricSamples {
+                        source: metric.source.clone(),
+                        name: metric.name.clone(),
+                        unit: metric.unit,
+                        values: vec![value],
+                    });
+                }
+            }
+        }
+
+        let excluded = excluded
+            .into_iter()
+            .map(|e| ExcludedRun {
+                file: iterations[e.iteration].file.to_string(),
+                iteration: iterations[e.iteration].index,
+                reason: e.reason,
+            })
+            .collect();
+
+        PhaseIterations {
+            name,
+            samples,
+            excluded,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&PhaseIterations> {
+        self.0.iter().find(|phase| phase.name == name)
+    }
+}
+
+/// Compares the phases present in both the baseline and the candidate.
+fn compare_phases(
+    baseline: &PhaseSamples,
+    candidate: &PhaseSamples,
+    significance_level: f64,
+) -> Vec<PhaseComparison> {
+    baseline
+        .0
+        .iter()
+        .filter_map(|baseline_phase| {
+            let name = &baseline_phase.name;
+            let Some(candidate_phase) = candidate.get(name) else {
+                warn!("Phase {name} is missing from the candidate results");
+                return None;
+            };
+
+            let metrics = baseline_phase
+                .samples
+                .iter()
+                .filter_map(|baseline| {
+                    let candidate = candidate_phase
+                        .samples
+                        .iter()
+                        .find(|c| c.source == baseline.source && c.name == baseline.name)?;
+                    if candidate.unit != baseline.unit {
+                        warn!(
+                            "Metric {}/{} has different units ({} and {}), skipping it",
+                            baseline.source, baseline.name, baseline.unit, candidate.unit
+                        );
+                        return None;
+                    }
+                    Some(compare_metric(baseline, candidate, significance_level))
+                })
+                .collect();
+
+            Some(PhaseComparison {
+                name: name.clone(),
+                metrics,
+                baseline_excluded: baseline_phase.excluded.clone(),
+                candidate_excluded: candidate_phase.excluded.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Compares the samples of a metric.
+fn compare_metric(
+    baseline: &MetricSamples,
+    candidate: &MetricSamples,
+    significance_level: f64,
+) -> MetricComparison {
+    let baseline_mean = mean(&baseline.values);
+    let candidate_mean = mean(&candidate.values);
+    let absolute_difference = candidate_mean - baseline_mean;
+    let relative_difference =
+        (baseline_mean != 0.0).then(|| absolute_difference / baseline_mean.abs());
+
+    let test = welch_t_test(&baseline.values, &candidate.values);
+    let verdict = match test {
+        None => Verdict::NotTested,
+        Some(test) if test.p_value >= significance_level => Verdict::NoSignificantDifference,
+        Some(_) if absolute_difference < 0.0 => Verdict::Lower,
+        Some(_) => Verdict::Higher,
+    };
+
+    MetricComparison {
+        source: baseline.source.clone(),
+        name: baseline.name.clone(),
+        unit: baseline.unit,
+        baseline: baseline_mean,
+        candidate: candidate_mean,
+        absolute_difference,
+        relative_difference,
+        baseline_iterations: baseline.values.len(),
+        candidate_iterations: candidate.values.len(),
+        test,
+        verdict,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::{Metric, Phase, PhaseToken, Phases};
+    use joule_profiler_core::unit::{Unit, UnitPrefix};
+
+    const UNIT: MetricUnit = MetricUnit {
+        prefix: UnitPrefix::Micro,
+        unit: Unit::Joule,
+    };
+
+    fn phase(start: &str, end: &str, values: &[(&str, u64)]) -> Phase {
+        Phase {
+            index: 0,
+            start_token: PhaseToken::Token(start.into()),
+            end_token: PhaseToken::Token(end.into()),
+            timestamp: 0,
+            duration_ms: 0,
+            start_token_line: None,
+            end_token_line: None,
+            metrics: values
+                .iter()
+                .map(|(name, value)| Metric::new(*name, *value, UNIT, "rapl"))
+                .collect(),
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn samples(phases: Phases) -> PhaseSamples {
+        filtered_samples(phases, &OutlierFilter::default())
+    }
+
+    fn results(phases: Phases) -> SavedResults {
+        SavedResults {
+            schema_version: 1,
+            command: String::new(),
+            token_pattern: String::new(),
+            timestamp: 0,
+            duration_ms: 0,
+            exit_code: 0,
+            phases,
+            budgets: None,
+        }
+    }
+
+    fn filtered_samples(phases: Phases, filter: &OutlierFilter) -> PhaseSamples {
+        PhaseSamples::from_results(&[("results.json", results(phases))], filter)
+    }
+
+    #[test]
+    fn single_runs_report_differences_without_test() {
+        let baseline = samples(vec![phase("A", "B", &[("PACKAGE-0", 100)])]);
+        let candidate = samples(vec![phase("A", "B", &[("PACKAGE-0", 80)])]);
+
+        let phases = compare_phases(&baseline, &candidate, 0.05);
+        let metric = &phases[0].metrics[0];
+        assert_eq!(phases[0].name, "A -> B");
+        assert!((metric.absolute_difference + 20.0).abs() < f64::EPSILON);
+        assert!((metric.relative_difference.unwrap() + 0.2).abs() < f64::EPSILON);
+        assert_eq!(metric.verdict, Verdict::NotTested);
+    }
+
+    #[test]
+    fn repeated_phases_are_tested() {
+        let baseline = samples(vec![
+            phase("I", "I", &[("PACKAGE-0", 100)]),
+            phase("I", "I", &[("PACKAGE-0", 102)]),
+            phase("I", "I", &[("PACKAGE-0", 98)]),
+        ]);
+        let candidate = samples(vec![
+            phase("I", "I", &[("PACKAGE-0", 50)]),
+            phase("I", "I", &[("PACKAGE-0", 51)]),
+            phase("I", "I", &[("PACKAGE-0", 49)]),
+        ]);
+
+        let phases = compare_phases(&baseline, &candidate, 0.05);
+        let metric = &phases[0].metrics[0];
+        assert_eq!(metric.baseline_iterations, 3);
+        assert!(metric.test.is_some());
+        assert_eq!(metric.verdict, Verdict::Lower);
+    }
+
+    #[test]
+    fn unmatched_phases_and_metrics_are_skipped() {
+        let baseline = samples(vec![
+            phase("A", "B", &[("PACKAGE-0", 100), ("DRAM-0", 10)]),
+            phase("B", "C", &[("PACKAGE-0", 100)]),
+        ]);
+        let candidate = samples(vec![phase("A", "B", &[("PACKAGE-0", 80)])]);
+
+        let phases = compare_phases(&baseline, &candidate, 0.05);
+        assert_eq!(phases.len(), 1);
+        assert_eq!(phases[0].metrics.len(), 1);
+    }
+
+    #[test]
+    fn outlier_iterations_are_excluded_and_reported() {
+        let filter = OutlierFilter {
+            warmup: 1,
+            keep_best: Some(2),
+            metric: "PACKAGE-0".into(),
+            ..Default::default()
+        };
+        let baseline = filtered_samples(
+            vec![
+                phase("I", "I", &[("PACKAGE-0", 500)]),
+                phase("I", "I", &[("PACKAGE-0", 100)]),
+                phase("I", "I", &[("PACKAGE-0", 300)]),
+                phase("I", "I", &[("PACKAGE-0", 102)]),
+            ],
+            &filter,
+        );
+        let candidate = filtered_samples(
+            vec![
+                phase("I", "I", &[("PACKAGE-0", 50)]),
+                phase("I", "I", &[("PACKAGE-0", 51)]),
+            ],
+            &filter,
+        );
+
+        let phases = compare_phases(&baseline, &candidate, 0.05);
+        let metric = &phases[0].metrics[0];
+        assert_eq!(metric.baseline_iterations, 2);
+        assert!((metric.baseline - 101.0).abs() < f64::EPSILON);
+        assert_eq!(
+            phases[0]
+                .baseline_excluded
+                .iter()
+                .map(|e| e.iteration)
+                .collect::<Vec<_>>(),
+            [0, 2]
+        );
+        assert_eq!(metric.candidate_iterations, 1);
+        assert_eq!(phases[0].candidate_excluded[0].iteration, 0);
+    }
+
+    #[test]
+    fn pooled_runs_are_trimmed_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for (run, value) in [(9, 500), (10, 100), (11, 102)] {
+            let path = dir.path().join(format!("baseline-run-{run}.json"));
+            let results = SavedResults {
+                timestamp: run,
+                ..results(vec![phase("I", "I", &[("PACKAGE-0", value)])])
+            };
+            std::fs::write(path, serde_json::to_string(&results).unwrap()).unwrap();
+        }
+        let candidate = dir.path().join("candidate.json");
+        let results = results(vec![
+            phase("I", "I", &[("PACKAGE-0", 50)]),
+            phase("I", "I", &[("PACKAGE-0", 52)]),
+        ]);
+        std::fs::write(&candidate, serde_json::to_string(&results).unwrap()).unwrap();
+
+        let filter = OutlierFilter {
+            warmup: 1,
+            metric: "PACKAGE-0".into(),
+            ..Default::default()
+        };
+        let pattern = dir.path().join("baseline-*.json").display().to_string();
+        let comparison =
+            compare(&[pattern, candidate.display().to_string()], 0.05, &filter).unwrap();
+
+        assert!(comparison.baseline_files[0].ends_with("baseline-run-9.json"));
+        assert!(comparison.baseline_files[2].ends_with("baseline-run-11.json"));
+        let phase = &comparison.candidates[0].phases[0];
+        assert_eq!(phase.metrics[0].baseline_iterations, 2);
+        assert!((phase.metrics[0].baseline - 101.0).abs() < f64::EPSILON);
+        assert_eq!(phase.baseline_excluded.len(), 1);
+        assert!(
+            phase.baseline_excluded[0]
+                .file
+                .ends_with("baseline-run-9.json")
+        );
+        assert_eq!(phase.candidate_excluded[0].iteration, 0);
+
+        let serialized = serde_json::to_value(&comparison).unwrap();
+        let excluded = &serialized["candidates"][0]["phases"][0]["baseline_excluded"][0];
+        assert_eq!(excluded["reason"], "warmup");
+        assert_eq!(excluded["iteration"], 0);
+        assert!(excluded["file"].is_string());
+    }
+
+    #[test]
+    fn unmatched_pattern_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let pattern = dir.path().join("*.json").display().to_string();
+        assert!(matches!(
+            compare(&[pattern.clone(), pattern], 0.05, &OutlierFilter::default()),
+            Err(CompareError::NoMatchingFile(_))
+        ));
+    }
+
+    #[test]
+    fn significance_level_must_be_a_probability() {
+        let files = ["a.json".to_string(), "b.json".to_string()];
+        for level in [0.0, 1.0, -0.1, 5.0, f64::NAN] {
+            assert!(matches!(
+                compare(&files, level, &OutlierFilter::default()),
+                Err(CompareError::InvalidSignificanceLevel(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn compare_needs_two_files() {
+        assert!(matches!(
+            compare(&["a.json".into()], 0.05, &OutlierFilter::default()),
+            Err(CompareError::NotEnoughFiles)
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+//! Exclusion of the warm-up and outlier iterations of a phase.
+//!
+//! Repeated runs of a benchmark suffer from cold caches during the first iterations and from
+//! interferences of other processes, skewing the means. Iterations are excluded according to an
+//! [`OutlierFilter`], each exclusion being recorded with its reason so that the filtering can
+//! be audited.
+
+use joule_profiler_core::config::{OutlierFilter, OutlierMethod};
+use serde::Serialize;
+use std::fmt::Display;
+
+use crate::compare::stats::{median, quantile};
+
+/// Multiplier of the interquartile range giving the Tukey fences.
+const IQR_FENCE: f64 = 1.5;
+
+/// Modified z-score above which an iteration is an outlier (Iglewicz and Hoaglin).
+const MAD_THRESHOLD: f64 = 3.5;
+
+/// Scale of the modified z-score, making the MAD consistent with the standard deviation.
+const MAD_SCALE: f64 = 0.6745;
+
+/// Reason of the exclusion of an iteration.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// The iteration is one of the first ones.
+    Warmup,
+
+    /// The value is outside of the interquartile range fences.
+    Iqr { value: f64, lower: f64, upper: f64 },
+
+    /// The modified z-score of the value exceeds the threshold.
+    Mad { value: f64, median: f64, score: f64 },
+
+    /// The value is not among the best ones.
+    NotBest { value: f64, rank: usize },
+}
+
+impl ExclusionReason {
+    /// Short name of the reason.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExclusionReason::Warmup => "warmup",
+            ExclusionReason::Iqr { .. } => "iqr",
+            ExclusionReason::Mad { .. } => "mad",
+            ExclusionReason::NotBest { .. } => "not_best",
+        }
+    }
+}
+
+impl Display for ExclusionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExclusionReason::Warmup => f.write_str("warm-up"),
+            ExclusionReason::Iqr {
+                value,
+                lower,
+                upper,
+            } => write!(f, "{value:.3} outside of [{lower:.3}, {upper:.3}]"),
+            ExclusionReason::Mad {
+                value,
+                median,
+                score,
+            } => write!(
+                f,
+                "{value:.3} is {score:+.2} MAD-scores away from {median:.3}"
+            ),
+            ExclusionReason::NotBest { value, rank } => {
+                write!(f, "{value:.3} ranked {} only", rank + 1)
+            }
+        }
+    }
+}
+
+/// An iteration excluded from the comparison.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct ExcludedIteration {
+    /// Index of the iteration among the iterations of the phase, starting at 0.
+    pub iteration: usize,
+
+    /// Why the iteration is excluded.
+    #[serde(flatten)]
+    pub reason: ExclusionReason,
+}
+
+/// Finds the iterations excluded by the filter, given the value of the filter metric of each
+/// iteration (`None` if the iteration lacks the metric).
+///
+/// Iterations without value are never excluded by the statistical filters.
+pub fn exclude_iterations(
+    filter: &OutlierFilter,
+    values: &[Option<f64>],
+) -> Vec<ExcludedIteration> {
+    let mut excluded: Vec<ExcludedIteration> = (0..filter.warmup.min(values.len()))
+        .map(|iteration| ExcludedIteration {
+            iteration,
+            reason: ExclusionReason::Warmup,
+        })
+        .collect();
+
+    let remaining = |excluded: &[ExcludedIteration]| -> Vec<(usize, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !excluded.iter().any(|e| e.iteration == *i))
+            .filter_map(|(i, value)| Some((i, (*value)?)))
+            .collect()
+    };
+
+    match filter.method {
+        Some(OutlierMethod::Iqr) => excluded.extend(iqr_outliers(&remaining(&excluded))),
+        Some(OutlierMethod::Mad) => excluded.extend(mad_outliers(&remaining(&excluded))),
+        None => {}
+    }
+
+    if let Some(keep_best) = filter.keep_best {
+        let mut ranked = remaining(&excluded);
+        ranked.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        excluded.extend(ranked.into_iter().enumerate().skip(keep_best).map(
+            |(rank, (iteration, value))| ExcludedIteration {
+                iteration,
+                reason: ExclusionReason::NotBest { value, rank },
+            },
+        ));
+    }
+
+    excluded.sort_by_key(|e| e.iteration);
+    excluded
+}
+
+/// Iterations outside of the Tukey fences.
+fn iqr_outliers(values: &[(usize, f64)]) -> Vec<ExcludedIteration> {
+    let mut sorted: Vec<f64> = values.iter().map(|(_, value)| *value).collect();
+    sorted.sort_by(f64::total_cmp);
+    let (Some(q1), Some(q3)) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75)) else {
+        return Vec::new();
+    };
+    let lower = q1 - IQR_FENCE * (q3 - q1);
+    let upper = q3 + IQR_FENCE * (q3 - q1);
+
+    values
+        .iter()
+        .filter(|(_, value)| *value < lower || *value > upper)
+        .map(|&(iteration, value)| ExcludedIteration {
+            iteration,
+            reason: ExclusionReason::Iqr {
+                value,
+                lower,
+                upper,
+            },
+        })
+        .collect()
+}
+
+/// Iterations whose modified z-score exceeds the threshold.
+///
+/// Nothing is excluded when more than half of the values are equal, the MAD being zero.
+fn mad_outliers(values: &[(usize, f64)]) -> Vec<ExcludedIteration> {
+    let samples: Vec<f64> = values.iter().map(|(_, value)| *value).collect();
+    let Some(median) = median(&samples) else {
+        return Vec::new();
+    };
+    let deviations: Vec<f64> = samples.iter().map(|value| (value - median).abs()).collect();
+    let Some(mad) = self::median(&deviations).filter(|mad| *mad > 0.0) else {
+        return Vec::new();
+    };
+
+    values
+        .iter()
+        .filter_map(|&(iteration, value)| {
+            let score = MAD_SCALE * (value - median) / mad;
+            (score.abs() > MAD_THRESHOLD).then_some(ExcludedIteration {
+                iteration,
+                reason: ExclusionReason::Mad {
+                    value,
+                    median,
+                    score,
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(
+        warmup: usize,
+        method: Option<OutlierMethod>,
+        keep_best: Option<usize>,
+    ) -> OutlierFilter {
+        OutlierFilter {
+            warmup,
+            method,
+            keep_best,
+            ..Default::default()
+        }
+    }
+
+    fn iterations(excluded: &[ExcludedIteration]) -> Vec<usize> {
+        excluded.iter().map(|e| e.iteration).collect()
+    }
+
+    fn values(values: &[f64]) -> Vec<Option<f64>> {
+        values.iter().copied().map(Some).collect()
+    }
+
+    #[test]
+    fn disabled_filter_keeps_everything() {
+        assert!(exclude_iterations(&OutlierFilter::default(), &values(&[1.0, 100.0])).is_empty());
+    }
+
+    #[test]
+    fn warmup_iterations_are_dropped() {
+        let excluded = exclude_iterations(&filter(2, None, None), &values(&[9.0, 5.0, 1.0]));
+        assert_eq!(iterations(&excluded), [0, 1]);
+        assert_eq!(excluded[0].reason, ExclusionReason::Warmup);
+    }
+
+    #[test]
+    fn iqr_excludes_spikes_after_warmup() {
+        let excluded = exclude_iterations(
+            &filter(1, Some(OutlierMethod::Iqr), None),
+            &values(&[50.0, 10.0, 11.0, 10.5, 9.5, 10.2, 30.0]),
+        );
+        assert_eq!(iterations(&excluded), [0, 6]);
+        assert!(
+            matches!(excluded[1].reason, ExclusionReason::Iqr { value, .. } if (value - 30.0).abs() < f64::EPSILON)
+        );
+    }
+
+    #[test]
+    fn mad_excludes_spikes() {
+        let excluded = exclude_iterations(
+            &filter(0, Some(OutlierMethod::Mad), None),
+            &values(&[10.0, 11.0, 10.5, 9.5, 10.2, 30.0]),
+        );
+        assert_eq!(iterations(&excluded), [5]);
+    }
+
+    #[test]
+    fn mad_without_deviation_keeps_everything() {
+        let excluded = exclude_iterations(
+            &filter(0, Some(OutlierMethod::Mad), None),
+            &values(&[10.0, 10.0, 10.0, 12.0]),
+        );
+        assert!(excluded.is_empty());
+    }
+
+    #[test]
+    fn keep_best_keeps_the_lowest_values() {
+        let excluded =
+            exclude_iterations(&filter(0, None, Some(2)), &values(&[3.0, 1.0, 4.0, 2.0]));
+        assert_eq!(iterations(&excluded), [0, 2]);
+        assert_eq!(
+            excluded[1].reason,
+            ExclusionReason::NotBest {
+                value: 4.0,
+                rank: 3
+            }
+        );
+    }
+
+    #[test]
+    fn iterations_without_value_are_kept() {
+        let excluded = exclude_iterations(&filter(0, None, Some(1)), &[Some(2.0), None, Some(1.0)]);
+        assert_eq!(iterations(&excluded), [0]);
+    }
+}
@@ -0,0 +1,248 @@
+//! Statistics used to compare samples of metric values.
+
+use serde::Serialize;
+
+/// Maximum number of iterations of the incomplete beta continued fraction.
+const MAX_ITERATIONS: usize = 200;
+
+/// Convergence threshold of the incomplete beta continued fraction.
+const EPSILON: f64 = 1e-12;
+
+/// Result of a two-sided Welch's t-test.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct WelchTest {
+    /// The t statistic, positive when the second sample mean is greater.
+    pub t: f64,
+
+    /// Welch–Satterthwaite degrees of freedom.
+    pub degrees_of_freedom: f64,
+
+    /// Two-sided p-value.
+    pub p_value: f64,
+}
+
+/// Arithmetic mean of the samples.
+#[allow(clippy::cast_precision_loss)]
+pub fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// Quantile of sorted samples, linearly interpolated between the closest ranks.
+///
+/// Returns `None` if there is no sample.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = q.clamp(0.0, 1.0) * last as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - rank.floor()))
+}
+
+/// Median of the samples, `None` if there is no sample.
+pub fn median(samples: &[f64]) -> Option<f64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    quantile(&sorted, 0.5)
+}
+
+/// Unbiased sample variance.
+#[allow(clippy::cast_precision_loss)]
+fn variance(samples: &[f64], mean: f64) -> f64 {
+    samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64
+}
+
+/// Runs a Welch's t-test between two samples.
+///
+/// Returns `None` if a sample has less than two values.
+#[allow(clippy::cast_precision_loss)]
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<WelchTest> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let se_a = variance(a, mean_a) / n_a;
+    let se_b = variance(b, mean_b) / n_b;
+    let se = se_a + se_b;
+
+    if se == 0.0 {
+        let difference = mean_b - mean_a;
+        let identical = difference.abs() < f64::EPSILON;
+        return Some(WelchTest {
+            t: if identical {
+                0.0
+            } else {
+                difference.signum() * f64::INFINITY
+            },
+            degrees_of_freedom: n_a + n_b - 2.0,
+            p_value: if identical { 1.0 } else { 0.0 },
+        });
+    }
+
+    let t = (mean_b - mean_a) / se.sqrt();
+    let degrees_of_freedom = se.powi(2) / (se_a.powi(2) / (n_a - 1.0) + se_b.powi(2) / (n_b - 1.0));
+    let p_value = regularized_incomplete_beta(
+        degrees_of_freedom / 2.0,
+        0.5,
+        degrees_of_freedom / (degrees_of_freedom + t * t),
+    );
+
+    Some(WelchTest {
+        t,
+        degrees_of_freedom,
+        p_value: p_value.clamp(0.0, 1.0),
+    })
+}
+
+/// Natural logarithm of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.120_865_097_386_617_9e-2,
+        -0.539_523_938_495_3e-5,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000_000_000_190_015;
+    let mut y = x;
+    for coefficient in COEFFICIENTS {
+        y += 1.0;
+        series += coefficient / y;
+    }
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`.
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Continued fraction of the incomplete beta function, evaluated with the modified Lentz's method.
+#[allow(clippy::cast_precision_loss, clippy::many_single_char_names)]
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let tiny = f64::MIN_POSITIVE;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < tiny {
+        d = tiny;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+
+        let numerator = m * (b - m) * x / ((a + m2 - 1.0) * (a + m2));
+        d = 1.0 + numerator * d;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = 1.0 + numerator / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = 1.0 / d;
+        result *= d * c;
+
+        let numerator = -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0));
+        d = 1.0 + numerator * d;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = 1.0 + numerator / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        result *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn mean_of_samples() {
+        assert_close(mean(&[1.0, 2.0, 3.0, 4.0]), 2.5, 1e-12);
+    }
+
+    #[test]
+    fn quantiles_are_interpolated() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_close(quantile(&sorted, 0.25).unwrap(), 1.75, 1e-12);
+        assert_close(quantile(&sorted, 1.0).unwrap(), 4.0, 1e-12);
+        assert_close(median(&[3.0, 1.0, 2.0]).unwrap(), 2.0, 1e-12);
+        assert!(median(&[]).is_none());
+    }
+
+    #[test]
+    fn incomplete_beta_matches_known_values() {
+        assert_close(regularized_incomplete_beta(1.0, 1.0, 0.3), 0.3, 1e-9);
+        assert_close(regularized_incomplete_beta(2.0, 3.0, 0.4), 0.5248, 1e-9);
+    }
+
+    #[test]
+    fn welch_t_test_matches_reference() {
+        // Reference computed by numerical integration of the Student's t density.
+        let a = [27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6];
+        let b = [27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2];
+
+        let test = welch_t_test(&a, &b).unwrap();
+        assert_close(test.t, 2.035_662, 1e-5);
+        assert_close(test.degrees_of_freedom, 15.497_9, 1e-3);
+        assert_close(test.p_value, 0.059_254, 1e-5);
+    }
+
+    #[test]
+    fn welch_t_test_needs_two_samples() {
+        assert!(welch_t_test(&[1.0], &[1.0, 2.0]).is_none());
+    }
+
+    #[test]
+    fn welch_t_test_without_variance() {
+        let same = welch_t_test(&[1.0, 1.0], &[1.0, 1.0]).unwrap();
+        assert_close(same.p_value, 1.0, 1e-12);
+
+        let different = welch_t_test(&[1.0, 1.0], &[2.0, 2.0]).unwrap();
+        assert_close(different.p_value, 0.0, 1e-12);
+        assert!(different.t > 0.0);
+    }
+}
@@ -1,26 +1,50 @@
 use std::collections::HashSet;
+use std::time::Duration;
 
-use clap::{ArgAction, Parser, ValueEnum};
+use clap::{ArgAction, ArgGroup, ColorChoice, Parser, ValueEnum};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 pub use commands::ProfilerCommand;
-use joule_profiler_core::config::{Command, Config, ProfileConfig};
+use joule_profiler_core::budget::{Budget, budgets_from_file};
+use joule_profiler_core::config::{
+    Command, CompareConfig, Config, OutlierFilter, ProfileConfig, ReplayConfig, ReportConfig,
+    ReportFilter,
+};
+use joule_profiler_core::footprint::{Footprint, FootprintConfig};
+use joule_profiler_core::source::MetricReader;
+use log::warn;
 
 use crate::output::{
-    displayer::Displayer,
+    displayer::{Displayer, multi::MultiOutput},
     formats::{
-        OutputFormat, csv::CsvOutput, json::JsonOutput, output_format, terminal::TerminalOutput,
+        OutputFormat, OutputSpec,
+        csv::{CsvLayout, CsvOutput, DEFAULT_DELIMITER, parse_delimiter},
+        flamegraph::FlameGraphOutput,
+        html::HtmlOutput,
+        influx::{InfluxOutput, Precision},
+        json::JsonOutput,
+        jsonl::JsonLinesOutput,
+        openmetrics::OpenMetricsOutput,
+        output_specs,
+        terminal::{TerminalLayout, TerminalOutput},
+        trace::TraceOutput,
     },
 };
 
+/// Exit code of the profiler when a budget is exceeded.
+pub const BUDGET_EXCEEDED_EXIT_CODE: i32 = 3;
+
 mod commands;
+pub mod compare;
 mod logging;
 mod output;
+pub mod report;
 
 /// joule-profiler: measure program energy consumption
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Parser, Debug)]
 #[command(name = "joule-profiler")]
+#[command(groups = output_groups(), mut_subcommands = |command| command.groups(output_groups()))]
 #[command(
     version,
     about = "Measure program metrics from various sources like RAPL"
@@ -45,18 +69,123 @@ pub struct CliArgs {
     #[arg(short = 's', long = "sockets")]
     pub sockets: Option<String>,
 
+    /// Layout of the phases in the terminal, `table` giving one row per phase and one column per metric
+    #[arg(long = "terminal-layout", value_enum, default_value_t = TerminalLayout::Detailed, global = true)]
+    pub terminal_layout: TerminalLayout,
+
+    /// When to color the terminal output, `auto` coloring it on a terminal unless `NO_COLOR` is set
+    #[arg(long, value_name = "WHEN", value_enum, default_value_t = ColorChoice::Auto, global = true)]
+    pub color: ColorChoice,
+
     /// Export results as JSON instead of pretty terminal output
-    #[arg(long, conflicts_with = "csv")]
+    #[arg(long, conflicts_with_all = ["csv", "openmetrics", "influx", "html", "chrome_trace", "flamegraph", "jsonl"], global = true)]
     pub json: bool,
 
-    /// Export results as CSV (semicolon-separated values)
-    #[arg(long, conflicts_with = "json")]
+    /// Stream results as JSON Lines records, each phase being written as soon as it completes
+    #[arg(long, conflicts_with_all = ["json", "csv", "openmetrics", "influx", "html", "chrome_trace", "flamegraph"], global = true)]
+    pub jsonl: bool,
+
+    /// Export results as CSV (RFC 4180, semicolon-separated values by default)
+    #[arg(long, conflicts_with_all = ["json", "openmetrics", "influx", "html", "chrome_trace", "flamegraph", "jsonl"], global = true)]
     pub csv: bool,
 
-    /// Output file for CSV/JSON (else `data<TIMESTAMP>`.csv/json)
-    #[arg(short = 'o', long = "output-file")]
+    /// Field delimiter of the CSV output (e.g. `,` or `\t` for a tab)
+    #[arg(
+        long = "csv-delimiter",
+        value_name = "CHAR",
+        value_parser = parse_delimiter,
+        default_value_t = DEFAULT_DELIMITER,
+        requires = "csv_output",
+        global = true
+    )]
+    pub csv_delimiter: char,
+
+    /// Layout of the CSV results, `wide` writing one row per phase and one column per metric
+    #[arg(
+        long = "csv-layout",
+        value_enum,
+        default_value_t = CsvLayout::Long,
+        requires = "csv_output",
+        global = true
+    )]
+    pub csv_layout: CsvLayout,
+
+    /// Export results in the `OpenMetrics` text format (e.g. for the `node_exporter` textfile collector)
+    #[arg(long, conflicts_with_all = ["json", "csv", "influx", "html", "chrome_trace", "flamegraph", "jsonl"], global = true)]
+    pub openmetrics: bool,
+
+    /// Serve the `OpenMetrics` results on `http://ADDR/metrics` until interrupted (e.g. 0.0.0.0:9464).
+    ///
+    /// No file is written unless `--output-file` is given.
+    #[arg(
+        long = "metrics-listen",
+        value_name = "ADDR",
+        requires = "openmetrics_output",
+        global = true
+    )]
+    pub metrics_listen: Option<String>,
+
+    /// Export results as `InfluxDB` line protocol points, including the polled readings
+    #[arg(long, conflicts_with_all = ["json", "csv", "openmetrics", "html", "chrome_trace", "flamegraph", "jsonl"], global = true)]
+    pub influx: bool,
+
+    /// Precision of the `InfluxDB` line protocol timestamps
+    #[arg(
+        long = "influx-precision",
+        value_enum,
+        default_value_t = Precision::Ns,
+        requires = "influx_output",
+        global = true
+    )]
+    pub influx_precision: Precision,
+
+    /// Export results as a standalone HTML report with charts
+    #[arg(long, conflicts_with_all = ["json", "csv", "openmetrics", "influx", "chrome_trace", "flamegraph", "jsonl"], global = true)]
+    pub html: bool,
+
+    /// Export results as a Chrome Trace Event JSON timeline, opened by Perfetto and `chrome://tracing`
+    #[arg(
+        long = "chrome-trace",
+        conflicts_with_all = ["json", "csv", "openmetrics", "influx", "html", "flamegraph", "jsonl"],
+        global = true
+    )]
+    pub chrome_trace: bool,
+
+    /// Export results as folded stacks of the phases weighted by energy, for flame graphs
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "csv", "openmetrics", "influx", "html", "chrome_trace", "jsonl"],
+        global = true
+    )]
+    pub flamegraph: bool,
+
+    /// Metric weighting the flame graph stacks, `TOTAL` splitting phases by energy domain
+    #[arg(
+        long = "flamegraph-metric",
+        value_name = "METRIC",
+        default_value = "TOTAL",
+        requires = "flamegraph_output",
+        global = true
+    )]
+    pub flamegraph_metric: String,
+
+    /// Render the flame graph as an SVG instead of folded stacks
+    #[arg(long = "flamegraph-svg", requires = "flamegraph_output", global = true)]
+    pub flamegraph_svg: bool,
+
+    /// Output file for CSV/JSON/JSON Lines/OpenMetrics/InfluxDB/HTML/trace/flame graph
+    /// (else `data<TIMESTAMP>`.csv/json/jsonl/prom/lp/html/trace.json/folded/svg)
+    #[arg(short = 'o', long = "output-file", conflicts_with = "outputs")]
     pub output_file: Option<String>,
 
+    /// Additional output of the results, with its file path (repeatable, e.g. `csv:results.csv`).
+    ///
+    /// FORMAT is one of terminal, json, jsonl, csv, openmetrics, influx, html, chrome-trace and
+    /// flamegraph. Without a path, the default file of the format is written. The terminal output
+    /// is only kept if given as well, or if a format flag is not set.
+    #[arg(long = "output", value_name = "FORMAT[:PATH]", global = true)]
+    pub outputs: Vec<OutputSpec>,
+
     /// GPU support
     #[arg(long)]
     pub gpu: bool,
@@ -65,6 +194,48 @@ pub struct CliArgs {
     #[arg(long)]
     pub perf: bool,
 
+    /// JSON configuration file of the carbon and cost estimation.
+    ///
+    /// Accepts the `carbon_intensity`, `carbon_intensity_file`, `price_per_kwh` and `pue` keys,
+    /// overridden by the corresponding command line options.
+    #[arg(long = "footprint-config", value_name = "FILE")]
+    pub footprint_config: Option<String>,
+
+    /// Carbon intensity of the grid in gCO2e/kWh, to estimate the phases emissions.
+    #[arg(
+        long = "carbon-intensity",
+        value_name = "G_PER_KWH",
+        conflicts_with = "carbon_intensity_file"
+    )]
+    pub carbon_intensity: Option<f64>,
+
+    /// CSV time series of the grid carbon intensity, matched with the phases by timestamp.
+    ///
+    /// Each line is a Unix timestamp in seconds followed by the intensity in gCO2e/kWh
+    /// (e.g. `1735689600,56`), a header line is allowed.
+    #[arg(long = "carbon-intensity-file", value_name = "CSV")]
+    pub carbon_intensity_file: Option<String>,
+
+    /// Electricity price per kWh, to estimate the phases cost.
+    #[arg(long = "energy-price", value_name = "PRICE_PER_KWH")]
+    pub energy_price: Option<f64>,
+
+    /// Power usage effectiveness of the facility, scaling the measured energy (e.g. 1.4).
+    #[arg(long = "pue", value_name = "FACTOR")]
+    pub pue: Option<f64>,
+
+    /// Budget to check against the results, failing the run if exceeded (repeatable).
+    ///
+    /// Syntax: `[phase(NAME).]METRIC OP VALUE[UNIT]` with OP one of <, <=, > and >=,
+    /// e.g. `PACKAGE-0 < 50J` or `phase(__COMPUTE__).GPU-0 < 2kJ`. Without a phase,
+    /// the metric is summed over all the phases.
+    #[arg(long = "assert", value_name = "BUDGET", global = true)]
+    pub assert: Vec<String>,
+
+    /// JSON file of budgets to check, as `{"budgets": ["PACKAGE-0 < 50J", ...]}`.
+    #[arg(long = "budgets", value_name = "FILE", global = true)]
+    pub budgets_file: Option<String>,
+
     /// Choose RAPL backend between powercap or perf
     #[arg(long = "rapl-backend", value_enum, default_value_t = RaplBackend::Perf)]
     pub rapl_backend: RaplBackend,
@@ -74,10 +245,43 @@ pub struct CliArgs {
     pub command: ProfilerCommand,
 }
 
+/// Groups of the options of each output format, an output being selected either by its format flag
+/// or by `--output`.
+///
+/// The output options being global, the groups are defined for every subcommand as well.
+fn output_groups() -> [ArgGroup; 4] {
+    [
+        ArgGroup::new("csv_output")
+            .args(["csv", "outputs"])
+            .multiple(true),
+        ArgGroup::new("openmetrics_output")
+            .args(["openmetrics", "outputs"])
+            .multiple(true),
+        ArgGroup::new("influx_output")
+            .args(["influx", "outputs"])
+            .multiple(true),
+        ArgGroup::new("flamegraph_output")
+            .args(["flamegraph", "outputs"])
+            .multiple(true),
+    ]
+}
+
 impl CliArgs {
     pub fn from_args() -> Self {
         Self::parse()
     }
+
+    /// Output file of the format flag, given before the subcommand or after `report`.
+    ///
+    /// `-o` is not a global option, as it redirects the program output in profile mode.
+    pub fn output_file(&self) -> Option<&str> {
+        match &self.command {
+            ProfilerCommand::Report(report_args) if self.output_file.is_none() => {
+                report_args.output_file.as_deref()
+            }
+            _ => self.output_file.as_deref(),
+        }
+    }
 }
 
 impl From<CliArgs> for Config {
@@ -88,9 +292,36 @@ impl From<CliArgs> for Config {
                 cmd: profile_args.cmd,
                 token_pattern: profile_args.token_pattern,
                 use_root: profile_args.use_root,
+                metrics_record_file: profile_args.metrics_record_file,
             }),
 
             ProfilerCommand::ListSensors => Command::ListSensors,
+
+            ProfilerCommand::Replay(replay_args) => Command::Replay(ReplayConfig {
+                recording_file: replay_args.recording_file,
+            }),
+
+            ProfilerCommand::Compare(compare_args) => Command::Compare(CompareConfig {
+                results_files: compare_args.results_files,
+                significance_level: compare_args.significance_level,
+                outlier_filter: OutlierFilter {
+                    warmup: compare_args.warmup,
+                    method: compare_args.outliers.map(Into::into),
+                    keep_best: compare_args.keep_best,
+                    metric: compare_args.outlier_metric,
+                },
+            }),
+
+            ProfilerCommand::Report(report_args) => Command::Report(ReportConfig {
+                results_file: report_args.results_file,
+                filter: ReportFilter {
+                    phases: report_args.phases,
+                    sensors: report_args.sensors,
+                    units: report_args.units,
+                },
+            }),
+
+            ProfilerCommand::Schema => Command::Schema,
         };
 
         Config {
@@ -107,18 +338,83 @@ pub enum RaplBackend {
 }
 
 pub fn output_format_to_displayer(cli: &CliArgs) -> Result<Box<dyn Displayer>> {
-    let output_format = output_format(cli.json, cli.csv);
-    let output_file = cli.output_file.clone();
+    let mut specs = output_specs(cli);
+    // Serving the OpenMetrics results blocks until interrupted, the other outputs come first.
+    specs.sort_by_key(|spec| {
+        spec.format == OutputFormat::OpenMetrics && cli.metrics_listen.is_some()
+    });
 
-    let displayer = match output_format {
-        OutputFormat::Terminal => TerminalOutput.into(),
+    let mut displayers = specs
+        .into_iter()
+        .map(|spec| output_spec_to_displayer(cli, spec))
+        .collect::<Result<Vec<_>>>()?;
+
+    if displayers.len() == 1 {
+        Ok(displayers.remove(0))
+    } else {
+        Ok(MultiOutput::new(displayers).into())
+    }
+}
+
+/// Creates the displayer of an output.
+fn output_spec_to_displayer(cli: &CliArgs, spec: OutputSpec) -> Result<Box<dyn Displayer>> {
+    let output_file = spec.path;
+
+    let displayer = match spec.format {
+        OutputFormat::Terminal => TerminalOutput::new(cli.terminal_layout, cli.color).into(),
         OutputFormat::Json => JsonOutput::new(output_file)?.into(),
-        OutputFormat::Csv => CsvOutput::try_new(output_file)?.into(),
+        OutputFormat::JsonLines => JsonLinesOutput::try_new(output_file)?.into(),
+        OutputFormat::Csv => {
+            CsvOutput::try_new(output_file, cli.csv_delimiter, cli.csv_layout)?.into()
+        }
+        OutputFormat::OpenMetrics => {
+            OpenMetricsOutput::try_new(output_file, cli.metrics_listen.as_deref())?.into()
+        }
+        OutputFormat::Influx => InfluxOutput::try_new(output_file, cli.influx_precision)?.into(),
+        OutputFormat::Html => HtmlOutput::try_new(output_file)?.into(),
+        OutputFormat::ChromeTrace => TraceOutput::try_new(output_file)?.into(),
+        OutputFormat::FlameGraph => FlameGraphOutput::try_new(
+            output_file,
+            cli.flamegraph_metric.clone(),
+            cli.flamegraph_svg,
+        )?
+        .into(),
     };
 
     Ok(displayer)
 }
 
+/// Builds the carbon and cost estimation stage from the configuration file and the CLI options.
+///
+/// Returns `None` if no estimation is configured.
+pub fn footprint(cli: &CliArgs) -> Result<Option<Footprint>> {
+    let config = match &cli.footprint_config {
+        Some(path) => FootprintConfig::from_file(path)?,
+        None => FootprintConfig::default(),
+    };
+    let config = config.override_with(FootprintConfig {
+        carbon_intensity: cli.carbon_intensity,
+        carbon_intensity_file: cli.carbon_intensity_file.clone(),
+        price_per_kwh: cli.energy_price,
+        pue: cli.pue,
+    });
+
+    Ok(Footprint::new(&config)?)
+}
+
+/// Parses the budgets of the budgets file and the `--assert` options.
+pub fn budgets(cli: &CliArgs) -> Result<Vec<Budget>> {
+    let mut budgets = match &cli.budgets_file {
+        Some(path) => budgets_from_file(path)?,
+        None => Vec::new(),
+    };
+    for assertion in &cli.assert {
+        budgets.push(assertion.parse()?);
+    }
+
+    Ok(budgets)
+}
+
 pub fn init_logging(verbose: u8) {
     logging::init_logging(verbose);
 }
@@ -130,3 +426,102 @@ pub fn parse_sockets_spec(sockets_spec: Option<&str>) -> Option<HashSet<u32>> {
             .collect()
     })
 }
+
+/// Checks the requested polling rate (in seconds) of a source and returns its polling interval.
+///
+/// Fails if the rate is not a finite and positive number of seconds. Returns `None` with a warning
+/// if the source does not support polling, and warns if the rate is faster than its counters update.
+pub fn check_polling<R: MetricReader>(
+    reader: &R,
+    polling_rate_s: Option<f64>,
+) -> Result<Option<Duration>> {
+    let Some(polling_rate_s) = polling_rate_s else {
+        return Ok(None);
+    };
+    let interval = polling_interval(polling_rate_s)?;
+
+    let capabilities = reader.capabilities();
+    if !capabilities.supports_polling {
+        warn!(
+            "{} does not support polling, --rapl-polling is ignored",
+            R::get_name()
+        );
+        return Ok(None);
+    }
+    if capabilities.is_sampling_too_fast(interval) {
+        warn!(
+            "Polling {} every {interval:?} is faster than its counters update, measures may be inaccurate",
+            R::get_name()
+        );
+    }
+    Ok(Some(interval))
+}
+
+/// Converts a polling rate in seconds into an interval, rejecting the rates which are not finite
+/// and positive.
+fn polling_interval(polling_rate_s: f64) -> Result<Duration> {
+    match Duration::try_from_secs_f64(polling_rate_s) {
+        Ok(interval) if !interval.is_zero() => Ok(interval),
+        _ => bail!("Invalid polling rate {polling_rate_s}, expected a positive number of seconds"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> clap::error::Result<CliArgs> {
+        CliArgs::try_parse_from(std::iter::once("joule-profiler").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn command_is_consistent() {
+        CliArgs::command().debug_assert();
+    }
+
+    #[test]
+    fn output_options_are_accepted_after_the_subcommand() {
+        let cli = parse(&["report", "results.json", "--csv", "--csv-layout", "wide"]).unwrap();
+        assert!(cli.csv);
+        assert_eq!(cli.csv_layout, CsvLayout::Wide);
+        assert!(matches!(
+            cli.command,
+            ProfilerCommand::Report(ref args) if args.results_file == "results.json"
+        ));
+
+        let cli = parse(&["--csv", "report", "results.json", "-o", "results.csv"]).unwrap();
+        assert!(cli.csv);
+        assert_eq!(cli.output_file(), Some("results.csv"));
+
+        let cli = parse(&["report", "results.json", "--output", "html:report.html"]).unwrap();
+        assert_eq!(cli.outputs.len(), 1);
+    }
+
+    #[test]
+    fn output_options_require_their_format() {
+        assert!(parse(&["report", "results.json", "--csv-layout", "wide"]).is_err());
+        assert!(parse(&["report", "results.json", "--json", "--csv"]).is_err());
+    }
+
+    #[test]
+    fn polling_interval_accepts_positive_rates() {
+        assert_eq!(polling_interval(0.5).unwrap(), Duration::from_millis(500));
+        assert_eq!(polling_interval(2.0).unwrap(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn polling_interval_rejects_invalid_rates() {
+        for rate in [
+            0.0,
+            -0.0,
+            -1.0,
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            1e-12,
+        ] {
+            assert!(polling_interval(rate).is_err(), "{rate} should be rejected");
+        }
+    }
+}
@@ -7,11 +7,19 @@
 //! # Overview
 //!
 //! - [`Displayer`] — Trait defining methods for displaying phases and sensors.
+//! - [`MultiOutput`][`multi::MultiOutput`] — Fan-out of the results to several displayers.
 //! - [`Terminal`][`super::formats::terminal::TerminalOutput`], [`JsonOutput`][`super::formats::json::JsonOutput`], [`CsvOutput`][`super::formats::csv::CsvOutput`] — Standard output formats.
 
 pub mod error;
+pub mod multi;
 pub use error::DisplayerError;
-use joule_profiler_core::{sensor::Sensor, types::ProfilerResults};
+use joule_profiler_core::{
+    budget::BudgetReport,
+    sensor::Sensor,
+    types::{PhaseListener, ProfilerResults},
+};
+
+use crate::compare::Comparison;
 
 /// Result type for displayer operations.
 pub(crate) type Result<T> = std::result::Result<T, DisplayerError>;
@@ -31,13 +39,33 @@ pub trait Displayer {
     /// - `cmd` — Command and arguments that were profiled.
     /// - `token_pattern` — Regex used to detect phases in output.
     /// - `results` — Results containing phases to display.
+    /// - `budgets` — Outcome of the budgets checked against the results, if any.
     fn display_results(
         &mut self,
         cmd: &[String],
         token_pattern: &str,
         results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
     ) -> Result<()>;
 
+    /// Start streaming the results of a profiling, returning the listener of the completed phases.
+    ///
+    /// Default implementation returns `None`, the results being only displayed once complete.
+    /// Phases given to the listener are still part of the results given to
+    /// [`display_results`](Self::display_results).
+    ///
+    /// # Parameters
+    ///
+    /// - `_cmd` — Command and arguments being profiled.
+    /// - `_token_pattern` — Regex used to detect phases in output.
+    fn phase_listener(
+        &mut self,
+        _cmd: &[String],
+        _token_pattern: &str,
+    ) -> Result<Option<PhaseListener>> {
+        Ok(None)
+    }
+
     /// List available sensors.
     ///
     /// Default implementation returns [`DisplayerError::NotImplementedForFormat`].
@@ -48,6 +76,17 @@ pub trait Displayer {
     fn list_sensors(&mut self, _sensors: &[Sensor]) -> Result<()> {
         Err(DisplayerError::NotImplementedForFormat)
     }
+
+    /// Display the comparison of saved results.
+    ///
+    /// Default implementation returns [`DisplayerError::NotImplementedForFormat`].
+    ///
+    /// # Parameters
+    ///
+    /// - `_comparison` — Comparison of the results files against the baseline.
+    fn display_comparison(&mut self, _comparison: &Comparison) -> Result<()> {
+        Err(DisplayerError::NotImplementedForFormat)
+    }
 }
 
 impl<T: Displayer + 'static> From<T> for Box<dyn Displayer> {
@@ -0,0 +1,188 @@
+//! Fan-out of the results to several displayers.
+
+use joule_profiler_core::budget::BudgetReport;
+use joule_profiler_core::sensor::Sensor;
+use joule_profiler_core::types::{Phase, PhaseListener, ProfilerResults};
+
+use crate::compare::Comparison;
+use crate::output::displayer::{Displayer, DisplayerError, Result};
+
+/// Displayer forwarding everything to several displayers, in order.
+pub struct MultiOutput {
+    /// Displayers receiving the results.
+    displayers: Vec<Box<dyn Displayer>>,
+}
+
+impl MultiOutput {
+    /// Create a displayer forwarding to the given displayers.
+    pub fn new(displayers: Vec<Box<dyn Displayer>>) -> Self {
+        Self { displayers }
+    }
+
+    /// Calls `display` on every displayer, skipping the ones not supporting the operation.
+    ///
+    /// Returns [`DisplayerError::NotImplementedForFormat`] if no displayer supports it.
+    fn fan_out<F>(&mut self, mut display: F) -> Result<()>
+    where
+        F: FnMut(&mut dyn Displayer) -> Result<()>,
+    {
+        let mut handled = false;
+        for displayer in &mut self.displayers {
+            match display(displayer.as_mut()) {
+                Ok(()) => handled = true,
+                Err(DisplayerError::NotImplementedForFormat) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if handled {
+            Ok(())
+        } else {
+            Err(DisplayerError::NotImplementedForFormat)
+        }
+    }
+}
+
+impl Displayer for MultiOutput {
+    fn phase_listener(
+        &mut self,
+        cmd: &[String],
+        token_pattern: &str,
+    ) -> Result<Option<PhaseListener>> {
+        let mut listeners = Vec::new();
+        for displayer in &mut self.displayers {
+            listeners.extend(displayer.phase_listener(cmd, token_pattern)?);
+        }
+        if listeners.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(move |phase: &Phase| {
+            for listener in &mut listeners {
+                listener(phase);
+            }
+        })))
+    }
+
+    fn display_results(
+        &mut self,
+        cmd: &[String],
+        token_pattern: &str,
+        results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
+    ) -> Result<()> {
+        for displayer in &mut self.displayers {
+            displayer.display_results(cmd, token_pattern, results, budgets)?;
+        }
+        Ok(())
+    }
+
+    fn list_sensors(&mut self, sensors: &[Sensor]) -> Result<()> {
+        self.fan_out(|displayer| displayer.list_sensors(sensors))
+    }
+
+    fn display_comparison(&mut self, comparison: &Comparison) -> Result<()> {
+        self.fan_out(|displayer| displayer.display_comparison(comparison))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Displayer recording the calls it receives, optionally not supporting sensors listing.
+    struct Recorder {
+        calls: Arc<Mutex<Vec<&'static str>>>,
+        lists_sensors: bool,
+    }
+
+    impl Displayer for Recorder {
+        fn phase_listener(
+            &mut self,
+            _cmd: &[String],
+            _token_pattern: &str,
+        ) -> Result<Option<PhaseListener>> {
+            let calls = Arc::clone(&self.calls);
+            Ok(Some(Box::new(move |_: &Phase| {
+                calls.lock().unwrap().push("phase");
+            })))
+        }
+
+        fn display_results(
+            &mut self,
+            _cmd: &[String],
+            _token_pattern: &str,
+            _results: &ProfilerResults,
+            _budgets: Option<&BudgetReport>,
+        ) -> Result<()> {
+            self.calls.lock().unwrap().push("results");
+            Ok(())
+        }
+
+        fn list_sensors(&mut self, _sensors: &[Sensor]) -> Result<()> {
+            if !self.lists_sensors {
+                return Err(DisplayerError::NotImplementedForFormat);
+            }
+            self.calls.lock().unwrap().push("sensors");
+            Ok(())
+        }
+    }
+
+    fn multi(lists_sensors: &[bool]) -> (MultiOutput, Arc<Mutex<Vec<&'static str>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let displayers = lists_sensors
+            .iter()
+            .map(|&lists_sensors| {
+                Box::new(Recorder {
+                    calls: Arc::clone(&calls),
+                    lists_sensors,
+                }) as Box<dyn Displayer>
+            })
+            .collect();
+        (MultiOutput::new(displayers), calls)
+    }
+
+    #[test]
+    fn results_and_phases_reach_every_displayer() {
+        let (mut output, calls) = multi(&[true, true]);
+        let results = ProfilerResults {
+            timestamp: 0,
+            duration_ms: 0,
+            exit_code: 0,
+            phases: Vec::new(),
+        };
+
+        let mut listener = output.phase_listener(&[], ".*").unwrap().unwrap();
+        listener(&Phase {
+            index: 0,
+            start_token: joule_profiler_core::types::PhaseToken::Start,
+            end_token: joule_profiler_core::types::PhaseToken::End,
+            timestamp: 0,
+            duration_ms: 0,
+            start_token_line: None,
+            end_token_line: None,
+            metrics: Vec::new(),
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples: Vec::new(),
+        });
+        output.display_results(&[], ".*", &results, None).unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["phase", "phase", "results", "results"]
+        );
+    }
+
+    #[test]
+    fn unsupported_operations_are_skipped() {
+        let (mut output, calls) = multi(&[false, true]);
+        output.list_sensors(&[]).unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["sensors"]);
+
+        let (mut output, _) = multi(&[false, false]);
+        assert!(matches!(
+            output.list_sensors(&[]),
+            Err(DisplayerError::NotImplementedForFormat)
+        ));
+    }
+}
@@ -1,16 +1,44 @@
+//! CSV export of the results, following RFC 4180.
+//!
+//! Fields containing the delimiter, a double quote or a line break are quoted, embedded quotes
+//! being doubled, and records end with CRLF. The long layout writes one row per metric of each
+//! phase, the wide layout one row per phase with one column per `source/sensor (unit)`.
+
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Write;
 
+use clap::ValueEnum;
+use joule_profiler_core::budget::BudgetReport;
 use joule_profiler_core::fs::{
     create_file_with_user_permissions, default_results_filename, get_absolute_path,
 };
 use joule_profiler_core::sensor::Sensor;
-use joule_profiler_core::types::{Phase, ProfilerResults};
+use joule_profiler_core::types::{Metric, Phase, ProfilerResults};
 
+use crate::compare::Comparison;
+use crate::compare::ExcludedRun;
 use crate::output::displayer::{Displayer, DisplayerError};
 
 type Result<T> = std::result::Result<T, DisplayerError>;
 
+/// Default field delimiter, kept for compatibility with the previous outputs.
+pub const DEFAULT_DELIMITER: char = ';';
+
+/// Record terminator of RFC 4180.
+const LINE_BREAK: &str = "\r\n";
+
+/// Layout of the results table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum CsvLayout {
+    /// One row per metric of each phase.
+    #[default]
+    Long,
+
+    /// One row per phase, with one column per `source/sensor (unit)`.
+    Wide,
+}
+
 /// CSV output writer to a file.
 pub struct CsvOutput {
     /// File handle for writing CSV data.
@@ -18,11 +46,21 @@ pub struct CsvOutput {
 
     /// Path to the output CSV file.
     filename: String,
+
+    /// Field delimiter.
+    delimiter: char,
+
+    /// Layout of the results table.
+    layout: CsvLayout,
 }
 
 impl CsvOutput {
     /// Create a CSV output writer to a file, optionally specifying the file path.
-    pub fn try_new(output_file: Option<String>) -> Result<Self> {
+    pub fn try_new(
+        output_file: Option<String>,
+        delimiter: char,
+        layout: CsvLayout,
+    ) -> Result<Self> {
         let filename = output_file.unwrap_or(default_results_filename("csv"));
 
         let absolute_path = get_absolute_path(&filename)?;
@@ -31,31 +69,46 @@ impl CsvOutput {
         Ok(Self {
             file,
             filename: absolute_path,
+            delimiter,
+            layout,
         })
     }
 
-    /// Write CSV header row.
+    /// Write a record to the output file.
+    fn write_record<S: AsRef<str>>(&mut self, fields: &[S]) -> Result<()> {
+        write_record(&mut self.file, self.delimiter, fields)
+    }
+
+    /// Write CSV header row of the long layout.
+    ///
+    /// Columns added after the first releases go last, keeping the positions of the existing ones.
     fn write_header(&mut self, with_iteration_id: bool) -> Result<()> {
+        let mut header = Vec::new();
         if with_iteration_id {
-            write!(self.file, "iteration_id;")?;
+            header.push("iteration_id");
         }
-
-        write!(self.file, "phase_id;phase_name;phase_duration_ms;")?;
-        write!(
-            self.file,
-            "metric_name;metric_value;metric_unit;metric_source;"
-        )?;
-        write!(
-            self.file,
-            "start_token;end_token;start_token_line;end_token_line;timestamp;"
-        )?;
-        write!(self.file, "command;exit_code;token_pattern")?;
-        writeln!(self.file)?;
-
-        Ok(())
+        header.extend([
+            "phase_id",
+            "phase_name",
+            "phase_duration_ms",
+            "metric_name",
+            "metric_value",
+            "metric_unit",
+            "metric_source",
+            "start_token",
+            "end_token",
+            "start_token_line",
+            "end_token_line",
+            "timestamp",
+            "command",
+            "exit_code",
+            "token_pattern",
+            "metric_scope",
+        ]);
+        self.write_record(&header)
     }
 
-    /// Write a CSV row for a single phase.
+    /// Write the CSV rows of a single phase in the long layout, one per metric.
     fn write_phase(
         &mut self,
         phase: &Phase,
@@ -64,44 +117,131 @@ impl CsvOutput {
         token_pattern: &str,
     ) -> Result<()> {
         for metric in &phase.metrics {
-            let start_token_line = phase
-                .start_token_line
-                .map(|l| l.to_string())
-                .unwrap_or_default();
-            let end_token_line = phase
-                .end_token_line
-                .map(|l| l.to_string())
-                .unwrap_or_default();
-
-            write!(
-                self.file,
-                "{};\"{}\";{};",
-                phase.index,
+            self.write_record(&[
+                phase.index.to_string(),
                 phase.get_name(),
-                phase.duration_ms
-            )?;
-            write!(
-                self.file,
-                "{};{};{};{};",
-                metric.name, metric.value, metric.unit, metric.source
-            )?;
-            write!(
-                self.file,
-                "{};{};{};{};{};",
-                phase.start_token,
-                phase.end_token,
-                start_token_line,
-                end_token_line,
-                phase.timestamp
-            )?;
-            write!(
-                self.file,
-                "\"{}\";{};\"{}\"",
-                cmd, results.exit_code, token_pattern
+                phase.duration_ms.to_string(),
+                metric.name.clone(),
+                metric.value.to_string(),
+                metric.unit.to_string(),
+                metric.source.clone(),
+                phase.start_token.to_string(),
+                phase.end_token.to_string(),
+                optional(phase.start_token_line),
+                optional(phase.end_token_line),
+                phase.timestamp.to_string(),
+                cmd.to_owned(),
+                results.exit_code.to_string(),
+                token_pattern.to_owned(),
+                metric.scope.to_string(),
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the phases in the wide layout, one row per phase and one column per metric.
+    ///
+    /// Metric columns are the union of the metrics of all phases, in order of first appearance,
+    /// a phase missing one of them leaving its cell empty.
+    fn write_wide(
+        &mut self,
+        results: &ProfilerResults,
+        cmd: &str,
+        token_pattern: &str,
+    ) -> Result<()> {
+        let mut columns: Vec<String> = Vec::new();
+        for metric in results.phases.iter().flat_map(|phase| &phase.metrics) {
+            let column = wide_column(metric);
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+
+        let mut header: Vec<String> = [
+            "phase_id",
+            "phase_name",
+            "phase_duration_ms",
+            "start_token",
+            "end_token",
+            "start_token_line",
+            "end_token_line",
+            "timestamp",
+            "command",
+            "exit_code",
+            "token_pattern",
+        ]
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+        header.extend(columns.iter().cloned());
+        self.write_record(&header)?;
+
+        for phase in &results.phases {
+            let mut row = vec![
+                phase.index.to_string(),
+                phase.get_name(),
+                phase.duration_ms.to_string(),
+                phase.start_token.to_string(),
+                phase.end_token.to_string(),
+                optional(phase.start_token_line),
+                optional(phase.end_token_line),
+                phase.timestamp.to_string(),
+                cmd.to_owned(),
+                results.exit_code.to_string(),
+                token_pattern.to_owned(),
+            ];
+            row.extend(columns.iter().map(|column| {
+                phase
+                    .metrics
+                    .iter()
+                    .find(|metric| wide_column(metric) == *column)
+                    .map(|metric| metric.value.to_string())
+                    .unwrap_or_default()
+            }));
+            self.write_record(&row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the outcome of the budgets to a separate CSV file, next to the results one.
+    ///
+    /// The results file keeps a single table, `results.csv` budgets being written to
+    /// `results-budgets.csv`.
+    fn write_budgets(&self, budgets: &BudgetReport) -> Result<()> {
+        let stem = self.filename.strip_suffix(".csv").unwrap_or(&self.filename);
+        let filename = format!("{stem}-budgets.csv");
+        let mut file = create_file_with_user_permissions(&filename)?;
+
+        write_record(
+            &mut file,
+            self.delimiter,
+            &[
+                "assertion",
+                "phase_name",
+                "value",
+                "unit",
+                "passed",
+                "reason",
+            ],
+        )?;
+        for check in &budgets.checks {
+            write_record(
+                &mut file,
+                self.delimiter,
+                &[
+                    check.assertion.clone(),
+                    check.phase.clone().unwrap_or_default(),
+                    optional(check.value),
+                    optional(check.unit),
+                    check.passed.to_string(),
+                    check.reason.clone().unwrap_or_default(),
+                ],
             )?;
-            writeln!(self.file)?;
         }
 
+        println!("CSV budgets written to: {filename}");
         Ok(())
     }
 
@@ -117,15 +257,24 @@ impl Displayer for CsvOutput {
         cmd: &[String],
         token_pattern: &str,
         results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
     ) -> Result<()> {
+        if let Some(budgets) = budgets {
+            self.write_budgets(budgets)?;
+        }
         if results.phases.is_empty() {
             return Ok(());
         }
         let command = cmd.join(" ");
 
-        self.write_header(false)?;
-        for phase in &results.phases {
-            self.write_phase(phase, results, command.as_str(), token_pattern)?;
+        match self.layout {
+            CsvLayout::Long => {
+                self.write_header(false)?;
+                for phase in &results.phases {
+                    self.write_phase(phase, results, command.as_str(), token_pattern)?;
+                }
+            }
+            CsvLayout::Wide => self.write_wide(results, command.as_str(), token_pattern)?,
         }
 
         self.finalize();
@@ -133,14 +282,63 @@ impl Displayer for CsvOutput {
     }
 
     fn list_sensors(&mut self, sensors: &[Sensor]) -> Result<()> {
-        writeln!(self.file, "sensor;unit;source")?;
+        self.write_record(&["sensor", "unit", "source"])?;
 
         for sensor in sensors {
-            writeln!(
-                self.file,
-                "{};{};{}",
-                sensor.name, sensor.unit, sensor.source
-            )?;
+            self.write_record(&[
+                sensor.name.clone(),
+                sensor.unit.to_string(),
+                sensor.source.clone(),
+            ])?;
+        }
+
+        self.finalize();
+        Ok(())
+    }
+
+    fn display_comparison(&mut self, comparison: &Comparison) -> Result<()> {
+        self.write_record(&[
+            "baseline",
+            "candidate",
+            "phase_name",
+            "metric_name",
+            "metric_unit",
+            "metric_source",
+            "baseline_value",
+            "candidate_value",
+            "absolute_difference",
+            "relative_difference",
+            "baseline_iterations",
+            "candidate_iterations",
+            "p_value",
+            "verdict",
+            "baseline_excluded",
+            "candidate_excluded",
+        ])?;
+
+        for candidate in &comparison.candidates {
+            for phase in &candidate.phases {
+                for metric in &phase.metrics {
+                    self.write_record(&[
+                        comparison.baseline.clone(),
+                        candidate.file.clone(),
+                        phase.name.clone(),
+                        metric.name.clone(),
+                        metric.unit.to_string(),
+                        metric.source.clone(),
+                        metric.baseline.to_string(),
+                        metric.candidate.to_string(),
+                        metric.absolute_difference.to_string(),
+                        optional(metric.relative_difference),
+                        metric.baseline_iterations.to_string(),
+                        metric.candidate_iterations.to_string(),
+                        optional(metric.test.map(|test| test.p_value)),
+                        metric.verdict.to_string(),
+                        excluded_iterations(&phase.baseline_excluded),
+                        excluded_iterations(&phase.candidate_excluded),
+                    ])?;
+                }
+            }
         }
 
         self.finalize();
@@ -148,6 +346,75 @@ impl Displayer for CsvOutput {
     }
 }
 
+/// Parse a field delimiter, `\t` or `tab` standing for a tab.
+///
+/// Double quotes and line breaks are rejected, being special characters of RFC 4180.
+pub fn parse_delimiter(value: &str) -> std::result::Result<char, String> {
+    let delimiter = match value {
+        "\\t" | "tab" => '\t',
+        _ => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(delimiter), None) => delimiter,
+                _ => return Err(format!("expected a single character, got '{value}'")),
+            }
+        }
+    };
+    if matches!(delimiter, '"' | '\r' | '\n') {
+        return Err(format!(
+            "'{}' cannot be used as a delimiter",
+            delimiter.escape_default()
+        ));
+    }
+    Ok(delimiter)
+}
+
+/// Write a record, escaping its fields and ending it with CRLF.
+fn write_record<W: Write, S: AsRef<str>>(
+    writer: &mut W,
+    delimiter: char,
+    fields: &[S],
+) -> Result<()> {
+    let mut line = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            line.push(delimiter);
+        }
+        line.push_str(&escape_field(field.as_ref(), delimiter));
+    }
+    line.push_str(LINE_BREAK);
+    writer.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Escape a field, quoting it if it contains the delimiter, a double quote or a line break.
+fn escape_field(field: &str, delimiter: char) -> Cow<'_, str> {
+    if field.contains([delimiter, '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Header of the wide layout column of a metric.
+fn wide_column(metric: &Metric) -> String {
+    format!("{}/{} ({})", metric.source, metric.name, metric.unit)
+}
+
+/// Formats an optional value, `None` being an empty field.
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Formats excluded iterations as `file:iteration:reason` triples separated by commas.
+fn excluded_iterations(excluded: &[ExcludedRun]) -> String {
+    excluded
+        .iter()
+        .map(|e| format!("{}:{}:{}", e.file, e.iteration, e.reason.name()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,6 +436,7 @@ mod tests {
         Metric::new(name, value, unit(), "rapl")
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn phase(
         index: usize,
         start: PhaseToken,
@@ -188,6 +456,9 @@ mod tests {
             start_token_line: start_line,
             end_token_line: end_line,
             metrics,
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples: Vec::new(),
         }
     }
 
@@ -216,7 +487,10 @@ mod tests {
     fn csv_to_tempfile() -> (CsvOutput, NamedTempFile) {
         let tmp = NamedTempFile::new().unwrap();
         let path = tmp.path().to_str().unwrap().to_owned();
-        (CsvOutput::try_new(Some(path)).unwrap(), tmp)
+        (
+            CsvOutput::try_new(Some(path), DEFAULT_DELIMITER, CsvLayout::Long).unwrap(),
+            tmp,
+        )
     }
 
     fn read(tmp: &NamedTempFile) -> String {
@@ -226,7 +500,7 @@ mod tests {
     #[test]
     fn phases_single_empty_phases_writes_nothing() {
         let (mut csv, tmp) = csv_to_tempfile();
-        csv.display_results(&["echo".into()], ".*", &results(0, vec![]))
+        csv.display_results(&["echo".into()], ".*", &results(0, vec![]), None)
             .unwrap();
         assert!(read(&tmp).is_empty());
     }
@@ -235,7 +509,8 @@ mod tests {
     fn phases_single_writes_header_without_iteration_id() {
         let (mut csv, tmp) = csv_to_tempfile();
         let iter = results(0, vec![simple_phase(vec![metric("PKG", 10)])]);
-        csv.display_results(&["echo".into()], ".*", &iter).unwrap();
+        csv.display_results(&["echo".into()], ".*", &iter, None)
+            .unwrap();
         let content = read(&tmp);
         assert!(content.contains("phase_id"));
         assert!(content.contains("phase_name"));
@@ -243,11 +518,28 @@ mod tests {
         assert!(!content.contains("iteration_id"));
     }
 
+    #[test]
+    fn phases_single_keeps_original_columns_first() {
+        let (mut csv, tmp) = csv_to_tempfile();
+        let iter = results(0, vec![simple_phase(vec![metric("PKG", 10)])]);
+        csv.display_results(&["echo".into()], ".*", &iter, None)
+            .unwrap();
+        let content = read(&tmp);
+        let header = content.lines().next().unwrap();
+        assert_eq!(
+            header,
+            "phase_id;phase_name;phase_duration_ms;metric_name;metric_value;metric_unit;\
+             metric_source;start_token;end_token;start_token_line;end_token_line;timestamp;\
+             command;exit_code;token_pattern;metric_scope"
+        );
+    }
+
     #[test]
     fn phases_single_writes_metric_values() {
         let (mut csv, tmp) = csv_to_tempfile();
         let iter = results(0, vec![simple_phase(vec![metric("PKG", 42)])]);
-        csv.display_results(&["echo".into()], ".*", &iter).unwrap();
+        csv.display_results(&["echo".into()], ".*", &iter, None)
+            .unwrap();
         let content = read(&tmp);
         assert!(content.contains("PKG"));
         assert!(content.contains("42"));
@@ -258,13 +550,18 @@ mod tests {
     fn phases_single_writes_phase_metadata() {
         let (mut csv, tmp) = csv_to_tempfile();
         let iter = results(3, vec![simple_phase(vec![metric("PKG", 1)])]);
-        csv.display_results(&["my_cmd".into(), "--flag".into()], "MY_PATTERN", &iter)
-            .unwrap();
+        csv.display_results(
+            &["my_cmd".into(), "--flag".into()],
+            "MY_PATTERN",
+            &iter,
+            None,
+        )
+        .unwrap();
         let content = read(&tmp);
         assert!(content.contains("500")); // duration_us
         assert!(content.contains("MY_PATTERN"));
         assert!(content.contains("my_cmd --flag"));
-        assert!(content.contains("3")); // exit_code
+        assert!(content.contains('3')); // exit_code
     }
 
     #[test]
@@ -283,12 +580,13 @@ mod tests {
                 vec![metric("PKG", 1)],
             )],
         );
-        csv.display_results(&["cmd".into()], ".*", &iter).unwrap();
+        csv.display_results(&["cmd".into()], ".*", &iter, None)
+            .unwrap();
         let content = read(&tmp);
         assert!(content.contains("__A__"));
         assert!(content.contains("__B__"));
-        assert!(content.contains("3"));
-        assert!(content.contains("7"));
+        assert!(content.contains('3'));
+        assert!(content.contains('7'));
     }
 
     #[test]
@@ -302,7 +600,8 @@ mod tests {
                 metric("CORE", 3),
             ])],
         );
-        csv.display_results(&["cmd".into()], ".*", &iter).unwrap();
+        csv.display_results(&["cmd".into()], ".*", &iter, None)
+            .unwrap();
         let content = read(&tmp);
 
         assert_eq!(content.lines().count(), 4);
@@ -324,11 +623,33 @@ mod tests {
                 vec![metric("PKG", 1)],
             )],
         );
-        csv.display_results(&["cmd".into()], ".*", &iter).unwrap();
+        csv.display_results(&["cmd".into()], ".*", &iter, None)
+            .unwrap();
 
         assert!(read(&tmp).contains("__START__ -> __END__"));
     }
 
+    #[test]
+    fn budgets_are_written_next_to_the_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.csv");
+        let mut csv = CsvOutput::try_new(
+            Some(path.to_str().unwrap().to_owned()),
+            DEFAULT_DELIMITER,
+            CsvLayout::Long,
+        )
+        .unwrap();
+        let iter = results(0, vec![simple_phase(vec![metric("PKG", 1)])]);
+        let budgets = BudgetReport::check(&["PKG < 1µJ".parse().unwrap()], &iter);
+
+        csv.display_results(&["cmd".into()], ".*", &iter, Some(&budgets))
+            .unwrap();
+
+        let content = fs::read_to_string(dir.path().join("results-budgets.csv")).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(content.contains("PKG < 1µJ;;1;µJ;false;\r\n"));
+    }
+
     #[test]
     fn list_sensors_writes_header_and_one_row_per_sensor() {
         let (mut csv, tmp) = csv_to_tempfile();
@@ -343,4 +664,79 @@ mod tests {
         assert!(content.contains("DRAM"));
         assert_eq!(content.lines().count(), 3);
     }
+
+    #[test]
+    fn fields_are_escaped() {
+        let (mut csv, tmp) = csv_to_tempfile();
+        let iter = results(0, vec![simple_phase(vec![metric("PKG", 1)])]);
+        csv.display_results(
+            &["sh".into(), "-c".into(), "echo \"a;b\"".into()],
+            ".*",
+            &iter,
+            None,
+        )
+        .unwrap();
+
+        let content = read(&tmp);
+        assert!(content.contains(";\"sh -c echo \"\"a;b\"\"\";0;.*;machine\r\n"));
+        assert_eq!(escape_field("a,b", ','), "\"a,b\"");
+        assert_eq!(escape_field("a\nb", ';'), "\"a\nb\"");
+        assert_eq!(escape_field("a,b", ';'), "a,b");
+    }
+
+    #[test]
+    fn delimiter_is_configurable() {
+        let tmp = NamedTempFile::new().unwrap();
+        let path = tmp.path().to_str().unwrap().to_owned();
+        let mut csv = CsvOutput::try_new(Some(path), ',', CsvLayout::Long).unwrap();
+        let iter = results(0, vec![simple_phase(vec![metric("PKG", 1)])]);
+        csv.display_results(&["cmd".into()], "a,b", &iter, None)
+            .unwrap();
+
+        let content = read(&tmp);
+        assert!(content.starts_with("phase_id,phase_name,phase_duration_ms,"));
+        assert!(content.contains(",cmd,0,\"a,b\",machine\r\n"));
+    }
+
+    #[test]
+    fn parse_delimiter_rejects_special_characters() {
+        assert_eq!(parse_delimiter(","), Ok(','));
+        assert_eq!(parse_delimiter("\\t"), Ok('\t'));
+        assert!(parse_delimiter("\"").is_err());
+        assert!(parse_delimiter(";;").is_err());
+    }
+
+    #[test]
+    fn wide_layout_has_one_row_per_phase() {
+        let tmp = NamedTempFile::new().unwrap();
+        let path = tmp.path().to_str().unwrap().to_owned();
+        let mut csv = CsvOutput::try_new(Some(path), ',', CsvLayout::Wide).unwrap();
+        let iter = results(
+            0,
+            vec![
+                simple_phase(vec![metric("PKG", 1), metric("DRAM", 2)]),
+                phase(
+                    1,
+                    PhaseToken::Token("__A__".into()),
+                    PhaseToken::End,
+                    100,
+                    0,
+                    Some(2),
+                    None,
+                    vec![metric("CORE", 3), metric("PKG", 4)],
+                ),
+            ],
+        );
+        csv.display_results(&["cmd".into()], ".*", &iter, None)
+            .unwrap();
+
+        let content = read(&tmp);
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(",rapl/PKG (µJ),rapl/DRAM (µJ),rapl/CORE (µJ)"));
+        assert!(lines[1].starts_with("0,START -> END,500,"));
+        assert!(lines[1].ends_with(",1,2,"));
+        assert!(lines[2].starts_with("1,__A__ -> END,100,__A__,END,2,,"));
+        assert!(lines[2].ends_with(",4,,3"));
+    }
 }
@@ -0,0 +1,430 @@
+//! Energy flame graph of the phases, as folded stacks or as an SVG.
+//!
+//! Each phase is a stack `command;phase` weighted by a chosen metric, repeated phases being merged
+//! into a single stack. Weighted by the cross-source `TOTAL`, each phase is split into the energy
+//! domains summed into its total (e.g. `command;phase;PACKAGE-0`), showing where the joules go.
+//!
+//! Phases are flat: the profiler delimits successive phases between tokens, never nested ones, so
+//! the stacks have a fixed depth of two frames, or three when split by energy domain.
+//!
+//! Energies are weighted in microjoules, the folded format only accepting integer weights. Other
+//! metrics are weighted by their rounded value.
+//!
+//! Failed budgets are listed below the title of the SVG. The folded stacks having no room for
+//! them, they are logged as warnings instead.
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+
+use joule_profiler_core::budget::{BudgetCheck, BudgetReport};
+use joule_profiler_core::fs::{
+    create_file_with_user_permissions, default_results_filename, get_absolute_path,
+};
+use joule_profiler_core::types::{Metric, Phase, ProfilerResults, TOTAL_ENERGY, TotalEnergy};
+use joule_profiler_core::unit::{MetricUnit, Unit, UnitPrefix};
+use log::warn;
+
+use super::escape;
+use crate::output::displayer::{Displayer, DisplayerError};
+
+type Result<T> = std::result::Result<T, DisplayerError>;
+
+const MICRO_JOULE: MetricUnit = MetricUnit {
+    prefix: UnitPrefix::Micro,
+    unit: Unit::Joule,
+};
+
+/// Width of the SVG in pixels.
+const SVG_WIDTH: f64 = 1200.0;
+
+/// Height of a frame in pixels.
+const FRAME_HEIGHT: f64 = 18.0;
+
+/// Approximate width of a character of the frame labels in pixels.
+const CHAR_WIDTH: f64 = 7.0;
+
+/// Flame graph output writer to a file.
+pub struct FlameGraphOutput {
+    /// File handle for writing the flame graph.
+    file: File,
+
+    /// Path to the output file.
+    filename: String,
+
+    /// Metric weighting the stacks.
+    metric: String,
+
+    /// Whether to render an SVG instead of the folded stacks.
+    svg: bool,
+}
+
+impl FlameGraphOutput {
+    /// Create a flame graph writer to a file, optionally specifying the file path.
+    pub fn try_new(output_file: Option<String>, metric: String, svg: bool) -> Result<Self> {
+        let extension = if svg { "svg" } else { "folded" };
+        let filename = output_file.unwrap_or(default_results_filename(extension));
+
+        let absolute_path = get_absolute_path(&filename)?;
+        let file = create_file_with_user_permissions(&absolute_path)?;
+
+        Ok(Self {
+            file,
+            filename: absolute_path,
+            metric,
+            svg,
+        })
+    }
+}
+
+impl Displayer for FlameGraphOutput {
+    fn display_results(
+        &mut self,
+        cmd: &[String],
+        _token_pattern: &str,
+        results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
+    ) -> Result<()> {
+        let stacks = folded_stacks(cmd, results, &self.metric);
+        if stacks.is_empty() {
+            warn!(
+                "No phase has a positive {} metric, the flame graph is empty",
+                self.metric
+            );
+        }
+
+        let failed: Vec<&BudgetCheck> = budgets
+            .iter()
+            .flat_map(|budgets| &budgets.checks)
+            .filter(|check| !check.passed)
+            .collect();
+
+        let content = if self.svg {
+            render_svg(&stacks, &self.metric, &failed)
+        } else {
+            for check in &failed {
+                warn!("{}", budget_failure(check));
+            }
+            stacks
+                .iter()
+                .fold(String::new(), |mut folded, (stack, weight)| {
+                    let _ = writeln!(folded, "{stack} {weight}");
+                    folded
+                })
+        };
+        self.file.write_all(content.as_bytes())?;
+
+        println!("Flame graph written to: {}", self.filename);
+        Ok(())
+    }
+}
+
+/// Folded stacks of the phases weighted by the metric, in order of first appearance.
+fn folded_stacks(cmd: &[String], results: &ProfilerResults, metric: &str) -> Vec<(String, u64)> {
+    let root = frame(&cmd.join(" "));
+    let mut stacks: Vec<(String, u64)> = Vec::new();
+
+    for phase in &results.phases {
+        let phase_stack = format!("{root};{}", frame(&phase.get_name()));
+        for (leaf, weight) in phase_weights(phase, metric) {
+            let stack = match leaf {
+                Some(leaf) => format!("{phase_stack};{}", frame(&leaf)),
+                None => phase_stack.clone(),
+            };
+            match stacks.iter_mut().find(|(existing, _)| *existing == stack) {
+                Some((_, total)) => *total += weight,
+                None => stacks.push((stack, weight)),
+            }
+        }
+    }
+
+    stacks.retain(|(_, weight)| *weight > 0);
+    stacks
+}
+
+/// Weights of a phase, split by energy domain for the total energy.
+fn phase_weights(phase: &Phase, metric: &str) -> Vec<(Option<String>, u64)> {
+    let find = |name: &str| phase.metrics.iter().find(|m| m.name == name);
+
+    if metric == TOTAL_ENERGY
+        && let Some(total) = TotalEnergy::from_metrics(&phase.metrics)
+    {
+        return total
+            .summed
+            .into_iter()
+            .filter_map(|name| {
+                let weight = weight(find(&name)?);
+                Some((Some(name), weight))
+            })
+            .collect();
+    }
+
+    find(metric)
+        .map(|metric| vec![(None, weight(metric))])
+        .unwrap_or_default()
+}
+
+/// Integer weight of a metric, in microjoules for energies.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn weight(metric: &Metric) -> u64 {
+    let value = metric.value.as_f64();
+    let value = metric.unit.convert(value, MICRO_JOULE).unwrap_or(value);
+    value.max(0.0).round() as u64
+}
+
+/// Frame name, semicolons being the frames separator.
+fn frame(name: &str) -> String {
+    name.replace(';', ":")
+}
+
+/// A frame of the flame graph and its callees.
+#[derive(Default)]
+struct Frame {
+    name: String,
+    weight: u64,
+    children: Vec<Frame>,
+}
+
+impl Frame {
+    /// Add a stack below this frame.
+    fn insert<'a>(&mut self, mut frames: impl Iterator<Item = &'a str>, weight: u64) {
+        self.weight += weight;
+        let Some(name) = frames.next() else {
+            return;
+        };
+        let index = if let Some(index) = self.children.iter().position(|c| c.name == name) {
+            index
+        } else {
+            self.children.push(Frame {
+                name: name.to_owned(),
+                ..Default::default()
+            });
+            self.children.len() - 1
+        };
+        self.children[index].insert(frames, weight);
+    }
+
+    fn depth(&self) -> usize {
+        1 + self.children.iter().map(Frame::depth).max().unwrap_or(0)
+    }
+}
+
+/// Description of a failed budget.
+fn budget_failure(check: &BudgetCheck) -> String {
+    let phase = check.phase.as_deref().unwrap_or("all phases");
+    match (check.value, check.unit, &check.reason) {
+        (_, _, Some(reason)) => format!("Budget failed: {} on {phase}: {reason}", check.assertion),
+        (Some(value), Some(unit), None) => {
+            format!(
+                "Budget failed: {} on {phase}: {value:.6} {unit}",
+                check.assertion
+            )
+        }
+        _ => format!("Budget failed: {} on {phase}", check.assertion),
+    }
+}
+
+/// Render the stacks as an SVG flame graph, the root at the bottom and the failed budgets below
+/// the title.
+#[allow(clippy::cast_precision_loss)]
+fn render_svg(stacks: &[(String, u64)], metric: &str, failed: &[&BudgetCheck]) -> String {
+    let mut root = Frame {
+        name: "all".into(),
+        ..Default::default()
+    };
+    for (stack, weight) in stacks {
+        root.insert(stack.split(';'), *weight);
+    }
+
+    let height = (root.depth() as f64 + 2.0 + failed.len() as f64) * FRAME_HEIGHT;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{height}\" font-family=\"monospace\" font-size=\"12\">"
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"14\">Energy flame graph ({})</text>",
+        SVG_WIDTH / 2.0,
+        FRAME_HEIGHT * 0.8,
+        escape(metric)
+    );
+    for (line, check) in failed.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"rgb(200,0,0)\">{}</text>",
+            SVG_WIDTH / 2.0,
+            FRAME_HEIGHT * (line as f64 + 1.8),
+            escape(&budget_failure(check))
+        );
+    }
+    if root.weight > 0 {
+        render_frame(&mut svg, &root, root.weight, 0.0, 0, height);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Render a frame and its callees, `x` being its left side.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn render_frame(svg: &mut String, frame: &Frame, total: u64, x: f64, depth: usize, height: f64) {
+    let width = frame.weight as f64 / total as f64 * SVG_WIDTH;
+    let y = height - (depth as f64 + 1.0) * FRAME_HEIGHT;
+    let percent = frame.weight as f64 / total as f64 * 100.0;
+
+    let _ = writeln!(
+        svg,
+        "<g><title>{} ({}, {percent:.2}%)</title><rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{width:.1}\" height=\"{:.1}\" fill=\"{}\" rx=\"2\"/>",
+        escape(&frame.name),
+        frame.weight,
+        FRAME_HEIGHT - 1.0,
+        color(&frame.name)
+    );
+    let fitting = ((width - 6.0) / CHAR_WIDTH).max(0.0) as usize;
+    if fitting >= 3 {
+        let label: String = if frame.name.chars().count() > fitting {
+            let mut label: String = frame.name.chars().take(fitting - 2).collect();
+            label.push_str("..");
+            label
+        } else {
+            frame.name.clone()
+        };
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+            x + 3.0,
+            y + FRAME_HEIGHT * 0.7,
+            escape(&label)
+        );
+    }
+    svg.push_str("</g>\n");
+
+    let mut child_x = x;
+    for child in &frame.children {
+        render_frame(svg, child, total, child_x, depth + 1, height);
+        child_x += child.weight as f64 / total as f64 * SVG_WIDTH;
+    }
+}
+
+/// Warm color of a frame, stable for a given name.
+fn color(name: &str) -> String {
+    let hash = name.bytes().fold(0u32, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte.into())
+    });
+    let red = 205 + hash % 50;
+    let green = (hash / 50) % 180;
+    let blue = (hash / 9000) % 55;
+    format!("rgb({red},{green},{blue})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::PhaseToken;
+
+    fn joules(name: &str, value: f64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Joule,
+        };
+        Metric::new(name, value, unit, "rapl")
+    }
+
+    fn phase(start: &str, end: &str, metrics: Vec<Metric>) -> Phase {
+        Phase {
+            index: 0,
+            start_token: PhaseToken::Token(start.into()),
+            end_token: PhaseToken::Token(end.into()),
+            timestamp: 0,
+            duration_ms: 1000,
+            start_token_line: None,
+            end_token_line: None,
+            metrics,
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn results() -> ProfilerResults {
+        let metrics = || {
+            vec![
+                joules("PACKAGE-0", 2.0),
+                joules("CORE-0", 1.5),
+                joules("DRAM-0", 0.5),
+                joules(TOTAL_ENERGY, 2.5),
+            ]
+        };
+        ProfilerResults {
+            timestamp: 0,
+            duration_ms: 3000,
+            exit_code: 0,
+            phases: vec![
+                phase("__A__", "__B__", metrics()),
+                phase("__B__", "__A__", vec![joules("PACKAGE-0", 1.0)]),
+                phase("__A__", "__B__", metrics()),
+            ],
+        }
+    }
+
+    #[test]
+    fn total_is_split_by_summed_domain_and_repeated_phases_merged() {
+        let stacks = folded_stacks(&["./bench;x".into()], &results(), TOTAL_ENERGY);
+
+        assert_eq!(
+            stacks,
+            [
+                ("./bench:x;__A__ -> __B__;PACKAGE-0".to_owned(), 4_000_000),
+                ("./bench:x;__A__ -> __B__;DRAM-0".to_owned(), 1_000_000),
+                ("./bench:x;__B__ -> __A__;PACKAGE-0".to_owned(), 1_000_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn sensor_weights_the_phases() {
+        let stacks = folded_stacks(&["bench".into()], &results(), "CORE-0");
+
+        assert_eq!(stacks, [("bench;__A__ -> __B__".to_owned(), 3_000_000)]);
+    }
+
+    #[test]
+    fn svg_frames_are_proportional_to_their_weight() {
+        let stacks = folded_stacks(&["bench".into()], &results(), TOTAL_ENERGY);
+        let svg = render_svg(&stacks, TOTAL_ENERGY, &[]);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<title>all (6000000, 100.00%)</title>"));
+        assert!(svg.contains(&format!(
+            "<title>__A__ -&gt; __B__ (5000000, 83.33%)</title><rect x=\"0.0\" y=\"{:.1}\" width=\"1000.0\"",
+            (4.0 + 2.0 - 3.0) * FRAME_HEIGHT
+        )));
+    }
+
+    #[test]
+    fn failed_budgets_are_listed_below_the_svg_title() {
+        let results = results();
+        let report = BudgetReport::check(
+            &[
+                "PACKAGE-0 < 2J".parse().unwrap(),
+                "PACKAGE-0 < 100J".parse().unwrap(),
+            ],
+            &results,
+        );
+        let failed: Vec<&BudgetCheck> = report.checks.iter().filter(|c| !c.passed).collect();
+        let stacks = folded_stacks(&["bench".into()], &results, TOTAL_ENERGY);
+        let svg = render_svg(&stacks, TOTAL_ENERGY, &failed);
+
+        assert_eq!(failed.len(), 1);
+        assert!(svg.contains("Budget failed: PACKAGE-0 &lt; 2J on all phases: 5.000000 J"));
+        assert!(!svg.contains("100J"));
+        assert!(svg.contains(&format!(
+            "y=\"{:.1}\" width=\"1000.0\"",
+            (4.0 + 2.0 + 1.0 - 3.0) * FRAME_HEIGHT
+        )));
+    }
+}
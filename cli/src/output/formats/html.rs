@@ -0,0 +1,516 @@
+//! Standalone HTML report of the results.
+//!
+//! The report is a single file without external assets: the styles are inline and the charts are
+//! SVG generated here. It contains a summary of the run, a stacked bar chart of the energy of each
+//! phase by sensor, a table of the durations and powers of the phases, a timeline of the phases
+//! and the metrics of each phase.
+//!
+//! The stacked sensors are the ones summed into the total energy, so that the bars do not count
+//! twice a domain included in another one (e.g. `CORE-0` in `PACKAGE-0`).
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+
+use joule_profiler_core::budget::BudgetReport;
+use joule_profiler_core::fs::{
+    create_file_with_user_permissions, default_results_filename, get_absolute_path,
+};
+use joule_profiler_core::types::{Phase, ProfilerResults, TOTAL_ENERGY, TotalEnergy};
+
+use super::escape;
+use crate::output::displayer::{Displayer, DisplayerError};
+
+type Result<T> = std::result::Result<T, DisplayerError>;
+
+/// Colors of the sensors in the charts.
+const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#9c755f",
+];
+
+/// Width of the charts drawing area in pixels.
+const CHART_WIDTH: f64 = 720.0;
+
+/// Width of the phase names column of the bar chart in pixels.
+const LABEL_WIDTH: f64 = 220.0;
+
+/// Height of a bar in pixels.
+const BAR_HEIGHT: f64 = 22.0;
+
+/// Height of the timeline lane in pixels.
+const LANE_HEIGHT: f64 = 36.0;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem auto;max-width:1000px;color:#222}\
+h1{font-size:1.6rem}h2{font-size:1.2rem;margin-top:2rem;border-bottom:1px solid #ddd}\
+table{border-collapse:collapse;margin:.5rem 0}th,td{padding:.25rem .75rem;text-align:left;border-bottom:1px solid #eee}\
+td.num{text-align:right;font-variant-numeric:tabular-nums}.pass{color:#2e7d32}.fail{color:#c62828;font-weight:bold}\
+.legend span{display:inline-block;margin-right:1rem}.legend i{display:inline-block;width:.8rem;height:.8rem;margin-right:.3rem}\
+svg text{font-size:12px;fill:#222}code{background:#f4f4f4;padding:.1rem .3rem}";
+
+/// HTML report writer to a file.
+pub struct HtmlOutput {
+    /// File handle for writing the report.
+    file: File,
+
+    /// Path to the output HTML file.
+    filename: String,
+}
+
+impl HtmlOutput {
+    /// Create an HTML report writer to a file, optionally specifying the file path.
+    pub fn try_new(output_file: Option<String>) -> Result<Self> {
+        let filename = output_file.unwrap_or(default_results_filename("html"));
+
+        let absolute_path = get_absolute_path(&filename)?;
+        let file = create_file_with_user_permissions(&absolute_path)?;
+
+        Ok(Self {
+            file,
+            filename: absolute_path,
+        })
+    }
+}
+
+impl Displayer for HtmlOutput {
+    fn display_results(
+        &mut self,
+        cmd: &[String],
+        token_pattern: &str,
+        results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
+    ) -> Result<()> {
+        let report = render(cmd, token_pattern, results, budgets);
+        self.file.write_all(report.as_bytes())?;
+        println!("HTML report written to: {}", self.filename);
+        Ok(())
+    }
+}
+
+/// Energy of a phase in joules, from its `TOTAL` metric.
+fn total_energy(phase: &Phase) -> Option<f64> {
+    phase
+        .metrics
+        .iter()
+        .find(|metric| metric.name == TOTAL_ENERGY)
+        .map(|metric| metric.value.as_f64() * metric.unit.prefix.factor())
+}
+
+/// Average power of a phase in watts.
+#[allow(clippy::cast_precision_loss)]
+fn average_power(phase: &Phase) -> Option<f64> {
+    total_energy(phase)
+        .filter(|_| phase.duration_ms > 0)
+        .map(|joules| joules / (phase.duration_ms as f64 / 1e3))
+}
+
+/// Energy of the summed sensors of a phase in joules, in the order of the total energy rule.
+fn stacked_energies(phase: &Phase) -> Vec<(String, f64)> {
+    let Some(total) = TotalEnergy::from_metrics(&phase.metrics) else {
+        return Vec::new();
+    };
+    total
+        .summed
+        .into_iter()
+        .filter_map(|name| {
+            let metric = phase.metrics.iter().find(|metric| metric.name == name)?;
+            Some((name, metric.value.as_f64() * metric.unit.prefix.factor()))
+        })
+        .collect()
+}
+
+/// Render the report.
+fn render(
+    cmd: &[String],
+    token_pattern: &str,
+    results: &ProfilerResults,
+    budgets: Option<&BudgetReport>,
+) -> String {
+    let command = cmd.join(" ");
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Joule Profiler report: {}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n",
+        escape(&command)
+    );
+    let _ = writeln!(html, "<h1>Joule Profiler report</h1>");
+
+    render_summary(&mut html, &command, token_pattern, results);
+    if let Some(budgets) = budgets {
+        render_budgets(&mut html, budgets);
+    }
+    render_energy_chart(&mut html, &results.phases);
+    render_phases_table(&mut html, &results.phases);
+    render_timeline(&mut html, results);
+    render_metrics(&mut html, &results.phases);
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Summary of the run.
+#[allow(clippy::cast_precision_loss)]
+fn render_summary(
+    html: &mut String,
+    command: &str,
+    token_pattern: &str,
+    results: &ProfilerResults,
+) {
+    let energies: Vec<f64> = results.phases.iter().filter_map(total_energy).collect();
+
+    let _ = writeln!(html, "<h2>Summary</h2>\n<table>");
+    let _ = writeln!(
+        html,
+        "<tr><th>Command</th><td><code>{}</code></td></tr>",
+        escape(command)
+    );
+    let _ = writeln!(
+        html,
+        "<tr><th>Token pattern</th><td><code>{}</code></td></tr>",
+        escape(token_pattern)
+    );
+    let _ = writeln!(
+        html,
+        "<tr><th>Exit code</th><td>{}</td></tr>",
+        results.exit_code
+    );
+    let _ = writeln!(
+        html,
+        "<tr><th>Duration</th><td>{} ms</td></tr>",
+        results.duration_ms
+    );
+    let _ = writeln!(
+        html,
+        "<tr><th>Phases</th><td>{}</td></tr>",
+        results.phases.len()
+    );
+    if !energies.is_empty() {
+        let joules: f64 = energies.iter().sum();
+        let _ = writeln!(html, "<tr><th>Total energy</th><td>{joules:.3} J</td></tr>");
+        if results.duration_ms > 0 {
+            let _ = writeln!(
+                html,
+                "<tr><th>Average power</th><td>{:.3} W</td></tr>",
+                joules / (results.duration_ms as f64 / 1e3)
+            );
+        }
+    }
+    let _ = writeln!(html, "</table>");
+}
+
+/// Outcome of the budgets.
+fn render_budgets(html: &mut String, budgets: &BudgetReport) {
+    let _ = writeln!(
+        html,
+        "<h2>Budgets</h2>\n<table>\n<tr><th>Status</th><th>Budget</th><th>Phase</th><th>Value</th></tr>"
+    );
+    for check in &budgets.checks {
+        let (class, status) = if check.passed {
+            ("pass", "PASS")
+        } else {
+            ("fail", "FAIL")
+        };
+        let outcome = match (check.value, check.unit, &check.reason) {
+            (_, _, Some(reason)) => reason.clone(),
+            (Some(value), Some(unit), None) => format!("{value:.6} {unit}"),
+            _ => String::new(),
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td class=\"{class}\">{status}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+            escape(&check.assertion),
+            escape(check.phase.as_deref().unwrap_or("all")),
+            escape(&outcome)
+        );
+    }
+    let _ = writeln!(html, "</table>");
+}
+
+/// Stacked horizontal bars of the energy of each phase by sensor.
+fn render_energy_chart(html: &mut String, phases: &[Phase]) {
+    let stacks: Vec<(String, Vec<(String, f64)>)> = phases
+        .iter()
+        .map(|phase| (phase.get_name(), stacked_energies(phase)))
+        .collect();
+    let highest = stacks
+        .iter()
+        .map(|(_, energies)| energies.iter().map(|(_, joules)| joules).sum::<f64>())
+        .fold(0.0, f64::max);
+    if highest <= 0.0 {
+        return;
+    }
+
+    let mut sensors: Vec<&str> = Vec::new();
+    for (_, energies) in &stacks {
+        for (name, _) in energies {
+            if !sensors.contains(&name.as_str()) {
+                sensors.push(name);
+            }
+        }
+    }
+    let color = |sensor: &str| {
+        let index = sensors.iter().position(|s| *s == sensor).unwrap_or(0);
+        PALETTE[index % PALETTE.len()]
+    };
+
+    let _ = writeln!(html, "<h2>Energy by sensor</h2>\n<p class=\"legend\">");
+    for sensor in &sensors {
+        let _ = writeln!(
+            html,
+            "<span><i style=\"background:{}\"></i>{}</span>",
+            color(sensor),
+            escape(sensor)
+        );
+    }
+    let _ = writeln!(html, "</p>");
+
+    let bars_width = CHART_WIDTH - LABEL_WIDTH;
+    #[allow(clippy::cast_precision_loss)]
+    let height = (stacks.len() as f64) * (BAR_HEIGHT + 8.0) + 8.0;
+    let _ = writeln!(
+        html,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" height=\"{height}\" role=\"img\">"
+    );
+    #[allow(clippy::cast_precision_loss)]
+    for (row, (name, energies)) in stacks.iter().enumerate() {
+        let y = 8.0 + row as f64 * (BAR_HEIGHT + 8.0);
+        let _ = writeln!(
+            html,
+            "<text x=\"0\" y=\"{:.1}\">{}</text>",
+            y + BAR_HEIGHT * 0.7,
+            escape(name)
+        );
+        let mut x = LABEL_WIDTH;
+        for (sensor, joules) in energies {
+            let width = joules / highest * bars_width;
+            let _ = writeln!(
+                html,
+                "<rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{width:.1}\" height=\"{BAR_HEIGHT}\" fill=\"{}\"><title>{}: {joules:.3} J</title></rect>",
+                color(sensor),
+                escape(sensor)
+            );
+            x += width;
+        }
+    }
+    let _ = writeln!(html, "</svg>");
+}
+
+/// Table of the durations, energies and powers of the phases.
+fn render_phases_table(html: &mut String, phases: &[Phase]) {
+    let _ = writeln!(
+        html,
+        "<h2>Phases</h2>\n<table>\n<tr><th>#</th><th>Phase</th><th>Duration</th><th>Energy</th><th>Average power</th><th>Power distribution</th></tr>"
+    );
+    for phase in phases {
+        let energy = total_energy(phase)
+            .map(|joules| format!("{joules:.3} J"))
+            .unwrap_or_default();
+        let power = average_power(phase)
+            .map(|watts| format!("{watts:.3} W"))
+            .unwrap_or_default();
+        let distribution = phase
+            .power
+            .iter()
+            .map(|stats| {
+                format!(
+                    "{}: p50 {:.2} W, p99 {:.2} W, max {:.2} W",
+                    escape(&stats.name),
+                    stats.p50,
+                    stats.p99,
+                    stats.max
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("<br>");
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{} ms</td><td class=\"num\">{energy}</td><td class=\"num\">{power}</td><td>{distribution}</td></tr>",
+            phase.index,
+            escape(&phase.get_name()),
+            phase.duration_ms
+        );
+    }
+    let _ = writeln!(html, "</table>");
+}
+
+/// Timeline of the phases, shaded by their average power.
+#[allow(clippy::cast_precision_loss)]
+fn render_timeline(html: &mut String, results: &ProfilerResults) {
+    let Some(start) = results.phases.iter().map(|phase| phase.timestamp).min() else {
+        return;
+    };
+    let end = results
+        .phases
+        .iter()
+        .map(|phase| phase.timestamp + phase.duration_ms * 1000)
+        .max()
+        .unwrap_or(start);
+    let span_us = end.saturating_sub(start).max(1) as f64;
+    let highest_power = results
+        .phases
+        .iter()
+        .filter_map(average_power)
+        .fold(0.0, f64::max);
+
+    let _ = writeln!(html, "<h2>Timeline</h2>");
+    let height = LANE_HEIGHT + 24.0;
+    let _ = writeln!(
+        html,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" height=\"{height}\" role=\"img\">"
+    );
+    for phase in &results.phases {
+        let x = (phase.timestamp - start) as f64 / span_us * CHART_WIDTH;
+        let width = (phase.duration_ms as f64 * 1000.0 / span_us * CHART_WIDTH).max(1.0);
+        let opacity = match average_power(phase) {
+            Some(watts) if highest_power > 0.0 => 0.25 + 0.75 * watts / highest_power,
+            _ => 0.25,
+        };
+        let _ = writeln!(
+            html,
+            "<rect x=\"{x:.1}\" y=\"0\" width=\"{width:.1}\" height=\"{LANE_HEIGHT}\" fill=\"{}\" fill-opacity=\"{opacity:.2}\" stroke=\"#fff\"><title>{}: {} ms</title></rect>",
+            PALETTE[0],
+            escape(&phase.get_name()),
+            phase.duration_ms
+        );
+    }
+    let _ = writeln!(
+        html,
+        "<text x=\"0\" y=\"{:.1}\">0 ms</text><text x=\"{CHART_WIDTH}\" y=\"{:.1}\" text-anchor=\"end\">{:.0} ms</text>",
+        LANE_HEIGHT + 16.0,
+        LANE_HEIGHT + 16.0,
+        span_us / 1000.0
+    );
+    let _ = writeln!(html, "</svg>");
+}
+
+/// Metrics of each phase, folded.
+fn render_metrics(html: &mut String, phases: &[Phase]) {
+    let _ = writeln!(html, "<h2>Metrics</h2>");
+    for phase in phases {
+        let _ = writeln!(
+            html,
+            "<details><summary>{}</summary>\n<table>\n<tr><th>Source</th><th>Metric</th><th>Value</th><th>Unit</th></tr>",
+            escape(&phase.get_name())
+        );
+        for metric in &phase.metrics {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+                escape(&metric.source),
+                escape(&metric.name),
+                metric.value,
+                escape(&metric.unit.to_string())
+            );
+        }
+        let _ = writeln!(html, "</table>\n</details>");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::{Metric, PhaseToken};
+    use joule_profiler_core::unit::{MetricUnit, Unit, UnitPrefix};
+
+    fn joules(name: &str, value: f64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Joule,
+        };
+        Metric::new(name, value, unit, "rapl")
+    }
+
+    fn phase(index: usize, timestamp: u128, metrics: Vec<Metric>) -> Phase {
+        Phase {
+            index,
+            start_token: PhaseToken::Start,
+            end_token: PhaseToken::Token(format!("__P{index}__")),
+            timestamp,
+            duration_ms: 1000,
+            start_token_line: None,
+            end_token_line: None,
+            metrics,
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn results() -> ProfilerResults {
+        ProfilerResults {
+            timestamp: 0,
+            duration_ms: 2000,
+            exit_code: 0,
+            phases: vec![
+                phase(
+                    0,
+                    0,
+                    vec![
+                        joules("PACKAGE-0", 6.0),
+                        joules("CORE-0", 4.0),
+                        joules("DRAM-0", 2.0),
+                        joules(TOTAL_ENERGY, 8.0),
+                    ],
+                ),
+                phase(
+                    1,
+                    1_000_000,
+                    vec![joules("PACKAGE-0", 3.0), joules(TOTAL_ENERGY, 3.0)],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn stacked_sensors_are_the_summed_ones() {
+        let energies = stacked_energies(&results().phases[0]);
+        let names: Vec<&str> = energies.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(names, ["PACKAGE-0", "DRAM-0"]);
+    }
+
+    #[test]
+    fn report_is_standalone() {
+        let html = render(&["./bench".into()], ".*", &results(), None);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<link"));
+        assert!(html.contains("<tr><th>Total energy</th><td>11.000 J</td></tr>"));
+        assert!(html.contains("<tr><th>Average power</th><td>5.500 W</td></tr>"));
+    }
+
+    #[test]
+    fn bars_are_scaled_to_the_highest_phase() {
+        let html = render(&["./bench".into()], ".*", &results(), None);
+
+        let full = CHART_WIDTH - LABEL_WIDTH;
+        assert!(html.contains(&format!(
+            "x=\"{LABEL_WIDTH:.1}\" y=\"8.0\" width=\"{:.1}\"",
+            full * 6.0 / 8.0
+        )));
+        assert!(html.contains(&format!("width=\"{:.1}\"", full * 3.0 / 8.0)));
+        assert!(html.contains("<title>DRAM-0: 2.000 J</title>"));
+    }
+
+    #[test]
+    fn timeline_places_the_phases() {
+        let html = render(&["./bench".into()], ".*", &results(), None);
+
+        assert!(html.contains(&format!(
+            "<rect x=\"{:.1}\" y=\"0\" width=\"{:.1}\"",
+            CHART_WIDTH / 2.0,
+            CHART_WIDTH / 2.0
+        )));
+        assert!(html.contains("2000 ms</text>"));
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let html = render(&["echo".into(), "<b>&".into()], ".*", &results(), None);
+
+        assert!(html.contains("<code>echo &lt;b&gt;&amp;</code>"));
+    }
+}
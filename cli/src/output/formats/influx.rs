@@ -0,0 +1,374 @@
+//! `InfluxDB` line protocol export of the results.
+//!
+//! Each metric of a phase is a point of the `joule_profiler` measurement, tagged with its
+//! `source`, `sensor`, `unit`, `phase` and `host`, with the `value` and `duration_ms` fields and
+//! the start timestamp of the phase. When the sources are polled, each reading of a phase is also
+//! a point of the `joule_profiler_sample` measurement, timestamped at the start of its interval.
+//! Each budget check is a point of the `joule_profiler_budget` measurement, tagged with its
+//! `assertion` and `phase`, with the `passed`, `value` and `threshold` fields, timestamped at the
+//! end of the run.
+
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+
+use clap::ValueEnum;
+use joule_profiler_core::budget::{BudgetCheck, BudgetReport};
+use joule_profiler_core::fs::{
+    create_file_with_user_permissions, default_results_filename, get_absolute_path,
+};
+use joule_profiler_core::types::{Metric, ProfilerResults};
+
+use crate::output::displayer::{Displayer, DisplayerError};
+
+type Result<T> = std::result::Result<T, DisplayerError>;
+
+/// Measurement of the phases metrics.
+const PHASE_MEASUREMENT: &str = "joule_profiler";
+
+/// Measurement of the polled readings.
+const SAMPLE_MEASUREMENT: &str = "joule_profiler_sample";
+
+/// Measurement of the budget checks.
+const BUDGET_MEASUREMENT: &str = "joule_profiler_budget";
+
+/// Host tag used when the hostname cannot be read.
+const UNKNOWN_HOST: &str = "unknown";
+
+/// Precision of the line protocol timestamps.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum Precision {
+    /// Nanoseconds, the default precision of `InfluxDB`.
+    #[default]
+    Ns,
+
+    /// Microseconds.
+    Us,
+
+    /// Milliseconds.
+    Ms,
+
+    /// Seconds.
+    S,
+}
+
+impl Precision {
+    /// Converts a timestamp in microseconds to this precision.
+    fn timestamp(self, timestamp_us: u128) -> u128 {
+        match self {
+            Precision::Ns => timestamp_us * 1000,
+            Precision::Us => timestamp_us,
+            Precision::Ms => timestamp_us / 1000,
+            Precision::S => timestamp_us / 1_000_000,
+        }
+    }
+}
+
+/// `InfluxDB` line protocol output writer to a file.
+pub struct InfluxOutput {
+    /// File handle for writing the points.
+    file: File,
+
+    /// Path to the output file.
+    filename: String,
+
+    /// Precision of the timestamps.
+    precision: Precision,
+
+    /// Host tag of the points.
+    host: String,
+}
+
+impl InfluxOutput {
+    /// Create a line protocol output writer to a file, optionally specifying the file path.
+    pub fn try_new(output_file: Option<String>, precision: Precision) -> Result<Self> {
+        let filename = output_file.unwrap_or(default_results_filename("lp"));
+
+        let absolute_path = get_absolute_path(&filename)?;
+        let file = create_file_with_user_permissions(&absolute_path)?;
+
+        Ok(Self {
+            file,
+            filename: absolute_path,
+            precision,
+            host: hostname(),
+        })
+    }
+}
+
+impl Displayer for InfluxOutput {
+    fn display_results(
+        &mut self,
+        _cmd: &[String],
+        _token_pattern: &str,
+        results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
+    ) -> Result<()> {
+        let mut lines = String::new();
+
+        for phase in &results.phases {
+            let name = phase.get_name();
+            let timestamp = self.precision.timestamp(phase.timestamp);
+            #[allow(clippy::cast_precision_loss)]
+            let duration_ms = phase.duration_ms as f64;
+
+            for metric in &phase.metrics {
+                write_point(
+                    &mut lines,
+                    PHASE_MEASUREMENT,
+                    metric,
+                    &name,
+                    &self.host,
+                    duration_ms,
+                    timestamp,
+                );
+            }
+
+            for sample in &phase.samples {
+                let timestamp = self.precision.timestamp(sample.timestamp);
+                #[allow(clippy::cast_precision_loss)]
+                let duration_ms = sample.duration_us as f64 / 1000.0;
+
+                for metric in &sample.metrics {
+                    write_point(
+                        &mut lines,
+                        SAMPLE_MEASUREMENT,
+                        metric,
+                        &name,
+                        &self.host,
+                        duration_ms,
+                        timestamp,
+                    );
+                }
+            }
+        }
+
+        let end_timestamp = self
+            .precision
+            .timestamp(results.timestamp + results.duration_ms * 1000);
+        for check in budgets.iter().flat_map(|budgets| &budgets.checks) {
+            write_budget_point(&mut lines, check, &self.host, end_timestamp);
+        }
+
+        self.file.write_all(lines.as_bytes())?;
+        println!("InfluxDB line protocol written to: {}", self.filename);
+        Ok(())
+    }
+}
+
+/// Write the point of a metric.
+///
+/// Non-finite values are skipped, the line protocol having no representation for them.
+fn write_point(
+    lines: &mut String,
+    measurement: &str,
+    metric: &Metric,
+    phase: &str,
+    host: &str,
+    duration_ms: f64,
+    timestamp: u128,
+) {
+    let value = metric.value.as_f64();
+    if !value.is_finite() {
+        return;
+    }
+
+    let _ = writeln!(
+        lines,
+        "{measurement},source={},sensor={},unit={},phase={},host={} value={},duration_ms={} {timestamp}",
+        escape_tag(&metric.source),
+        escape_tag(&metric.name),
+        escape_tag(&metric.unit.to_string()),
+        escape_tag(phase),
+        escape_tag(host),
+        format_float(value),
+        format_float(duration_ms),
+    );
+}
+
+/// Write the point of a budget check, the phase tag being left out for the whole run.
+///
+/// The value and threshold fields are left out when unknown or not finite.
+fn write_budget_point(lines: &mut String, check: &BudgetCheck, host: &str, timestamp: u128) {
+    let phase = check
+        .phase
+        .as_deref()
+        .map(|phase| format!(",phase={}", escape_tag(phase)))
+        .unwrap_or_default();
+    let mut fields = format!("passed={}", check.passed);
+    for (name, value) in [("value", check.value), ("threshold", check.threshold)] {
+        if let Some(value) = value.filter(|value| value.is_finite()) {
+            let _ = write!(fields, ",{name}={}", format_float(value));
+        }
+    }
+
+    let _ = writeln!(
+        lines,
+        "{BUDGET_MEASUREMENT},assertion={}{phase},host={} {fields} {timestamp}",
+        escape_tag(&check.assertion),
+        escape_tag(host),
+    );
+}
+
+/// Escape a tag value, commas, equal signs and spaces being special characters.
+///
+/// Empty tag values are not allowed, they are replaced by a placeholder.
+fn escape_tag(value: &str) -> String {
+    if value.is_empty() {
+        return "none".into();
+    }
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+        .replace('\n', "\\n")
+}
+
+/// Format a float field, integral values being written as floats to keep the field type stable.
+fn format_float(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{value:.1}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Hostname of the machine, for the host tag.
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|hostname| hostname.trim().to_owned())
+        .filter(|hostname| !hostname.is_empty())
+        .unwrap_or_else(|| UNKNOWN_HOST.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::{Phase, PhaseSample, PhaseToken};
+    use joule_profiler_core::unit::{MetricUnit, Unit, UnitPrefix};
+    use tempfile::NamedTempFile;
+
+    fn metric(value: u64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        Metric::new("PACKAGE-0", value, unit, "RAPL (Powercap)")
+    }
+
+    fn results(samples: Vec<PhaseSample>) -> ProfilerResults {
+        ProfilerResults {
+            timestamp: 1_700_000_000_000_000,
+            duration_ms: 1500,
+            exit_code: 0,
+            phases: vec![Phase {
+                index: 0,
+                start_token: PhaseToken::Start,
+                end_token: PhaseToken::End,
+                timestamp: 1_700_000_000_000_000,
+                duration_ms: 1500,
+                start_token_line: None,
+                end_token_line: None,
+                metrics: vec![metric(2_500_000)],
+                total_energy_rule: None,
+                power: Vec::new(),
+                samples,
+            }],
+        }
+    }
+
+    fn write(results: &ProfilerResults, precision: Precision) -> Vec<String> {
+        write_with_budgets(results, precision, None)
+    }
+
+    fn write_with_budgets(
+        results: &ProfilerResults,
+        precision: Precision,
+        budgets: Option<&BudgetReport>,
+    ) -> Vec<String> {
+        let tmp = NamedTempFile::new().unwrap();
+        let mut output =
+            InfluxOutput::try_new(Some(tmp.path().to_str().unwrap().to_owned()), precision)
+                .unwrap();
+        output.host = "bench host".into();
+        output
+            .display_results(&["cmd".into()], ".*", results, budgets)
+            .unwrap();
+        fs::read_to_string(tmp.path())
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn phase_metrics_are_points() {
+        let lines = write(&results(Vec::new()), Precision::Ns);
+
+        assert_eq!(
+            lines,
+            [
+                "joule_profiler,source=RAPL\\ (Powercap),sensor=PACKAGE-0,unit=µJ,phase=START\\ ->\\ END,host=bench\\ host value=2500000.0,duration_ms=1500.0 1700000000000000000"
+            ]
+        );
+    }
+
+    #[test]
+    fn samples_are_points_at_their_interval() {
+        let samples = vec![
+            PhaseSample {
+                timestamp: 1_700_000_000_000_000,
+                duration_us: 500_000,
+                metrics: vec![metric(1_000_000)],
+            },
+            PhaseSample {
+                timestamp: 1_700_000_000_500_000,
+                duration_us: 1_000_000,
+                metrics: vec![metric(1_500_000)],
+            },
+        ];
+        let lines = write(&results(samples), Precision::Ms);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("joule_profiler_sample,"));
+        assert!(lines[1].ends_with(" value=1000000.0,duration_ms=500.0 1700000000000"));
+        assert!(lines[2].ends_with(" value=1500000.0,duration_ms=1000.0 1700000000500"));
+    }
+
+    #[test]
+    fn budget_checks_are_points_at_the_end_of_the_run() {
+        let results = results(Vec::new());
+        let budgets = BudgetReport::check(
+            &[
+                "PACKAGE-0 < 2J".parse().unwrap(),
+                "phase(START).PACKAGE-0 <= 3J".parse().unwrap(),
+            ],
+            &results,
+        );
+        let lines = write_with_budgets(&results, Precision::Ms, Some(&budgets));
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "joule_profiler_budget,assertion=PACKAGE-0\\ <\\ 2J,host=bench\\ host passed=false,value=2.5,threshold=2.0 1700000001500"
+        );
+        assert!(lines[2].contains(",phase=START\\ ->\\ END,"));
+        assert!(lines[2].contains(" passed=true,value=2.5,threshold=3.0 "));
+    }
+
+    #[test]
+    fn precision_converts_microseconds() {
+        assert_eq!(Precision::Ns.timestamp(1_500_000), 1_500_000_000);
+        assert_eq!(Precision::Us.timestamp(1_500_000), 1_500_000);
+        assert_eq!(Precision::Ms.timestamp(1_500_000), 1500);
+        assert_eq!(Precision::S.timestamp(1_500_000), 1);
+    }
+
+    #[test]
+    fn tag_values_are_escaped() {
+        assert_eq!(escape_tag("a,b=c d"), "a\\,b\\=c\\ d");
+        assert_eq!(escape_tag(""), "none");
+    }
+}
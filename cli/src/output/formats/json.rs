@@ -1,14 +1,15 @@
 use std::fs::File;
 use std::io::Write;
 
+use crate::compare::Comparison;
 use crate::output::displayer::error::IntoDisplayerError;
 use crate::output::displayer::{Displayer, DisplayerError};
+use joule_profiler_core::budget::BudgetReport;
 use joule_profiler_core::fs::{
     create_file_with_user_permissions, default_results_filename, get_absolute_path,
 };
 use joule_profiler_core::sensor::Sensor;
-use joule_profiler_core::types::ProfilerResults;
-use serde_json::json;
+use joule_profiler_core::types::{ProfilerResults, SavedResults};
 
 type Result<T> = std::result::Result<T, DisplayerError>;
 
@@ -51,13 +52,12 @@ impl Displayer for JsonOutput {
         cmd: &[String],
         token_pattern: &str,
         results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
     ) -> Result<()> {
-        self.write_json(&json!({
-            "command": cmd.join(" "),
-            "token_pattern": token_pattern,
-            "exit_code": results.exit_code,
-            "phases": results.phases,
-        }))
+        let saved = SavedResults::new(cmd, token_pattern, results).with_budgets(budgets);
+        self.write_json(
+            &serde_json::to_value(saved).map_err(IntoDisplayerError::into_displayer_error)?,
+        )
     }
 
     fn list_sensors(&mut self, sensors: &[Sensor]) -> Result<()> {
@@ -65,4 +65,10 @@ impl Displayer for JsonOutput {
             &serde_json::to_value(sensors).map_err(IntoDisplayerError::into_displayer_error)?,
         )
     }
+
+    fn display_comparison(&mut self, comparison: &Comparison) -> Result<()> {
+        self.write_json(
+            &serde_json::to_value(comparison).map_err(IntoDisplayerError::into_displayer_error)?,
+        )
+    }
 }
@@ -0,0 +1,342 @@
+//! JSON Lines export of the results, streamed while profiling.
+//!
+//! Each line is a compact JSON record whose `type` is `run` for the header written when the
+//! profiling starts, `phase` for each phase written as soon as it completes, followed by one
+//! `sample` record per polled reading, and `summary` for the outcome of the run. Records are
+//! written unbuffered, the file being readable while the profiled command runs.
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use joule_profiler_core::budget::BudgetReport;
+use joule_profiler_core::fs::{
+    create_file_with_user_permissions, default_results_filename, get_absolute_path,
+};
+use joule_profiler_core::types::{
+    Metrics, Phase, PhaseListener, ProfilerResults, RESULTS_SCHEMA_VERSION,
+};
+use log::warn;
+use serde::Serialize;
+
+use crate::output::displayer::error::IntoDisplayerError;
+use crate::output::displayer::{Displayer, DisplayerError};
+
+type Result<T> = std::result::Result<T, DisplayerError>;
+
+/// Record of the output, one per line.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    /// Header of the run, written when the profiling starts.
+    Run {
+        schema_version: u32,
+        command: String,
+        token_pattern: &'a str,
+    },
+
+    /// Completed phase.
+    Phase(&'a Phase),
+
+    /// Polled reading of a phase.
+    Sample {
+        phase_index: usize,
+        timestamp: u128,
+        duration_us: u128,
+        metrics: &'a Metrics,
+    },
+
+    /// Outcome of the run, written once it is complete.
+    Summary {
+        timestamp: u128,
+        duration_ms: u128,
+        exit_code: i32,
+        phases: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        budgets: Option<&'a BudgetReport>,
+    },
+}
+
+/// Writer of the records, shared with the phase listener.
+struct RecordWriter {
+    /// File handle for writing the records.
+    file: File,
+
+    /// Whether the run header has been written.
+    run_written: bool,
+
+    /// Number of phases already written by the phase listener.
+    streamed: usize,
+
+    /// Whether a phase failed to be streamed, the listener then leaving the next phases to the
+    /// results so that the streamed ones are always the first phases.
+    stream_failed: bool,
+}
+
+impl RecordWriter {
+    /// Write a record on its own line.
+    fn write(&mut self, record: &Record) -> Result<()> {
+        let mut line =
+            serde_json::to_string(record).map_err(IntoDisplayerError::into_displayer_error)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Write the run header, unless it has already been written.
+    fn write_run(&mut self, cmd: &[String], token_pattern: &str) -> Result<()> {
+        if self.run_written {
+            return Ok(());
+        }
+        self.write(&Record::Run {
+            schema_version: RESULTS_SCHEMA_VERSION,
+            command: cmd.join(" "),
+            token_pattern,
+        })?;
+        self.run_written = true;
+        Ok(())
+    }
+
+    /// Write a phase followed by its samples.
+    fn write_phase(&mut self, phase: &Phase) -> Result<()> {
+        self.write(&Record::Phase(phase))?;
+        for sample in &phase.samples {
+            self.write(&Record::Sample {
+                phase_index: phase.index,
+                timestamp: sample.timestamp,
+                duration_us: sample.duration_us,
+                metrics: &sample.metrics,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON Lines output writer to a file.
+pub struct JsonLinesOutput {
+    /// Records writer, shared with the phase listener.
+    writer: Arc<Mutex<RecordWriter>>,
+
+    /// Path to the output file.
+    filename: String,
+}
+
+impl JsonLinesOutput {
+    /// Create a JSON Lines output writer to a file, optionally specifying the file path.
+    pub fn try_new(output_file: Option<String>) -> Result<Self> {
+        let filename = output_file.unwrap_or(default_results_filename("jsonl"));
+
+        let absolute_path = get_absolute_path(&filename)?;
+        let file = create_file_with_user_permissions(&absolute_path)?;
+
+        Ok(Self {
+            writer: Arc::new(Mutex::new(RecordWriter {
+                file,
+                run_written: false,
+                streamed: 0,
+                stream_failed: false,
+            })),
+            filename: absolute_path,
+        })
+    }
+}
+
+impl Displayer for JsonLinesOutput {
+    fn phase_listener(
+        &mut self,
+        cmd: &[String],
+        token_pattern: &str,
+    ) -> Result<Option<PhaseListener>> {
+        self.writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write_run(cmd, token_pattern)?;
+        println!("JSON Lines streamed to: {}", self.filename);
+
+        let writer = Arc::clone(&self.writer);
+        Ok(Some(Box::new(move |phase: &Phase| {
+            let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+            if writer.stream_failed {
+                return;
+            }
+            match writer.write_phase(phase) {
+                Ok(()) => writer.streamed += 1,
+                Err(err) => {
+                    warn!(
+                        "Failed to stream phase {}, the remaining phases are written with the results: {err}",
+                        phase.get_name()
+                    );
+                    writer.stream_failed = true;
+                }
+            }
+        })))
+    }
+
+    fn display_results(
+        &mut self,
+        cmd: &[String],
+        token_pattern: &str,
+        results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
+    ) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        writer.write_run(cmd, token_pattern)?;
+
+        for phase in results.phases.iter().skip(writer.streamed) {
+            writer.write_phase(phase)?;
+        }
+
+        writer.write(&Record::Summary {
+            timestamp: results.timestamp,
+            duration_ms: results.duration_ms,
+            exit_code: results.exit_code,
+            phases: results.phases.len(),
+            budgets,
+        })?;
+        println!("JSON Lines written to: {}", self.filename);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::{Metric, PhaseSample, PhaseToken};
+    use joule_profiler_core::unit::{MetricUnit, Unit, UnitPrefix};
+    use serde_json::Value;
+    use tempfile::NamedTempFile;
+
+    fn metric(value: u64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        Metric::new("PACKAGE-0", value, unit, "rapl")
+    }
+
+    fn phase(index: usize, samples: Vec<PhaseSample>) -> Phase {
+        Phase {
+            index,
+            start_token: PhaseToken::Start,
+            end_token: PhaseToken::End,
+            timestamp: 1000,
+            duration_ms: 2,
+            start_token_line: None,
+            end_token_line: None,
+            metrics: vec![metric(10)],
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples,
+        }
+    }
+
+    fn results(phases: Vec<Phase>) -> ProfilerResults {
+        ProfilerResults {
+            timestamp: 1000,
+            duration_ms: 4,
+            exit_code: 0,
+            phases,
+        }
+    }
+
+    fn output() -> (JsonLinesOutput, NamedTempFile) {
+        let tmp = NamedTempFile::new().unwrap();
+        let output =
+            JsonLinesOutput::try_new(Some(tmp.path().to_str().unwrap().to_owned())).unwrap();
+        (output, tmp)
+    }
+
+    fn records(tmp: &NamedTempFile) -> Vec<Value> {
+        std::fs::read_to_string(tmp.path())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn records_are_written_in_order() {
+        let (mut output, tmp) = output();
+        let sample = PhaseSample {
+            timestamp: 1000,
+            duration_us: 2000,
+            metrics: vec![metric(10)],
+        };
+
+        output
+            .display_results(
+                &["sleep".into(), "1".into()],
+                ".*",
+                &results(vec![phase(0, vec![sample])]),
+                None,
+            )
+            .unwrap();
+
+        let records = records(&tmp);
+        let types: Vec<&str> = records
+            .iter()
+            .map(|record| record["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, ["run", "phase", "sample", "summary"]);
+        assert_eq!(records[0]["command"], "sleep 1");
+        assert_eq!(records[1]["metrics"][0]["name"], "PACKAGE-0");
+        assert_eq!(records[2]["phase_index"], 0);
+        assert_eq!(records[3]["phases"], 1);
+        assert!(records[3].get("budgets").is_none());
+    }
+
+    #[test]
+    fn streamed_phases_are_not_written_twice() {
+        let (mut output, tmp) = output();
+        let cmd = ["cmd".to_owned()];
+        let mut listener = output.phase_listener(&cmd, ".*").unwrap().unwrap();
+
+        listener(&phase(0, Vec::new()));
+        assert_eq!(records(&tmp).len(), 2);
+
+        output
+            .display_results(
+                &cmd,
+                ".*",
+                &results(vec![phase(0, Vec::new()), phase(1, Vec::new())]),
+                None,
+            )
+            .unwrap();
+
+        let records = records(&tmp);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[1]["index"], 0);
+        assert_eq!(records[2]["index"], 1);
+        assert_eq!(records[3]["type"], "summary");
+    }
+
+    #[test]
+    fn streaming_stops_after_a_failed_phase() {
+        let (mut output, tmp) = output();
+        let cmd = ["cmd".to_owned()];
+        let mut listener = output.phase_listener(&cmd, ".*").unwrap().unwrap();
+
+        let file = std::mem::replace(
+            &mut output.writer.lock().unwrap().file,
+            File::open(tmp.path()).unwrap(),
+        );
+        listener(&phase(0, Vec::new()));
+        output.writer.lock().unwrap().file = file;
+        listener(&phase(1, Vec::new()));
+        assert_eq!(records(&tmp).len(), 1);
+
+        output
+            .display_results(
+                &cmd,
+                ".*",
+                &results(vec![phase(0, Vec::new()), phase(1, Vec::new())]),
+                None,
+            )
+            .unwrap();
+
+        let records = records(&tmp);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[1]["index"], 0);
+        assert_eq!(records[2]["index"], 1);
+    }
+}
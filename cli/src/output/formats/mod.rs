@@ -2,18 +2,32 @@
 //!
 //! This module defines the supported output formats and provides utilities
 //! for selecting and displaying metrics collected by `JouleProfiler`.
-//! It includes built-in formats for terminal display, JSON export, and CSV export.
+//! It includes built-in formats for terminal display, JSON and JSON Lines export, CSV export,
+//! `OpenMetrics` export, `InfluxDB` line protocol export, HTML reports,
+//! Chrome Trace Event timelines and energy flame graphs.
 //!
 //! # Overview
 //!
 //! - [`OutputFormat`] — Enum representing the available output formats.
-//! - `csv`, `json`, `terminal` — Submodules implementing the actual display logic for default output formats.
+//! - [`OutputSpec`] — Output requested with `--output FORMAT[:PATH]`.
+//! - `csv`, `flamegraph`, `html`, `influx`, `json`, `jsonl`, `openmetrics`, `terminal`, `trace` — Submodules implementing the actual display logic for default output formats.
 
 use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+use crate::CliArgs;
 
 pub mod csv;
+pub mod flamegraph;
+pub mod html;
+pub mod influx;
 pub mod json;
+pub mod jsonl;
+pub mod openmetrics;
 pub mod terminal;
+pub mod trace;
 
 /// Represents the supported output formats for `JouleProfiler`.
 ///
@@ -26,15 +40,36 @@ pub mod terminal;
 ///
 /// - `Terminal` — Display metrics directly in the terminal (default).
 /// - `Json` — Export metrics as JSON for easy parsing or integration.
+/// - `JsonLines` — Stream metrics as JSON Lines records while profiling.
 /// - `Csv` — Export metrics in CSV format for spreadsheets or analysis.
-#[derive(Debug, Clone, Copy, Default)]
+/// - `OpenMetrics` — Export metrics as labeled series for Prometheus.
+/// - `Influx` — Export metrics as `InfluxDB` line protocol points.
+/// - `Html` — Export metrics as a standalone HTML report with charts.
+/// - `ChromeTrace` — Export phases and power as a Chrome Trace Event timeline.
+/// - `FlameGraph` — Export phases as folded stacks or an SVG flame graph weighted by energy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 pub enum OutputFormat {
     #[default]
     Terminal,
 
     Json,
 
+    #[value(name = "jsonl")]
+    JsonLines,
+
     Csv,
+
+    #[value(name = "openmetrics")]
+    OpenMetrics,
+
+    Influx,
+
+    Html,
+
+    ChromeTrace,
+
+    #[value(name = "flamegraph")]
+    FlameGraph,
 }
 
 impl Display for OutputFormat {
@@ -42,19 +77,135 @@ impl Display for OutputFormat {
         f.write_str(match self {
             OutputFormat::Terminal => "Terminal",
             OutputFormat::Json => "Json",
+            OutputFormat::JsonLines => "JSON Lines",
             OutputFormat::Csv => "CSV",
+            OutputFormat::OpenMetrics => "OpenMetrics",
+            OutputFormat::Influx => "InfluxDB",
+            OutputFormat::Html => "HTML",
+            OutputFormat::ChromeTrace => "Chrome Trace",
+            OutputFormat::FlameGraph => "Flame graph",
         })?;
         Ok(())
     }
 }
 
+/// Output requested with `--output FORMAT[:PATH]`, e.g. `csv` or `json:results.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputSpec {
+    /// Format of the output.
+    pub format: OutputFormat,
+
+    /// Path of the output file, `None` for the default file of the format.
+    pub path: Option<String>,
+}
+
+impl FromStr for OutputSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> std::result::Result<Self, Self::Err> {
+        let (format, path) = match spec.split_once(':') {
+            Some((format, path)) => (format, Some(path)),
+            None => (spec, None),
+        };
+        let format = <OutputFormat as ValueEnum>::from_str(format, true).map_err(|_| {
+            let formats: Vec<_> = OutputFormat::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value)
+                .map(|value| value.get_name().to_owned())
+                .collect();
+            format!(
+                "unknown output format '{format}', expected one of {}",
+                formats.join(", ")
+            )
+        })?;
+
+        match path {
+            Some("") => Err(format!("empty path for the {format} output")),
+            Some(_) if format == OutputFormat::Terminal => {
+                Err("the terminal output has no path".into())
+            }
+            _ => Ok(Self {
+                format,
+                path: path.map(str::to_owned),
+            }),
+        }
+    }
+}
+
+/// Determine the outputs from the flags.
+///
+/// The format flag, or the terminal if none is set, gives the first output unless only
+/// `--output` options are used, which then give the other outputs in order.
+pub fn output_specs(cli: &CliArgs) -> Vec<OutputSpec> {
+    let format = output_format(cli);
+    let mut specs = Vec::with_capacity(cli.outputs.len() + 1);
+    if cli.outputs.is_empty() || format != OutputFormat::Terminal {
+        specs.push(OutputSpec {
+            format,
+            path: cli.output_file().map(str::to_owned),
+        });
+    }
+    specs.extend(cli.outputs.iter().cloned());
+    specs
+}
+
 /// Determine output format from flags
-pub fn output_format(json: bool, csv: bool) -> OutputFormat {
-    if json {
+pub fn output_format(cli: &CliArgs) -> OutputFormat {
+    if cli.json {
         OutputFormat::Json
-    } else if csv {
+    } else if cli.jsonl {
+        OutputFormat::JsonLines
+    } else if cli.csv {
         OutputFormat::Csv
+    } else if cli.openmetrics {
+        OutputFormat::OpenMetrics
+    } else if cli.influx {
+        OutputFormat::Influx
+    } else if cli.html {
+        OutputFormat::Html
+    } else if cli.chrome_trace {
+        OutputFormat::ChromeTrace
+    } else if cli.flamegraph {
+        OutputFormat::FlameGraph
     } else {
         OutputFormat::Terminal
     }
 }
+
+/// Escape text for HTML and SVG content and attributes.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_spec_with_path() {
+        assert_eq!(
+            "json:out/results.json".parse(),
+            Ok(OutputSpec {
+                format: OutputFormat::Json,
+                path: Some("out/results.json".into()),
+            })
+        );
+        assert_eq!(
+            "chrome-trace".parse(),
+            Ok(OutputSpec {
+                format: OutputFormat::ChromeTrace,
+                path: None,
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_output_specs_are_rejected() {
+        assert!("xml".parse::<OutputSpec>().unwrap_err().contains("jsonl"));
+        assert!("csv:".parse::<OutputSpec>().is_err());
+        assert!("terminal:out.txt".parse::<OutputSpec>().is_err());
+    }
+}
@@ -0,0 +1,572 @@
+//! `OpenMetrics` text exposition of the results.
+//!
+//! Each metric of a phase is a sample labeled with its `source`, `sensor`, `phase` and
+//! `command`, in a family named after its base unit (e.g. `joule_profiler_energy_joules`),
+//! values being converted to the base unit. The exposition is written to a file, readable by the
+//! `node_exporter` textfile collector, and can be served on `/metrics` for Prometheus to scrape.
+//!
+//! While profiling, the endpoint serves the samples of the phases completed so far, the exit code
+//! and budgets being added once the run is complete. Each request is answered on its own thread,
+//! so that a slow client does not hold back the scrapes.
+//!
+//! Samples carry no timestamp, the textfile collector rejecting them.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use joule_profiler_core::budget::BudgetReport;
+use joule_profiler_core::fs::{
+    create_file_with_user_permissions, default_results_filename, get_absolute_path,
+};
+use joule_profiler_core::types::{Phase, PhaseListener, ProfilerResults};
+use joule_profiler_core::unit::Unit;
+use log::{trace, warn};
+
+use crate::output::displayer::{Displayer, DisplayerError};
+
+type Result<T> = std::result::Result<T, DisplayerError>;
+
+/// Prefix of the metric families.
+const PREFIX: &str = "joule_profiler";
+
+/// Content type of the exposition served on `/metrics`.
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Time after which a silent or stalled connection to the endpoint is dropped.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `OpenMetrics` output writer to a file and an optional HTTP endpoint.
+pub struct OpenMetricsOutput {
+    /// Path to the output file, `None` to only serve the metrics.
+    filename: Option<String>,
+
+    /// Latest exposition, shared with the HTTP endpoint.
+    exposition: Arc<Mutex<String>>,
+
+    /// Address and thread of the HTTP endpoint, if enabled.
+    server: Option<(SocketAddr, JoinHandle<()>)>,
+}
+
+impl OpenMetricsOutput {
+    /// Create an `OpenMetrics` output writer.
+    ///
+    /// The exposition is written to `output_file`, or to `data<TIMESTAMP>.prom` if no endpoint
+    /// is enabled. With `listen`, the exposition is served on `http://<listen>/metrics` from now
+    /// on, an empty exposition being served until the first phase is complete.
+    pub fn try_new(output_file: Option<String>, listen: Option<&str>) -> Result<Self> {
+        let filename = match (output_file, listen) {
+            (Some(filename), _) => Some(get_absolute_path(&filename)?),
+            (None, None) => Some(get_absolute_path(&default_results_filename("prom"))?),
+            (None, Some(_)) => None,
+        };
+
+        let exposition = Arc::new(Mutex::new(String::from("# EOF\n")));
+        let server = listen
+            .map(|address| -> Result<_> {
+                let listener = TcpListener::bind(address)?;
+                let address = listener.local_addr()?;
+                let exposition = exposition.clone();
+                let handle = thread::spawn(move || serve(&listener, &exposition));
+                Ok((address, handle))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            filename,
+            exposition,
+            server,
+        })
+    }
+
+    /// Write the exposition to the output file.
+    ///
+    /// The exposition is written to a temporary file renamed afterwards, so that the textfile
+    /// collector never reads a partial file.
+    fn write_file(&self, exposition: &str) -> Result<()> {
+        let Some(filename) = &self.filename else {
+            return Ok(());
+        };
+
+        let tmp_filename = format!("{filename}.tmp");
+        let mut file = create_file_with_user_permissions(&tmp_filename)?;
+        file.write_all(exposition.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_filename, filename)?;
+
+        println!("OpenMetrics written to: {filename}");
+        Ok(())
+    }
+
+    /// Serve the metrics until the profiler is interrupted, if the HTTP endpoint is enabled.
+    fn wait(&mut self) {
+        if let Some((address, handle)) = self.server.take() {
+            println!("Serving metrics on http://{address}/metrics, press Ctrl-C to stop");
+            if handle.join().is_err() {
+                warn!("Metrics endpoint stopped unexpectedly");
+            }
+        }
+    }
+}
+
+impl Displayer for OpenMetricsOutput {
+    fn phase_listener(
+        &mut self,
+        cmd: &[String],
+        _token_pattern: &str,
+    ) -> Result<Option<PhaseListener>> {
+        if self.server.is_none() {
+            return Ok(None);
+        }
+
+        let command = cmd.join(" ");
+        let served = Arc::clone(&self.exposition);
+        let mut exposition = Exposition::default();
+        Ok(Some(Box::new(move |phase: &Phase| {
+            exposition.phase(phase, &command);
+            publish(&served, &exposition.render());
+        })))
+    }
+
+    fn display_results(
+        &mut self,
+        cmd: &[String],
+        _token_pattern: &str,
+        results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
+    ) -> Result<()> {
+        let exposition = render(cmd, results, budgets);
+        publish(&self.exposition, &exposition);
+        self.write_file(&exposition)?;
+        self.wait();
+        Ok(())
+    }
+}
+
+/// A metric family and its samples.
+struct Family {
+    name: String,
+    unit: Option<&'static str>,
+    help: &'static str,
+    samples: Vec<String>,
+}
+
+/// Families of the exposition, in order of first appearance.
+#[derive(Default)]
+struct Exposition {
+    families: Vec<Family>,
+}
+
+impl Exposition {
+    /// Add a sample to the family, declaring it on its first sample.
+    fn sample(
+        &mut self,
+        (name, unit, help): (String, Option<&'static str>, &'static str),
+        labels: &[(&str, &str)],
+        value: f64,
+    ) {
+        let index = if let Some(index) = self.families.iter().position(|f| f.name == name) {
+            index
+        } else {
+            self.families.push(Family {
+                name,
+                unit,
+                help,
+                samples: Vec::new(),
+            });
+            self.families.len() - 1
+        };
+        let family = &mut self.families[index];
+
+        let labels = labels
+            .iter()
+            .map(|(label, value)| format!("{label}=\"{}\"", escape_label(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        family.samples.push(format!(
+            "{}{{{labels}}} {}",
+            family.name,
+            format_value(value)
+        ));
+    }
+
+    /// Add the samples of a completed phase.
+    fn phase(&mut self, phase: &Phase, command: &str) {
+        let name = phase.get_name();
+        let index = phase.index.to_string();
+
+        for metric in &phase.metrics {
+            let (family, scale) = unit_family(metric.unit.unit);
+            self.sample(
+                family,
+                &[
+                    ("source", &metric.source),
+                    ("sensor", &metric.name),
+                    ("phase", &name),
+                    ("phase_index", &index),
+                    ("command", command),
+                ],
+                metric.value.as_f64() * metric.unit.prefix.factor() * scale,
+            );
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        self.sample(
+            (
+                format!("{PREFIX}_phase_duration_seconds"),
+                Some("seconds"),
+                "Duration of the phase.",
+            ),
+            &[
+                ("phase", &name),
+                ("phase_index", &index),
+                ("command", command),
+            ],
+            phase.duration_ms as f64 / 1e3,
+        );
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for family in &self.families {
+            let _ = writeln!(out, "# TYPE {} gauge", family.name);
+            if let Some(unit) = family.unit {
+                let _ = writeln!(out, "# UNIT {} {unit}", family.name);
+            }
+            let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+            for sample in &family.samples {
+                let _ = writeln!(out, "{sample}");
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// Render the results as an `OpenMetrics` exposition.
+fn render(cmd: &[String], results: &ProfilerResults, budgets: Option<&BudgetReport>) -> String {
+    let command = cmd.join(" ");
+    let mut exposition = Exposition::default();
+
+    for phase in &results.phases {
+        exposition.phase(phase, &command);
+    }
+
+    exposition.sample(
+        (
+            format!("{PREFIX}_exit_code"),
+            None,
+            "Exit code of the profiled command.",
+        ),
+        &[("command", &command)],
+        f64::from(results.exit_code),
+    );
+
+    for check in budgets.iter().flat_map(|budgets| &budgets.checks) {
+        exposition.sample(
+            (
+                format!("{PREFIX}_budget_passed"),
+                None,
+                "Whether the budget is respected (1) or exceeded (0).",
+            ),
+            &[("assertion", &check.assertion), ("command", &command)],
+            if check.passed { 1.0 } else { 0.0 },
+        );
+    }
+
+    exposition.render()
+}
+
+/// Family of the metrics of a base unit, with the factor scaling the values to the family unit.
+fn unit_family(unit: Unit) -> ((String, Option<&'static str>, &'static str), f64) {
+    let (suffix, unit, help, scale) = match unit {
+        Unit::Joule => ("energy_joules", Some("joules"), "Energy of the phase.", 1.0),
+        Unit::Watt => ("power_watts", Some("watts"), "Power of the phase.", 1.0),
+        Unit::Second => ("time_seconds", Some("seconds"), "Time of the phase.", 1.0),
+        Unit::Count => ("events", None, "Events counted during the phase.", 1.0),
+        Unit::Byte => ("bytes", Some("bytes"), "Data size of the phase.", 1.0),
+        Unit::Percent => ("ratio", Some("ratio"), "Ratio of the phase.", 0.01),
+        Unit::JouleSecond => (
+            "energy_delay_joule_seconds",
+            Some("joule_seconds"),
+            "Energy-delay product of the phase.",
+            1.0,
+        ),
+        Unit::JouleSecondSquared => (
+            "energy_delay_squared_joule_seconds_squared",
+            Some("joule_seconds_squared"),
+            "Energy-delay-squared product of the phase.",
+            1.0,
+        ),
+        Unit::JoulePerCount => (
+            "joules_per_event",
+            None,
+            "Energy per counted event of the phase.",
+            1.0,
+        ),
+        Unit::CountPerJoule => (
+            "events_per_joule",
+            None,
+            "Counted events per joule of the phase.",
+            1.0,
+        ),
+        Unit::GramCo2e => (
+            "emissions_grams",
+            Some("grams"),
+            "Estimated emissions of the phase in CO2 equivalent.",
+            1.0,
+        ),
+        Unit::Currency => ("cost", None, "Estimated cost of the phase.", 1.0),
+    };
+    ((format!("{PREFIX}_{suffix}"), unit, help), scale)
+}
+
+/// Escape a label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Format a sample value, with the special values spelled as in the exposition format.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.into()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Replace the exposition served by the HTTP endpoint.
+fn publish(served: &Mutex<String>, exposition: &str) {
+    let mut served = served.lock().unwrap_or_else(PoisonError::into_inner);
+    exposition.clone_into(&mut served);
+}
+
+/// Serve the exposition on `/metrics`, answering each incoming connection on its own thread.
+fn serve(listener: &TcpListener, exposition: &Arc<Mutex<String>>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let exposition = Arc::clone(exposition);
+                thread::spawn(move || {
+                    if let Err(err) = respond(stream, &exposition) {
+                        warn!("Cannot serve metrics request: {err}");
+                    }
+                });
+            }
+            Err(err) => warn!("Cannot accept metrics request: {err}"),
+        }
+    }
+}
+
+/// Answer a single HTTP request, closing the connection afterwards.
+///
+/// The connection is dropped if the request or the response stall for [`CONNECTION_TIMEOUT`].
+fn respond(mut stream: TcpStream, exposition: &Mutex<String>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+    stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    trace!("Metrics request: {}", request_line.trim_end());
+
+    let mut request = request_line.split_whitespace();
+    let method = request.next();
+    let path = request.next().and_then(|target| target.split('?').next());
+
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            CONTENT_TYPE,
+            exposition
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+        ),
+        _ => (
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            "Metrics are served on /metrics\n".to_owned(),
+        ),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::budget::BudgetReport;
+    use joule_profiler_core::types::{Metric, Phase, PhaseToken};
+    use joule_profiler_core::unit::{MetricUnit, UnitPrefix};
+    use std::io::Read;
+
+    fn results() -> ProfilerResults {
+        let micro_joules = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        let count = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Count,
+        };
+        ProfilerResults {
+            timestamp: 0,
+            duration_ms: 1500,
+            exit_code: 0,
+            phases: vec![Phase {
+                index: 0,
+                start_token: PhaseToken::Start,
+                end_token: PhaseToken::Token("__A__".into()),
+                timestamp: 0,
+                duration_ms: 1500,
+                start_token_line: None,
+                end_token_line: None,
+                metrics: vec![
+                    Metric::new("PACKAGE-0", 2_500_000u64, micro_joules, "rapl"),
+                    Metric::new("INSTRUCTIONS", 42u64, count, "perf_event"),
+                ],
+                total_energy_rule: None,
+                power: Vec::new(),
+                samples: Vec::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn metrics_are_labeled_series_in_base_units() {
+        let exposition = render(&["./bench".into(), "-n".into()], &results(), None);
+
+        assert!(exposition.contains(
+            "# TYPE joule_profiler_energy_joules gauge\n# UNIT joule_profiler_energy_joules joules\n"
+        ));
+        assert!(exposition.contains(
+            "joule_profiler_energy_joules{source=\"rapl\",sensor=\"PACKAGE-0\",phase=\"START -> __A__\",phase_index=\"0\",command=\"./bench -n\"} 2.5\n"
+        ));
+        assert!(
+            exposition
+                .contains("joule_profiler_events{source=\"perf_event\",sensor=\"INSTRUCTIONS\"")
+        );
+        assert!(exposition.contains("joule_profiler_phase_duration_seconds{phase=\"START -> __A__\",phase_index=\"0\",command=\"./bench -n\"} 1.5\n"));
+        assert!(
+            exposition.ends_with("joule_profiler_exit_code{command=\"./bench -n\"} 0\n# EOF\n")
+        );
+    }
+
+    #[test]
+    fn budgets_are_exposed() {
+        let results = results();
+        let budgets = BudgetReport::check(&["PACKAGE-0 < 2J".parse().unwrap()], &results);
+        let exposition = render(&["cmd".into()], &results, Some(&budgets));
+
+        assert!(exposition.contains(
+            "joule_profiler_budget_passed{assertion=\"PACKAGE-0 < 2J\",command=\"cmd\"} 0\n"
+        ));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label("a \"b\"\\\nc"), "a \\\"b\\\"\\\\\\nc");
+        assert_eq!(format_value(f64::INFINITY), "+Inf");
+    }
+
+    #[test]
+    fn exposition_is_written_to_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bench.prom");
+        let mut output =
+            OpenMetricsOutput::try_new(Some(path.to_str().unwrap().to_owned()), None).unwrap();
+
+        output
+            .display_results(&["cmd".into()], ".*", &results(), None)
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# TYPE joule_profiler_energy_joules gauge\n"));
+        assert!(!dir.path().join("bench.prom.tmp").exists());
+    }
+
+    fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn exposition_is_served_on_metrics() {
+        let output = OpenMetricsOutput::try_new(None, Some("127.0.0.1:0")).unwrap();
+        let address = output.server.as_ref().unwrap().0;
+        assert!(get(address, "/metrics").ends_with("\r\n\r\n# EOF\n"));
+
+        publish(
+            &output.exposition,
+            &render(&["cmd".into()], &results(), None),
+        );
+        let response = get(address, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(CONTENT_TYPE));
+        assert!(response.contains("sensor=\"PACKAGE-0\""));
+
+        assert!(get(address, "/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[test]
+    fn completed_phases_are_served_while_profiling() {
+        let mut output = OpenMetricsOutput::try_new(None, Some("127.0.0.1:0")).unwrap();
+        let address = output.server.as_ref().unwrap().0;
+        let mut listener = output
+            .phase_listener(&["cmd".into()], ".*")
+            .unwrap()
+            .unwrap();
+
+        listener(&results().phases[0]);
+
+        let response = get(address, "/metrics");
+        assert!(response.contains("sensor=\"PACKAGE-0\",phase=\"START -> __A__\""));
+        assert!(response.ends_with("} 1.5\n# EOF\n"));
+        assert!(!response.contains("joule_profiler_exit_code"));
+    }
+
+    #[test]
+    fn phases_are_not_listened_without_endpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bench.prom");
+        let mut output =
+            OpenMetricsOutput::try_new(Some(path.to_str().unwrap().to_owned()), None).unwrap();
+
+        assert!(
+            output
+                .phase_listener(&["cmd".into()], ".*")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn idle_connection_does_not_block_requests() {
+        let output = OpenMetricsOutput::try_new(None, Some("127.0.0.1:0")).unwrap();
+        let address = output.server.as_ref().unwrap().0;
+
+        let _idle = TcpStream::connect(address).unwrap();
+        assert!(get(address, "/metrics").starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+}
@@ -1,70 +1,141 @@
+//! Terminal display of the results.
+//!
+//! Energies, powers, emissions and sizes are scaled to the most readable SI prefix, and each
+//! energy of a phase is followed by its share of the energy of the sensor over all the phases.
+//! The detailed layout prints a section per phase, the table layout a row per phase and a column
+//! per metric. Colors are only used on a terminal unless forced.
+
 use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::IsTerminal;
 
+use clap::{ColorChoice, ValueEnum};
 use joule_profiler_core::{
+    budget::BudgetReport,
     sensor::Sensor,
-    types::{Metric, Phase, ProfilerResults},
+    types::{Metric, Phase, PowerStats, ProfilerResults},
+    unit::{MetricUnit, Unit, UnitPrefix},
 };
 
+use crate::compare::Comparison;
 use crate::output::displayer::{Displayer, DisplayerError};
 
 /// Constants for formatting
 const BORDER_DOUBLE: &str = "═";
 const BORDER_SINGLE: &str = "─";
 const BOX_WIDTH: usize = 50;
+const HISTOGRAM_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Width of the bars of the energy shares.
+const SHARE_BAR_WIDTH: usize = 20;
+
+/// Prefixes the scaled units are picked from.
+const SCALE_PREFIXES: [UnitPrefix; 6] = [
+    UnitPrefix::Micro,
+    UnitPrefix::Milli,
+    UnitPrefix::None,
+    UnitPrefix::Kilo,
+    UnitPrefix::Mega,
+    UnitPrefix::Giga,
+];
+
+/// ANSI styles.
+const BOLD: &str = "1";
+const CYAN: &str = "36";
+const GREEN: &str = "32";
+const RED: &str = "31";
 
 type Result<T> = std::result::Result<T, DisplayerError>;
 
+/// Layout of the phases in the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TerminalLayout {
+    /// One section per phase, with the metrics grouped by source.
+    #[default]
+    Detailed,
+
+    /// One row per phase and one column per metric.
+    Table,
+}
+
 #[derive(Debug, Clone, Default)]
-pub struct TerminalOutput;
+pub struct TerminalOutput {
+    /// Layout of the phases.
+    layout: TerminalLayout,
+
+    /// Whether ANSI colors are used.
+    color: bool,
+}
 
 impl TerminalOutput {
+    /// Create a terminal output, `ColorChoice::Auto` enabling colors if stdout is a terminal
+    /// and `NO_COLOR` is not set.
+    pub fn new(layout: TerminalLayout, color: ColorChoice) -> Self {
+        let color = match color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+        };
+        Self { layout, color }
+    }
+
+    /// Apply an ANSI style to a text if colors are enabled.
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color {
+            format!("\x1b[{style}m{text}\x1b[0m")
+        } else {
+            text.to_owned()
+        }
+    }
+
     /// Display command header
-    fn display_command(command: &[String]) {
+    fn display_command(&self, command: &[String]) {
         if !command.is_empty() {
-            Self::print_header("Command");
+            self.print_header("Command");
             println!("  {}", command.join(" "));
         }
     }
 
-    /// Print a formatted header
-    fn print_header(title: &str) {
-        println!("╔{}╗", BORDER_DOUBLE.repeat(BOX_WIDTH - 2));
-        println!("║  {:<width$} ║", title, width = BOX_WIDTH - 5);
-        println!("╚{}╝", BORDER_DOUBLE.repeat(BOX_WIDTH - 2));
+    /// Print a formatted header, widened to fit long titles
+    fn print_header(&self, title: &str) {
+        let width = BOX_WIDTH.max(title.chars().count() + 5);
+        println!("╔{}╗", BORDER_DOUBLE.repeat(width - 2));
+        println!(
+            "║  {} ║",
+            self.paint(&format!("{:<width$}", title, width = width - 5), BOLD)
+        );
+        println!("╚{}╝", BORDER_DOUBLE.repeat(width - 2));
     }
 
-    /// Print a formatted sub-header
-    fn print_subheader(title: &str, prefix: &str) {
+    /// Print a formatted sub-header, widened to fit long titles
+    fn print_subheader(&self, title: &str, prefix: &str) {
+        let width = BOX_WIDTH.max(prefix.len() + title.chars().count() + 3);
         println!(
             "{}┌{}┐",
             prefix,
-            BORDER_SINGLE.repeat(BOX_WIDTH - prefix.len() - 2)
+            BORDER_SINGLE.repeat(width - prefix.len() - 2)
         );
         println!(
-            "{}│ {:<width$}│",
+            "{}│ {}│",
             prefix,
-            title,
-            width = BOX_WIDTH - prefix.len() - 3
+            self.paint(
+                &format!("{:<width$}", title, width = width - prefix.len() - 3),
+                CYAN
+            )
         );
         println!(
             "{}└{}┘",
             prefix,
-            BORDER_SINGLE.repeat(BOX_WIDTH - prefix.len() - 2)
+            BORDER_SINGLE.repeat(width - prefix.len() - 2)
         );
     }
 
     /// Display a single measurement result
-    fn display_phase(phase: &Phase, prefix: &str) {
+    fn display_phase(&self, phase: &Phase, totals: &EnergyTotals, prefix: &str) {
         println!();
 
-        let mut keys: Vec<_> = phase
-            .metrics
-            .iter()
-            .map(|metric| &metric.name)
-            .cloned()
-            .collect();
-        keys.sort_unstable();
-
         let mut metrics_per_source: HashMap<&String, Vec<&Metric>> = HashMap::new();
         for metric in &phase.metrics {
             metrics_per_source
@@ -78,27 +149,83 @@ impl TerminalOutput {
         metrics_per_source.sort_by_key(|(source, _)| *source);
 
         for (source, metrics) in metrics_per_source {
-            Self::print_subheader(source, prefix);
+            let scope = metrics
+                .first()
+                .map(|metric| metric.scope)
+                .unwrap_or_default();
+            self.print_subheader(&format!("{source} ({scope})"), prefix);
 
             for metric in metrics {
+                let share = totals
+                    .share(metric)
+                    .map(|share| format!("  {}", self.share_bar(share)))
+                    .unwrap_or_default();
                 println!(
-                    "{}  {:<20}: {:10.6} {}",
-                    prefix, metric.name, metric.value, metric.unit
+                    "{}  {:<20}: {}{}",
+                    prefix,
+                    metric.name,
+                    format_metric(metric),
+                    share
                 );
             }
         }
+
+        if !phase.power.is_empty() {
+            self.print_subheader("Power (W)", prefix);
+            for stats in &phase.power {
+                Self::display_power(stats, prefix);
+            }
+        }
+    }
+
+    /// Format a share of the energy as a percentage followed by a bar.
+    fn share_bar(&self, share: f64) -> String {
+        format!(
+            "{:>5.1}% {}",
+            share * 100.0,
+            self.paint(&share_bar(share, SHARE_BAR_WIDTH), GREEN)
+        )
+    }
+    /// Display the power distribution of a sensor on one line, followed by its histogram if any
+    fn display_power(stats: &PowerStats, prefix: &str) {
+        println!(
+            "{}  {:<20}: p50 {:.2} p90 {:.2} p99 {:.2} min {:.2} max {:.2} σ {:.2} ({} samples)",
+            prefix,
+            stats.name,
+            stats.p50,
+            stats.p90,
+            stats.p99,
+            stats.min,
+            stats.max,
+            stats.std_dev,
+            stats.samples
+        );
+
+        if let Some(histogram) = &stats.histogram {
+            let highest = histogram.counts.iter().copied().max().unwrap_or_default();
+            let bars: String = histogram
+                .counts
+                .iter()
+                .map(|&count| match count {
+                    0 => ' ',
+                    _ => HISTOGRAM_BARS[(count * HISTOGRAM_BARS.len() - 1) / highest],
+                })
+                .collect();
+            println!(
+                "{}  {:<20}  {:.2} [{}] {:.2}",
+                prefix, "", stats.min, bars, stats.max
+            );
+        }
     }
 
     /// Display phase header with token information
-    fn display_phase_header(phase: &Phase, prefix: &str) {
+    fn display_phase_header(&self, phase: &Phase, prefix: &str) {
         let phase_name = phase.get_name();
         println!();
         if prefix.is_empty() {
-            println!("╔{}╗", BORDER_DOUBLE.repeat(BOX_WIDTH - 2));
-            println!("║  Phase: {:<width$} ║", phase_name, width = BOX_WIDTH - 12);
-            println!("╚{}╝", BORDER_DOUBLE.repeat(BOX_WIDTH - 2));
+            self.print_header(&format!("Phase: {phase_name}"));
         } else {
-            Self::print_subheader(&format!("Phase: {phase_name}"), prefix);
+            self.print_subheader(&format!("Phase: {phase_name}"), prefix);
         }
 
         // Display token information
@@ -122,6 +249,53 @@ impl TerminalOutput {
         println!("{}  {:<20}: {:>10}", prefix, "Start token", start_info);
 
         println!("{}  {:<20}: {:>10}", prefix, "End token", end_info);
+
+        if let Some(rule) = &phase.total_energy_rule {
+            println!("{}  {:<20}: {}", prefix, "Total energy", rule);
+        }
+    }
+
+    /// Display the outcome of the budgets
+    fn display_budgets(&self, budgets: &BudgetReport) {
+        println!();
+        self.print_header("Budgets");
+
+        for check in &budgets.checks {
+            let status = if check.passed {
+                self.paint("PASS", GREEN)
+            } else {
+                self.paint("FAIL", RED)
+            };
+            let phase = check
+                .phase
+                .as_ref()
+                .map(|phase| format!(" [{phase}]"))
+                .unwrap_or_default();
+            let outcome = match (check.value, check.unit, &check.reason) {
+                (_, _, Some(reason)) => reason.clone(),
+                (Some(value), Some(unit), None) => format!("{value:.6} {unit}"),
+                _ => String::new(),
+            };
+            println!("  {status}  {}{phase}: {outcome}", check.assertion);
+        }
+
+        let violations = budgets.violations().count();
+        if violations > 0 {
+            println!();
+            println!("  {violations} budget(s) exceeded");
+        }
+    }
+
+    /// Display the phases as a table, one row per phase and one column per metric.
+    fn display_table(&self, results: &ProfilerResults) {
+        println!();
+        for (i, line) in phases_table(results).lines().enumerate() {
+            if i == 0 {
+                println!("{}", self.paint(line, BOLD));
+            } else {
+                println!("{line}");
+            }
+        }
     }
 }
 
@@ -131,8 +305,9 @@ impl Displayer for TerminalOutput {
         cmd: &[String],
         _token_pattern: &str,
         results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
     ) -> Result<()> {
-        Self::display_command(cmd);
+        self.display_command(cmd);
         println!(" {}", BORDER_SINGLE.repeat(BOX_WIDTH - 2));
 
         let prefix = "";
@@ -143,9 +318,86 @@ impl Displayer for TerminalOutput {
         );
         println!("{}  {:<20}: {:>10}", prefix, "Exit code", results.exit_code);
 
-        for phase in &results.phases {
-            Self::display_phase_header(phase, prefix);
-            Self::display_phase(phase, prefix);
+        match self.layout {
+            TerminalLayout::Detailed => {
+                let totals = EnergyTotals::new(&results.phases);
+                for phase in &results.phases {
+                    self.display_phase_header(phase, prefix);
+                    self.display_phase(phase, &totals, prefix);
+                }
+            }
+            TerminalLayout::Table => self.display_table(results),
+        }
+
+        if let Some(budgets) = budgets {
+            self.display_budgets(budgets);
+        }
+
+        Ok(())
+    }
+
+    fn display_comparison(&mut self, comparison: &Comparison) -> Result<()> {
+        self.print_header("Comparison");
+        println!("  {:<20}: {}", "Baseline", comparison.baseline);
+        if comparison.baseline_files.len() > 1 {
+            println!(
+                "  {:<20}: {}",
+                "Pooled files",
+                comparison.baseline_files.join(", ")
+            );
+        }
+        println!(
+            "  {:<20}: {}",
+            "Significance level", comparison.significance_level
+        );
+
+        for candidate in &comparison.candidates {
+            println!();
+            self.print_header(&format!("Candidate: {}", candidate.file));
+            if candidate.files.len() > 1 {
+                println!("  {:<20}: {}", "Pooled files", candidate.files.join(", "));
+            }
+
+            for phase in &candidate.phases {
+                println!();
+                self.print_subheader(&format!("Phase: {}", phase.name), "");
+
+                for (side, excluded) in [
+                    ("Baseline", &phase.baseline_excluded),
+                    ("Candidate", &phase.candidate_excluded),
+                ] {
+                    for e in excluded {
+                        println!(
+                            "  {:<20}: iteration {} of {} ({})",
+                            format!("{side} excluded"),
+                            e.iteration,
+                            e.file,
+                            e.reason
+                        );
+                    }
+                }
+
+                for metric in &phase.metrics {
+                    let relative_difference = metric
+                        .relative_difference
+                        .map_or_else(|| "n/a".to_string(), |d| format!("{:+.2}%", d * 100.0));
+                    let p_value = metric
+                        .test
+                        .map(|test| format!(", p={:.4}", test.p_value))
+                        .unwrap_or_default();
+
+                    println!(
+                        "  {:<20}: {:.3} -> {:.3} {} ({:+.3}, {})",
+                        format!("{}/{}", metric.source, metric.name),
+                        metric.baseline,
+                        metric.candidate,
+                        metric.unit,
+                        metric.absolute_difference,
+                        relative_difference
+                    );
+                    println!("  {:<20}  {}{}", "", metric.verdict, p_value);
+                }
+            }
         }
 
         Ok(())
@@ -157,7 +409,7 @@ impl Displayer for TerminalOutput {
             return Ok(());
         }
 
-        Self::print_header("Available Sensors");
+        self.print_header("Available Sensors");
 
         let mut sensors_by_source: HashMap<&String, Vec<&Sensor>> = HashMap::new();
         for sensor in sensors {
@@ -174,7 +426,7 @@ impl Displayer for TerminalOutput {
             let source_sensors = &sensors_by_source[source];
 
             println!();
-            Self::print_subheader(source, "");
+            self.print_subheader(source, "");
 
             println!("  {:<20} | {:<5}", "Name", "Unit");
             println!(" {}", BORDER_SINGLE.repeat(BOX_WIDTH - 2));
@@ -187,3 +439,312 @@ impl Displayer for TerminalOutput {
         Ok(())
     }
 }
+
+/// Energy of each sensor over all the phases, in joules.
+struct EnergyTotals {
+    /// Total energy by source and sensor name, only kept when there are several phases.
+    totals: HashMap<(String, String), f64>,
+}
+
+impl EnergyTotals {
+    fn new(phases: &[Phase]) -> Self {
+        let mut totals = HashMap::new();
+        if phases.len() > 1 {
+            for metric in phases.iter().flat_map(|phase| &phase.metrics) {
+                if let Some(joules) = joules(metric) {
+                    *totals
+                        .entry((metric.source.clone(), metric.name.clone()))
+                        .or_default() += joules;
+                }
+            }
+        }
+        Self { totals }
+    }
+
+    /// Share of the energy of the sensor over all the phases, `None` if it is not an energy.
+    fn share(&self, metric: &Metric) -> Option<f64> {
+        let joules = joules(metric)?;
+        let total = self
+            .totals
+            .get(&(metric.source.clone(), metric.name.clone()))?;
+        (*total > 0.0).then(|| joules / total)
+    }
+}
+
+/// Energy of a metric in joules, `None` if it is not an energy.
+fn joules(metric: &Metric) -> Option<f64> {
+    let joule = MetricUnit {
+        prefix: UnitPrefix::None,
+        unit: Unit::Joule,
+    };
+    metric.unit.convert(metric.value.as_f64(), joule)
+}
+
+/// Whether SI prefixes are meaningful for a unit.
+fn is_scalable(unit: Unit) -> bool {
+    matches!(unit, Unit::Joule | Unit::Watt | Unit::GramCo2e | Unit::Byte)
+}
+
+/// Unit with the prefix giving a value between 1 and 1000, or the closest one.
+///
+/// Units without meaningful prefixes are kept.
+fn scaled_unit(value: f64, unit: MetricUnit) -> MetricUnit {
+    if !is_scalable(unit.unit) || value == 0.0 || !value.is_finite() {
+        return unit;
+    }
+    let magnitude = value.abs() * unit.prefix.factor();
+    let prefix = SCALE_PREFIXES
+        .iter()
+        .rev()
+        .find(|prefix| magnitude >= prefix.factor())
+        .unwrap_or(&SCALE_PREFIXES[0]);
+    MetricUnit {
+        prefix: *prefix,
+        unit: unit.unit,
+    }
+}
+
+/// Format a value in a unit, converted to `target`.
+fn format_value(value: f64, unit: MetricUnit, target: MetricUnit) -> String {
+    match unit.convert(value, target) {
+        Some(scaled) if is_scalable(unit.unit) => format!("{scaled:>10.3} {target}"),
+        _ => format!("{value:10.6} {unit}"),
+    }
+}
+
+/// Format the value of a metric with its scaled unit.
+fn format_metric(metric: &Metric) -> String {
+    if is_scalable(metric.unit.unit) {
+        let value = metric.value.as_f64();
+        format_value(value, metric.unit, scaled_unit(value, metric.unit))
+    } else {
+        format!("{:10.6} {}", metric.value, metric.unit)
+    }
+}
+
+/// Bar of `width` characters filled proportionally to a share between 0 and 1.
+fn share_bar(share: f64, width: usize) -> String {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    let filled = ((share.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Table of the phases, one row per phase and one column per metric with its scaled unit.
+///
+/// The last row sums the energies when there are several phases.
+fn phases_table(results: &ProfilerResults) -> String {
+    let mut columns: Vec<(&str, &str, MetricUnit)> = Vec::new();
+    for metric in results.phases.iter().flat_map(|phase| &phase.metrics) {
+        if !columns
+            .iter()
+            .any(|(source, name, _)| *source == metric.source && *name == metric.name)
+        {
+            columns.push((&metric.source, &metric.name, metric.unit));
+        }
+    }
+    columns.sort_by_key(|(source, _, _)| *source);
+
+    let mut header = vec!["Phase".to_owned(), "Duration".to_owned()];
+    let mut rows: Vec<Vec<String>> = results
+        .phases
+        .iter()
+        .map(|phase| vec![phase.get_name(), format!("{} ms", phase.duration_ms)])
+        .collect();
+    let mut total_row = vec![
+        "All phases".to_owned(),
+        format!("{} ms", results.duration_ms),
+    ];
+
+    for (source, name, unit) in &columns {
+        let values: Vec<Option<f64>> = results
+            .phases
+            .iter()
+            .map(|phase| {
+                phase
+                    .metrics
+                    .iter()
+                    .find(|metric| metric.source == *source && metric.name == *name)
+                    .and_then(|metric| metric.unit.convert(metric.value.as_f64(), *unit))
+            })
+            .collect();
+        let largest = values.iter().flatten().fold(0.0_f64, |a, v| a.max(v.abs()));
+        let target = scaled_unit(largest, *unit);
+
+        header.push(format!("{name} ({target})"));
+        for (row, value) in rows.iter_mut().zip(&values) {
+            row.push(
+                value
+                    .and_then(|value| unit.convert(value, target))
+                    .map(|value| format_cell(value, unit.unit))
+                    .unwrap_or_default(),
+            );
+        }
+        let total = (unit.unit == Unit::Joule)
+            .then(|| values.iter().flatten().sum::<f64>())
+            .and_then(|total| unit.convert(total, target));
+        total_row.push(
+            total
+                .map(|total| format_cell(total, unit.unit))
+                .unwrap_or_default(),
+        );
+    }
+    if results.phases.len() > 1 {
+        rows.push(total_row);
+    }
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(header[i].chars().count()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut table = String::new();
+    let _ = writeln!(table, "{}", table_row(&header, &widths));
+    let _ = writeln!(
+        table,
+        "{}",
+        widths
+            .iter()
+            .map(|width| BORDER_SINGLE.repeat(*width))
+            .collect::<Vec<_>>()
+            .join("─┼─")
+    );
+    for row in &rows {
+        let _ = writeln!(table, "{}", table_row(row, &widths));
+    }
+    table
+}
+
+/// Format a table cell, values of unscalable units keeping their precision.
+fn format_cell(value: f64, unit: Unit) -> String {
+    if is_scalable(unit) {
+        format!("{value:.3}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format a table row, the first column being left-aligned and the others right-aligned.
+fn table_row(cells: &[String], widths: &[usize]) -> String {
+    cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, width))| {
+            if i == 0 {
+                format!("{cell:<width$}")
+            } else {
+                format!("{cell:>width$}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" │ ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::PhaseToken;
+
+    fn unit(prefix: UnitPrefix, unit: Unit) -> MetricUnit {
+        MetricUnit { prefix, unit }
+    }
+
+    fn phase(index: usize, end: &str, metrics: Vec<Metric>) -> Phase {
+        Phase {
+            index,
+            start_token: PhaseToken::Start,
+            end_token: PhaseToken::Token(end.into()),
+            timestamp: 0,
+            duration_ms: 10,
+            start_token_line: None,
+            end_token_line: None,
+            metrics,
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn energy(value: u64) -> Metric {
+        Metric::new(
+            "PACKAGE-0",
+            value,
+            unit(UnitPrefix::Micro, Unit::Joule),
+            "rapl",
+        )
+    }
+
+    #[test]
+    fn units_are_scaled_to_the_readable_prefix() {
+        let micro_joule = unit(UnitPrefix::Micro, Unit::Joule);
+        assert_eq!(
+            scaled_unit(12_345_678.0, micro_joule),
+            unit(UnitPrefix::None, Unit::Joule)
+        );
+        assert_eq!(
+            scaled_unit(1500.0, micro_joule),
+            unit(UnitPrefix::Milli, Unit::Joule)
+        );
+        assert_eq!(scaled_unit(0.5, micro_joule), micro_joule);
+        assert_eq!(
+            scaled_unit(2e9, micro_joule),
+            unit(UnitPrefix::Kilo, Unit::Joule)
+        );
+
+        let count = unit(UnitPrefix::None, Unit::Count);
+        assert_eq!(scaled_unit(12_345_678.0, count), count);
+        assert_eq!(format_metric(&energy(12_345_678)), "    12.346 J");
+    }
+
+    #[test]
+    fn share_bar_is_filled_proportionally() {
+        assert_eq!(share_bar(0.5, 4), "██░░");
+        assert_eq!(share_bar(1.2, 4), "████");
+        assert_eq!(share_bar(0.0, 4), "░░░░");
+    }
+
+    #[test]
+    fn shares_are_relative_to_all_phases() {
+        let phases = vec![
+            phase(0, "__A__", vec![energy(1_000_000)]),
+            phase(1, "__B__", vec![energy(3_000_000)]),
+        ];
+        let totals = EnergyTotals::new(&phases);
+
+        assert_eq!(totals.share(&phases[1].metrics[0]), Some(0.75));
+        assert_eq!(EnergyTotals::new(&phases[..1]).share(&energy(1)), None);
+    }
+
+    #[test]
+    fn table_has_a_row_per_phase_and_a_total() {
+        let results = ProfilerResults {
+            timestamp: 0,
+            duration_ms: 20,
+            exit_code: 0,
+            phases: vec![
+                phase(0, "__A_VERY_LONG_PHASE_NAME__", vec![energy(1_500_000)]),
+                phase(1, "__B__", vec![energy(2_500_000)]),
+            ],
+        };
+
+        let table = phases_table(&results);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("Phase "));
+        assert!(lines[0].ends_with("PACKAGE-0 (J)"));
+        assert!(lines[2].starts_with("START -> __A_VERY_LONG_PHASE_NAME__ │    10 ms │"));
+        assert!(lines[2].ends_with("1.500"));
+        assert!(lines[4].starts_with("All phases"));
+        assert!(lines[4].ends_with("4.000"));
+    }
+}
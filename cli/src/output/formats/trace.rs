@@ -0,0 +1,337 @@
+//! Chrome Trace Event export of the results, opened by Perfetto and `chrome://tracing`.
+//!
+//! Each phase is a complete event (`ph: X`) whose args are its metrics grouped by source. The
+//! power of each energy sensor is a counter event (`ph: C`): one value per polled reading when the
+//! sources are polled, else the average power of each phase. Counters drop to zero at the end of
+//! the run, where each budget check is an instant event (`ph: i`) whose args are its outcome.
+
+use std::fs::File;
+use std::io::Write;
+
+use joule_profiler_core::budget::BudgetReport;
+use joule_profiler_core::fs::{
+    create_file_with_user_permissions, default_results_filename, get_absolute_path,
+};
+use joule_profiler_core::types::{Metric, Metrics, ProfilerResults};
+use joule_profiler_core::unit::{MetricUnit, Unit, UnitPrefix};
+use serde_json::{Map, Value, json};
+
+use crate::output::displayer::error::IntoDisplayerError;
+use crate::output::displayer::{Displayer, DisplayerError};
+
+type Result<T> = std::result::Result<T, DisplayerError>;
+
+/// Process id of the events, a trace holding a single run.
+const PID: u32 = 1;
+
+/// Thread id of the phases events.
+const PHASES_TID: u32 = 1;
+
+const JOULE: MetricUnit = MetricUnit {
+    prefix: UnitPrefix::None,
+    unit: Unit::Joule,
+};
+
+/// Chrome Trace Event output writer to a file.
+pub struct TraceOutput {
+    /// File handle for writing the trace.
+    file: File,
+
+    /// Path to the output trace file.
+    filename: String,
+}
+
+impl TraceOutput {
+    /// Create a trace output writer to a file, optionally specifying the file path.
+    pub fn try_new(output_file: Option<String>) -> Result<Self> {
+        let filename = output_file.unwrap_or(default_results_filename("trace.json"));
+
+        let absolute_path = get_absolute_path(&filename)?;
+        let file = create_file_with_user_permissions(&absolute_path)?;
+
+        Ok(Self {
+            file,
+            filename: absolute_path,
+        })
+    }
+}
+
+impl Displayer for TraceOutput {
+    fn display_results(
+        &mut self,
+        cmd: &[String],
+        _token_pattern: &str,
+        results: &ProfilerResults,
+        budgets: Option<&BudgetReport>,
+    ) -> Result<()> {
+        let trace = trace(cmd, results, budgets)?;
+        serde_json::to_writer(&mut self.file, &trace)
+            .map_err(IntoDisplayerError::into_displayer_error)?;
+        writeln!(self.file)?;
+        println!("Chrome trace written to: {}", self.filename);
+        Ok(())
+    }
+}
+
+/// Build the trace of the results and of their budget checks.
+fn trace(
+    cmd: &[String],
+    results: &ProfilerResults,
+    budgets: Option<&BudgetReport>,
+) -> Result<Value> {
+    let mut events = vec![
+        json!({
+            "name": "process_name",
+            "ph": "M",
+            "pid": PID,
+            "args": { "name": format!("joule-profiler: {}", cmd.join(" ")) },
+        }),
+        json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": PID,
+            "tid": PHASES_TID,
+            "args": { "name": "Phases" },
+        }),
+    ];
+
+    let mut counters: Vec<(&str, &str)> = Vec::new();
+    let mut end = results.timestamp;
+
+    for phase in &results.phases {
+        let duration_us = phase.duration_ms * 1000;
+        end = end.max(phase.timestamp + duration_us);
+
+        events.push(json!({
+            "name": phase.get_name(),
+            "cat": "phase",
+            "ph": "X",
+            "ts": phase.timestamp,
+            "dur": duration_us,
+            "pid": PID,
+            "tid": PHASES_TID,
+            "args": phase_args(&phase.metrics),
+        }));
+
+        let mut sampled: Vec<&str> = Vec::new();
+        for sample in &phase.samples {
+            for (name, source, watts) in powers(&sample.metrics, sample.duration_us) {
+                events.push(counter(name, source, watts, sample.timestamp));
+                if !sampled.contains(&name) {
+                    sampled.push(name);
+                }
+                if !counters.contains(&(name, source)) {
+                    counters.push((name, source));
+                }
+            }
+        }
+
+        for (name, source, watts) in powers(&phase.metrics, duration_us) {
+            if sampled.contains(&name) {
+                continue;
+            }
+            events.push(counter(name, source, watts, phase.timestamp));
+            if !counters.contains(&(name, source)) {
+                counters.push((name, source));
+            }
+        }
+    }
+
+    for (name, source) in counters {
+        events.push(counter(name, source, 0.0, end));
+    }
+
+    for check in budgets.iter().flat_map(|budgets| &budgets.checks) {
+        let outcome = if check.passed { "passed" } else { "failed" };
+        let args = serde_json::to_value(check).map_err(IntoDisplayerError::into_displayer_error)?;
+        events.push(json!({
+            "name": format!("Budget {outcome}: {}", check.assertion),
+            "cat": "budget",
+            "ph": "i",
+            "s": "p",
+            "ts": end,
+            "pid": PID,
+            "tid": PHASES_TID,
+            "args": args,
+        }));
+    }
+
+    Ok(json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    }))
+}
+
+/// Metrics of a phase grouped by source, each one keyed by its name and unit.
+fn phase_args(metrics: &Metrics) -> Value {
+    let mut args = Map::new();
+    for metric in metrics {
+        let source = args
+            .entry(metric.source.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(source) = source {
+            source.insert(
+                format!("{} ({})", metric.name, metric.unit),
+                json!(metric.value.as_f64()),
+            );
+        }
+    }
+    Value::Object(args)
+}
+
+/// Power in watts of the energy metrics over an interval, with their name and source.
+#[allow(clippy::cast_precision_loss)]
+fn powers(metrics: &Metrics, duration_us: u128) -> Vec<(&str, &str, f64)> {
+    if duration_us == 0 {
+        return Vec::new();
+    }
+    metrics
+        .iter()
+        .filter_map(|metric: &Metric| {
+            let joules = metric.unit.convert(metric.value.as_f64(), JOULE)?;
+            Some((
+                metric.name.as_str(),
+                metric.source.as_str(),
+                joules / (duration_us as f64 / 1e6),
+            ))
+        })
+        .collect()
+}
+
+/// Name of the power counter of a sensor.
+fn counter_name(name: &str) -> String {
+    format!("{name} power (W)")
+}
+
+/// Counter event of the power of a sensor, each source being a series of the counter.
+fn counter(name: &str, source: &str, watts: f64, timestamp: u128) -> Value {
+    json!({
+        "name": counter_name(name),
+        "ph": "C",
+        "ts": timestamp,
+        "pid": PID,
+        "args": { source: watts },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::{Phase, PhaseSample, PhaseToken};
+
+    fn micro_joules(name: &str, value: u64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        Metric::new(name, value, unit, "rapl")
+    }
+
+    fn results(samples: Vec<PhaseSample>) -> ProfilerResults {
+        ProfilerResults {
+            timestamp: 1_000_000,
+            duration_ms: 2000,
+            exit_code: 0,
+            phases: vec![Phase {
+                index: 0,
+                start_token: PhaseToken::Start,
+                end_token: PhaseToken::End,
+                timestamp: 1_000_000,
+                duration_ms: 2000,
+                start_token_line: None,
+                end_token_line: None,
+                metrics: vec![
+                    micro_joules("PACKAGE-0", 10_000_000),
+                    Metric::new(
+                        "INSTRUCTIONS",
+                        42u64,
+                        MetricUnit {
+                            prefix: UnitPrefix::None,
+                            unit: Unit::Count,
+                        },
+                        "perf_event",
+                    ),
+                ],
+                total_energy_rule: None,
+                power: Vec::new(),
+                samples,
+            }],
+        }
+    }
+
+    fn events(trace: &Value, ph: &str) -> Vec<Value> {
+        trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|event| event["ph"] == ph)
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn phases_are_complete_events_with_metrics() {
+        let trace = trace(&["./bench".into()], &results(Vec::new()), None).unwrap();
+        let phases = events(&trace, "X");
+
+        assert_eq!(phases.len(), 1);
+        assert_eq!(phases[0]["name"], "START -> END");
+        assert_eq!(phases[0]["ts"], 1_000_000);
+        assert_eq!(phases[0]["dur"], 2_000_000);
+        assert_eq!(phases[0]["args"]["rapl"]["PACKAGE-0 (µJ)"], 10_000_000.0);
+        assert_eq!(
+            phases[0]["args"]["perf_event"]["INSTRUCTIONS (count)"],
+            42.0
+        );
+    }
+
+    #[test]
+    fn phases_without_samples_give_their_average_power() {
+        let trace = trace(&["./bench".into()], &results(Vec::new()), None).unwrap();
+        let counters = events(&trace, "C");
+
+        assert_eq!(counters.len(), 2);
+        assert_eq!(counters[0]["name"], "PACKAGE-0 power (W)");
+        assert_eq!(counters[0]["args"]["rapl"], 5.0);
+        assert_eq!(counters[1]["ts"], 3_000_000);
+        assert_eq!(counters[1]["args"]["rapl"], 0.0);
+    }
+
+    #[test]
+    fn samples_give_the_power_counters() {
+        let samples = vec![
+            PhaseSample {
+                timestamp: 1_000_000,
+                duration_us: 500_000,
+                metrics: vec![micro_joules("PACKAGE-0", 1_000_000)],
+            },
+            PhaseSample {
+                timestamp: 1_500_000,
+                duration_us: 1_500_000,
+                metrics: vec![micro_joules("PACKAGE-0", 9_000_000)],
+            },
+        ];
+        let trace = trace(&["./bench".into()], &results(samples), None).unwrap();
+        let counters = events(&trace, "C");
+
+        assert_eq!(counters.len(), 3);
+        assert_eq!(counters[0]["args"]["rapl"], 2.0);
+        assert_eq!(counters[1]["ts"], 1_500_000);
+        assert_eq!(counters[1]["args"]["rapl"], 6.0);
+    }
+
+    #[test]
+    fn budget_checks_are_instant_events_at_the_end() {
+        let results = results(Vec::new());
+        let budgets = BudgetReport::check(&["PACKAGE-0 < 2J".parse().unwrap()], &results);
+        let trace = trace(&["./bench".into()], &results, Some(&budgets)).unwrap();
+        let instants = events(&trace, "i");
+
+        assert_eq!(instants.len(), 1);
+        assert_eq!(instants[0]["name"], "Budget failed: PACKAGE-0 < 2J");
+        assert_eq!(instants[0]["ts"], 3_000_000);
+        assert_eq!(instants[0]["args"]["passed"], false);
+        assert_eq!(instants[0]["args"]["value"], 10.0);
+        assert_eq!(instants[0]["args"]["threshold"], 2.0);
+    }
+}
@@ -0,0 +1,222 @@
+//! Rendering of saved profiling results.
+//!
+//! Results files written by the JSON output are loaded and rendered again through any output,
+//! so that a single measurement can be formatted many ways without profiling the program again.
+//! A [`ReportFilter`] selects the rendered phases and sensors and converts the metrics units
+//! beforehand.
+
+use joule_profiler_core::config::ReportFilter;
+use joule_profiler_core::types::{Metric, MetricValue, Phase, ProfilerResults};
+use log::warn;
+
+/// Keeps the phases and sensors selected by the filter and converts the metrics units.
+pub fn apply_filter(filter: &ReportFilter, results: &mut ProfilerResults) {
+    if !filter.phases.is_empty() {
+        results
+            .phases
+            .retain(|phase| filter.phases.iter().any(|name| matches_phase(phase, name)));
+        if results.phases.is_empty() {
+            warn!("No phase matches {}", filter.phases.join(", "));
+        }
+    }
+
+    for phase in &mut results.phases {
+        if !filter.sensors.is_empty() {
+            phase.metrics.retain(|metric| {
+                filter
+                    .sensors
+                    .iter()
+                    .any(|sensor| matches_sensor(&metric.source, &metric.name, sensor))
+            });
+            phase.power.retain(|power| {
+                filter
+                    .sensors
+                    .iter()
+                    .any(|sensor| matches_sensor(&power.source, &power.name, sensor))
+            });
+        }
+        for metric in &mut phase.metrics {
+            convert_metric(filter, metric);
+        }
+    }
+
+    if !filter.sensors.is_empty() && results.phases.iter().all(|p| p.metrics.is_empty()) {
+        warn!("No sensor matches {}", filter.sensors.join(", "));
+    }
+}
+
+/// Tells whether the phase matches a name or a start token.
+fn matches_phase(phase: &Phase, name: &str) -> bool {
+    phase.get_name() == name || phase.start_token.to_string() == name
+}
+
+/// Tells whether a sensor matches a name or a `SOURCE/NAME` selector.
+fn matches_sensor(source: &str, name: &str, sensor: &str) -> bool {
+    match sensor.split_once('/') {
+        Some((sensor_source, sensor_name)) if sensor_source == source => sensor_name == name,
+        _ => sensor == name,
+    }
+}
+
+/// Converts the metric into the first unit of the filter with the same base unit, if any.
+fn convert_metric(filter: &ReportFilter, metric: &mut Metric) {
+    let Some((target, value)) = filter.units.iter().find_map(|target| {
+        metric
+            .unit
+            .convert(metric.value.as_f64(), *target)
+            .map(|value| (*target, value))
+    }) else {
+        return;
+    };
+
+    if target != metric.unit {
+        metric.value = MetricValue::Float(value);
+        metric.unit = target;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use joule_profiler_core::types::{PhaseToken, PowerStats};
+    use joule_profiler_core::unit::{MetricUnit, Unit, UnitPrefix};
+
+    const MICRO_JOULE: MetricUnit = MetricUnit {
+        prefix: UnitPrefix::Micro,
+        unit: Unit::Joule,
+    };
+
+    fn power(name: &str) -> PowerStats {
+        PowerStats {
+            name: name.into(),
+            source: "rapl".into(),
+            samples: 2,
+            min: 1.0,
+            max: 2.0,
+            mean: 1.5,
+            std_dev: 0.5,
+            p50: 1.5,
+            p90: 2.0,
+            p99: 2.0,
+            histogram: None,
+        }
+    }
+
+    fn phase(start: &str, end: &str) -> Phase {
+        Phase {
+            index: 0,
+            start_token: PhaseToken::Token(start.into()),
+            end_token: PhaseToken::Token(end.into()),
+            timestamp: 0,
+            duration_ms: 10,
+            start_token_line: None,
+            end_token_line: None,
+            metrics: vec![
+                Metric::new("PACKAGE-0", 2_000_000u64, MICRO_JOULE, "rapl"),
+                Metric::new("DRAM-0", 500_000u64, MICRO_JOULE, "rapl"),
+                Metric::new("GPU-0", 1_000u64, MICRO_JOULE, "nvml"),
+            ],
+            total_energy_rule: None,
+            power: vec![power("PACKAGE-0"), power("DRAM-0")],
+            samples: Vec::new(),
+        }
+    }
+
+    fn results() -> ProfilerResults {
+        ProfilerResults {
+            timestamp: 0,
+            duration_ms: 20,
+            exit_code: 0,
+            phases: vec![phase("__A__", "__B__"), phase("__B__", "__C__")],
+        }
+    }
+
+    fn names(phase: &Phase) -> Vec<&str> {
+        phase.metrics.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn default_filter_keeps_everything() {
+        let mut results = results();
+        apply_filter(&ReportFilter::default(), &mut results);
+
+        assert_eq!(results.phases.len(), 2);
+        assert_eq!(names(&results.phases[0]), ["PACKAGE-0", "DRAM-0", "GPU-0"]);
+        assert_eq!(
+            results.phases[0].metrics[0].value,
+            MetricValue::UnsignedInteger(2_000_000)
+        );
+    }
+
+    #[test]
+    fn filters_phases_by_name_or_start_token() {
+        let filter = |phases: &[&str]| ReportFilter {
+            phases: phases.iter().map(ToString::to_string).collect(),
+            ..ReportFilter::default()
+        };
+
+        let mut results = results();
+        apply_filter(&filter(&["__B__ -> __C__"]), &mut results);
+        assert_eq!(results.phases.len(), 1);
+        assert_eq!(results.phases[0].get_name(), "__B__ -> __C__");
+
+        let mut results = self::results();
+        apply_filter(&filter(&["__A__", "__B__"]), &mut results);
+        assert_eq!(results.phases.len(), 2);
+
+        let mut results = self::results();
+        apply_filter(&filter(&["__C__"]), &mut results);
+        assert!(results.phases.is_empty());
+    }
+
+    #[test]
+    fn filters_sensors_by_name_or_source() {
+        let mut results = results();
+        let filter = ReportFilter {
+            sensors: vec![
+                "PACKAGE-0".into(),
+                "nvml/GPU-0".into(),
+                "nvml/DRAM-0".into(),
+            ],
+            ..ReportFilter::default()
+        };
+        apply_filter(&filter, &mut results);
+
+        let phase = &results.phases[0];
+        assert_eq!(names(phase), ["PACKAGE-0", "GPU-0"]);
+        assert_eq!(phase.power, [power("PACKAGE-0")]);
+    }
+
+    #[test]
+    fn converts_metrics_of_the_same_base_unit() {
+        let mut results = results();
+        let filter = ReportFilter {
+            units: vec![
+                MetricUnit::try_from("mW").unwrap(),
+                MetricUnit::try_from("J").unwrap(),
+                MetricUnit::try_from("kJ").unwrap(),
+            ],
+            ..ReportFilter::default()
+        };
+        apply_filter(&filter, &mut results);
+
+        let metric = &results.phases[0].metrics[0];
+        assert_eq!(metric.value, MetricValue::Float(2.0));
+        assert_eq!(metric.unit.to_string(), "J");
+    }
+
+    #[test]
+    fn keeps_values_already_in_the_target_unit() {
+        let mut results = results();
+        let filter = ReportFilter {
+            units: vec![MICRO_JOULE],
+            ..ReportFilter::default()
+        };
+        apply_filter(&filter, &mut results);
+
+        assert_eq!(
+            results.phases[0].metrics[0].value,
+            MetricValue::UnsignedInteger(2_000_000)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Attribution of the machine-wide energy to the profiled process.
+//!
+//! RAPL measures the whole socket, including the background activity of the machine. When the
+//! phase has the `PROCESS_CPU_TIME` and `SYSTEM_CPU_TIME` metrics of the `proc` source, the
+//! RAPL `PACKAGE` and `CORE` energies are split by the CPU time share of the profiled process,
+//! and reported next to the raw energies.
+
+use crate::aggregate::{Metric, Metrics};
+use crate::source::MetricScope;
+use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+/// Source name of the attributed metrics.
+pub(crate) const ATTRIBUTION_SOURCE_NAME: &str = "attribution";
+
+/// Name of the metric holding the CPU time of the profiled process.
+const PROCESS_CPU_TIME: &str = "PROCESS_CPU_TIME";
+
+/// Name of the metric holding the busy CPU time of the machine.
+const SYSTEM_CPU_TIME: &str = "SYSTEM_CPU_TIME";
+
+/// Prefix of the attributed energy metrics names.
+const ATTRIBUTED_PREFIX: &str = "ATTRIBUTED_";
+
+/// Prefixes of the RAPL domains split between the processes.
+const ATTRIBUTED_DOMAINS: &[&str] = &["PACKAGE", "CORE"];
+
+/// Computes the energy attributed to the profiled process during a phase.
+pub(crate) fn attributed_metrics(metrics: &Metrics) -> Metrics {
+    let Some(share) = cpu_time_share(metrics) else {
+        return Metrics::new();
+    };
+
+    let mut attributed = vec![metric(
+        "CPU_TIME_SHARE",
+        share * 100.0,
+        MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Percent,
+        },
+    )];
+    attributed.extend(
+        metrics
+            .iter()
+            .filter(|metric| {
+                metric.unit.unit == Unit::Joule
+                    && ATTRIBUTED_DOMAINS
+                        .iter()
+                        .any(|domain| metric.name.starts_with(domain))
+            })
+            .map(|energy| {
+                metric(
+                    &format!("{ATTRIBUTED_PREFIX}{}", energy.name),
+                    energy.value.as_f64() * share,
+                    energy.unit,
+                )
+            }),
+    );
+    attributed
+}
+
+/// Share of the machine busy CPU time spent by the profiled process.
+///
+/// The CPU times are sampled at slightly different instants than the energy, the share is thus
+/// clamped to 1. `None` if the CPU times are missing or the machine has not been busy.
+fn cpu_time_share(metrics: &Metrics) -> Option<f64> {
+    let seconds = MetricUnit {
+        prefix: UnitPrefix::None,
+        unit: Unit::Second,
+    };
+    let cpu_time = |name: &str| {
+        metrics
+            .iter()
+            .filter(|metric| metric.name == name)
+            .find_map(|metric| metric.unit.convert(metric.value.as_f64(), seconds))
+    };
+
+    let system = cpu_time(SYSTEM_CPU_TIME).filter(|&system| system > 0.0)?;
+    let process = cpu_time(PROCESS_CPU_TIME)?;
+    Some((process / system).min(1.0))
+}
+
+fn metric(name: &str, value: f64, unit: MetricUnit) -> Metric {
+    let mut metric = Metric::new(name, value, unit, ATTRIBUTION_SOURCE_NAME);
+    metric.scope = MetricScope::Process;
+    metric
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MetricValue;
+
+    fn energy(name: &str, micro_joules: u64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        Metric::new(name, micro_joules, unit, "RAPL (Powercap)")
+    }
+
+    fn cpu_time(name: &str, millis: u64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Milli,
+            unit: Unit::Second,
+        };
+        Metric::new(name, millis, unit, "proc")
+    }
+
+    fn value(metrics: &Metrics, name: &str) -> Option<MetricValue> {
+        metrics
+            .iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| metric.value)
+    }
+
+    #[test]
+    fn package_and_core_energy_split_by_cpu_time_share() {
+        let metrics = vec![
+            energy("PACKAGE-0", 1000),
+            energy("CORE-0", 600),
+            energy("DRAM-0", 100),
+            cpu_time(PROCESS_CPU_TIME, 250),
+            cpu_time(SYSTEM_CPU_TIME, 1000),
+        ];
+
+        let attributed = attributed_metrics(&metrics);
+        assert_eq!(attributed.len(), 3);
+        assert_eq!(
+            value(&attributed, "CPU_TIME_SHARE"),
+            Some(MetricValue::Float(25.0))
+        );
+        assert_eq!(
+            value(&attributed, "ATTRIBUTED_PACKAGE-0"),
+            Some(MetricValue::Float(250.0))
+        );
+        assert_eq!(
+            value(&attributed, "ATTRIBUTED_CORE-0"),
+            Some(MetricValue::Float(150.0))
+        );
+        assert!(
+            attributed
+                .iter()
+                .all(|metric| metric.scope == MetricScope::Process)
+        );
+    }
+
+    #[test]
+    fn share_is_clamped() {
+        let metrics = vec![
+            energy("PACKAGE-0", 1000),
+            cpu_time(PROCESS_CPU_TIME, 1100),
+            cpu_time(SYSTEM_CPU_TIME, 1000),
+        ];
+
+        assert_eq!(
+            value(&attributed_metrics(&metrics), "ATTRIBUTED_PACKAGE-0"),
+            Some(MetricValue::Float(1000.0))
+        );
+    }
+
+    #[test]
+    fn nothing_without_cpu_times() {
+        let metrics = vec![energy("PACKAGE-0", 1000), cpu_time(PROCESS_CPU_TIME, 10)];
+        assert!(attributed_metrics(&metrics).is_empty());
+    }
+
+    #[test]
+    fn nothing_on_idle_machine() {
+        let metrics = vec![
+            energy("PACKAGE-0", 1000),
+            cpu_time(PROCESS_CPU_TIME, 0),
+            cpu_time(SYSTEM_CPU_TIME, 0),
+        ];
+        assert!(attributed_metrics(&metrics).is_empty());
+    }
+}
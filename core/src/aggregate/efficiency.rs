@@ -0,0 +1,144 @@
+//! Energy-efficiency metrics derived from the merged metrics of a phase.
+//!
+//! The energy is the [total energy](`crate::aggregate::energy`) of the phase, the delay its duration,
+//! and the instructions and cycles the `perf_event` counters. Each derived metric is only computed
+//! when all its inputs are present in the phase.
+
+use crate::aggregate::{Metric, Metrics};
+use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+/// Source name of the derived metrics.
+pub(crate) const EFFICIENCY_SOURCE_NAME: &str = "efficiency";
+
+/// Name of the `perf_event` instructions counter.
+const INSTRUCTIONS: &str = "INSTRUCTIONS";
+
+/// Name of the `perf_event` cycles counter.
+const CPU_CYCLES: &str = "CPU_CYCLES";
+
+/// Computes the efficiency metrics of a phase which consumed `energy` joules in `duration_ms`.
+pub(crate) fn efficiency_metrics(metrics: &Metrics, energy: f64, duration_ms: u128) -> Metrics {
+    let mut derived = Metrics::new();
+
+    #[allow(clippy::cast_precision_loss)]
+    let delay = duration_ms as f64 / 1000.0;
+    derived.push(metric("EDP", energy * delay, Unit::JouleSecond));
+    derived.push(metric(
+        "ED2P",
+        energy * delay * delay,
+        Unit::JouleSecondSquared,
+    ));
+
+    if let Some(instructions) = count(metrics, INSTRUCTIONS) {
+        if instructions > 0.0 {
+            derived.push(metric(
+                "ENERGY_PER_INSTRUCTION",
+                energy / instructions,
+                Unit::JoulePerCount,
+            ));
+        }
+        if energy > 0.0 {
+            derived.push(metric(
+                "INSTRUCTIONS_PER_JOULE",
+                instructions / energy,
+                Unit::CountPerJoule,
+            ));
+        }
+    }
+
+    if let Some(cycles) = count(metrics, CPU_CYCLES)
+        && energy > 0.0
+    {
+        derived.push(metric(
+            "CYCLES_PER_JOULE",
+            cycles / energy,
+            Unit::CountPerJoule,
+        ));
+    }
+
+    derived
+}
+
+/// Sums the values of the counter named `name`, `None` if the phase does not have it.
+fn count(metrics: &Metrics, name: &str) -> Option<f64> {
+    metrics
+        .iter()
+        .filter(|metric| metric.name == name && metric.unit.unit == Unit::Count)
+        .map(|metric| metric.value.as_f64())
+        .reduce(|a, b| a + b)
+}
+
+fn metric(name: &str, value: f64, unit: Unit) -> Metric {
+    let unit = MetricUnit {
+        prefix: UnitPrefix::None,
+        unit,
+    };
+    Metric::new(name, value, unit, EFFICIENCY_SOURCE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MetricValue;
+
+    fn counter(name: &str, value: u64) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Count,
+        };
+        Metric::new(name, value, unit, "perf_event")
+    }
+
+    fn value(metrics: &Metrics, name: &str) -> Option<f64> {
+        metrics
+            .iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| match metric.value {
+                MetricValue::Float(v) => v,
+                _ => panic!("derived metrics are floats"),
+            })
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!(
+            (actual - expected).abs() < expected * 1e-9,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn all_metrics_with_energy_and_counters() {
+        let metrics = vec![
+            counter(INSTRUCTIONS, 4_000_000),
+            counter(CPU_CYCLES, 8_000_000),
+        ];
+
+        let derived = efficiency_metrics(&metrics, 2.0, 500);
+        assert_eq!(derived.len(), 5);
+        assert_close(value(&derived, "EDP"), 1.0);
+        assert_close(value(&derived, "ED2P"), 0.5);
+        assert_close(value(&derived, "ENERGY_PER_INSTRUCTION"), 5e-7);
+        assert_close(value(&derived, "INSTRUCTIONS_PER_JOULE"), 2e6);
+        assert_close(value(&derived, "CYCLES_PER_JOULE"), 4e6);
+        assert!(
+            derived
+                .iter()
+                .all(|metric| metric.source == EFFICIENCY_SOURCE_NAME)
+        );
+    }
+
+    #[test]
+    fn only_delay_products_without_counters() {
+        let derived = efficiency_metrics(&Metrics::new(), 1.0, 1000);
+        assert_eq!(derived.len(), 2);
+        assert_close(value(&derived, "EDP"), 1.0);
+    }
+
+    #[test]
+    fn ratios_skipped_on_zero_values() {
+        let derived = efficiency_metrics(&vec![counter(INSTRUCTIONS, 0)], 0.0, 1000);
+        assert!(value(&derived, "ENERGY_PER_INSTRUCTION").is_none());
+        assert!(value(&derived, "INSTRUCTIONS_PER_JOULE").is_none());
+    }
+}
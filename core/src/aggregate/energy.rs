@@ -0,0 +1,245 @@
+//! Cross-source total energy.
+//!
+//! The energy domains reported by the sources overlap: the RAPL `PSYS` domain measures the whole
+//! platform including the packages, and each `PACKAGE` includes its `CORE` and `UNCORE` domains.
+//! Summing all the energy metrics of a phase thus counts the same energy several times.
+//!
+//! The total energy of a phase is the sum of the outermost domains only, the domains contained
+//! in another measured domain being excluded:
+//!
+//! ```text
+//! PSYS ⊇ PACKAGE-n ⊇ CORE-n, UNCORE-n
+//! DRAM-n and GPU-n (NVML) are disjoint from the other domains
+//! ```
+
+use std::fmt::Display;
+
+use crate::aggregate::{Metric, Metrics};
+use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+/// Name of the total energy metric.
+pub const TOTAL_ENERGY: &str = "TOTAL";
+
+/// Source name of the total energy metric.
+pub(crate) const TOTAL_SOURCE_NAME: &str = "total";
+
+/// An energy domain measured by a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnergyDomain {
+    /// RAPL platform domain.
+    Psys,
+
+    /// RAPL package domain of a socket.
+    Package(u32),
+
+    /// RAPL cores domain of a socket.
+    Core(u32),
+
+    /// RAPL uncore (e.g. integrated GPU) domain of a socket.
+    Uncore(u32),
+
+    /// RAPL memory domain of a socket.
+    Dram(u32),
+
+    /// NVIDIA GPU.
+    Gpu(u32),
+}
+
+impl EnergyDomain {
+    /// Parses an energy metric name (e.g. `PACKAGE-0`, `PSYS` or `GPU-1`).
+    pub fn from_metric_name(name: &str) -> Option<Self> {
+        if name == "PSYS" {
+            return Some(Self::Psys);
+        }
+
+        let (domain, index) = name.rsplit_once('-')?;
+        let index = index.parse().ok()?;
+        match domain {
+            "PACKAGE" => Some(Self::Package(index)),
+            "CORE" => Some(Self::Core(index)),
+            "UNCORE" => Some(Self::Uncore(index)),
+            "DRAM" => Some(Self::Dram(index)),
+            "GPU" => Some(Self::Gpu(index)),
+            _ => None,
+        }
+    }
+
+    /// Whether the energy of `other` is included in this domain.
+    pub fn contains(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::Psys, Self::Package(_) | Self::Core(_) | Self::Uncore(_)) => true,
+            (Self::Package(socket), Self::Core(other_socket) | Self::Uncore(other_socket)) => {
+                socket == other_socket
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Total energy of a phase and the rule used to compute it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TotalEnergy {
+    /// Total energy in joules.
+    pub joules: f64,
+
+    /// Names of the summed metrics.
+    pub summed: Vec<String>,
+
+    /// Names of the metrics excluded because another measured domain includes them.
+    pub excluded: Vec<String>,
+}
+
+impl TotalEnergy {
+    /// Computes the total energy of the metrics, `None` if they have no energy domain.
+    ///
+    /// A domain reported by several sources is only counted once.
+    pub fn from_metrics(metrics: &Metrics) -> Option<Self> {
+        let mut domains: Vec<(EnergyDomain, &Metric)> = Vec::new();
+        for metric in metrics
+            .iter()
+            .filter(|metric| metric.unit.unit == Unit::Joule)
+        {
+            if let Some(domain) = EnergyDomain::from_metric_name(&metric.name)
+                && !domains.iter().any(|(existing, _)| *existing == domain)
+            {
+                domains.push((domain, metric));
+            }
+        }
+        if domains.is_empty() {
+            return None;
+        }
+
+        let mut total = Self {
+            joules: 0.0,
+            summed: Vec::new(),
+            excluded: Vec::new(),
+        };
+        for (domain, metric) in &domains {
+            if domains.iter().any(|(other, _)| other.contains(*domain)) {
+                total.excluded.push(metric.name.clone());
+            } else {
+                total.joules += metric.value.as_f64() * metric.unit.prefix.factor();
+                total.summed.push(metric.name.clone());
+            }
+        }
+        Some(total)
+    }
+
+    /// The total energy metric.
+    pub(crate) fn to_metric(&self) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Joule,
+        };
+        Metric::new(TOTAL_ENERGY, self.joules, unit, TOTAL_SOURCE_NAME)
+    }
+}
+
+impl Display for TotalEnergy {
+    /// Formats the rule, e.g. `PSYS + DRAM-0 (excluding PACKAGE-0, CORE-0)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.summed.join(" + "))?;
+        if !self.excluded.is_empty() {
+            write!(f, " (excluding {})", self.excluded.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn energy(name: &str, value: u64, prefix: UnitPrefix, source: &str) -> Metric {
+        let unit = MetricUnit {
+            prefix,
+            unit: Unit::Joule,
+        };
+        Metric::new(name, value, unit, source)
+    }
+
+    fn rapl(name: &str, joules: u64) -> Metric {
+        energy(name, joules, UnitPrefix::None, "RAPL (Powercap)")
+    }
+
+    #[test]
+    fn parse_domains() {
+        assert_eq!(
+            EnergyDomain::from_metric_name("PSYS"),
+            Some(EnergyDomain::Psys)
+        );
+        assert_eq!(
+            EnergyDomain::from_metric_name("PACKAGE-1"),
+            Some(EnergyDomain::Package(1))
+        );
+        assert_eq!(
+            EnergyDomain::from_metric_name("GPU-0"),
+            Some(EnergyDomain::Gpu(0))
+        );
+        assert_eq!(EnergyDomain::from_metric_name("ATTRIBUTED_PACKAGE-0"), None);
+        assert_eq!(EnergyDomain::from_metric_name("PACKAGE"), None);
+    }
+
+    #[test]
+    fn containment_follows_rapl_hierarchy() {
+        assert!(EnergyDomain::Psys.contains(EnergyDomain::Package(1)));
+        assert!(EnergyDomain::Psys.contains(EnergyDomain::Core(0)));
+        assert!(EnergyDomain::Package(0).contains(EnergyDomain::Uncore(0)));
+        assert!(!EnergyDomain::Package(0).contains(EnergyDomain::Core(1)));
+        assert!(!EnergyDomain::Psys.contains(EnergyDomain::Dram(0)));
+        assert!(!EnergyDomain::Psys.contains(EnergyDomain::Gpu(0)));
+    }
+
+    #[test]
+    fn total_without_psys_sums_packages_dram_and_gpus() {
+        let metrics = vec![
+            rapl("CORE-0", 5),
+            rapl("PACKAGE-0", 10),
+            rapl("DRAM-0", 2),
+            rapl("PACKAGE-1", 8),
+            energy("GPU-0", 3000, UnitPrefix::Milli, "NVML"),
+        ];
+
+        let total = TotalEnergy::from_metrics(&metrics).unwrap();
+        assert!((total.joules - 23.0).abs() < 1e-9);
+        assert_eq!(
+            total.to_string(),
+            "PACKAGE-0 + DRAM-0 + PACKAGE-1 + GPU-0 (excluding CORE-0)"
+        );
+    }
+
+    #[test]
+    fn total_with_psys_excludes_packages() {
+        let metrics = vec![
+            rapl("PACKAGE-0", 10),
+            rapl("CORE-0", 5),
+            rapl("PSYS", 15),
+            rapl("DRAM-0", 2),
+        ];
+
+        let total = TotalEnergy::from_metrics(&metrics).unwrap();
+        assert!((total.joules - 17.0).abs() < 1e-9);
+        assert_eq!(total.summed, vec!["PSYS", "DRAM-0"]);
+        assert_eq!(total.excluded, vec!["PACKAGE-0", "CORE-0"]);
+    }
+
+    #[test]
+    fn duplicated_domains_are_counted_once() {
+        let metrics = vec![
+            rapl("PACKAGE-0", 10),
+            energy("PACKAGE-0", 10, UnitPrefix::None, "RAPL (perf_event)"),
+        ];
+        let total = TotalEnergy::from_metrics(&metrics).unwrap();
+        assert!((total.joules - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_total_without_energy_domains() {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Count,
+        };
+        let metrics = vec![Metric::new("PACKAGE-0", 10u64, unit, "other")];
+        assert!(TotalEnergy::from_metrics(&metrics).is_none());
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use crate::aggregate::{Metric, MetricValue, Metrics};
+use crate::unit::MetricUnit;
+
+/// A collection of metrics indexed by source and name.
+///
+/// Metrics keep their insertion order, and a metric with the same source and name as an existing
+/// one replaces it (see [`IndexedMetrics::insert`]) or is summed with it
+/// (see [`IndexedMetrics::accumulate`]).
+///
+/// # Examples
+///
+/// ```
+/// use joule_profiler_core::types::{IndexedMetrics, Metric, MetricValue};
+/// use joule_profiler_core::unit::MetricUnit;
+///
+/// let micro_joules = MetricUnit::try_from("µJ").unwrap();
+/// let metrics: IndexedMetrics = vec![
+///     Metric::new("PACKAGE-0", 1_500_000u64, micro_joules, "rapl"),
+///     Metric::new("DRAM-0", 500_000u64, micro_joules, "rapl"),
+///     Metric::new("GPU-0", 3000u64, MetricUnit::try_from("mJ").unwrap(), "nvml"),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// assert_eq!(
+///     metrics.value("rapl", "DRAM-0"),
+///     Some(MetricValue::UnsignedInteger(500_000))
+/// );
+/// let joules = MetricUnit::try_from("J").unwrap();
+/// assert_eq!(metrics.sum(joules, |metric| metric.name != "DRAM-0"), Some(4.5));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IndexedMetrics {
+    metrics: Metrics,
+
+    /// Index of each metric in `metrics`, by source then name.
+    index: HashMap<String, HashMap<String, usize>>,
+}
+
+impl IndexedMetrics {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of metrics.
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Whether the collection has no metric.
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// Inserts a metric, returning the metric with the same source and name it replaced.
+    pub fn insert(&mut self, metric: Metric) -> Option<Metric> {
+        if let Some(existing) = self.get_mut(&metric.source, &metric.name) {
+            return Some(std::mem::replace(existing, metric));
+        }
+
+        self.index
+            .entry(metric.source.clone())
+            .or_default()
+            .insert(metric.name.clone(), self.metrics.len());
+        self.metrics.push(metric);
+        None
+    }
+
+    /// Inserts a metric, or sums its value with the metric with the same source and name.
+    ///
+    /// Values are summed as by the source runtime, integers saturating instead of overflowing.
+    pub fn accumulate(&mut self, metric: Metric) {
+        if let Some(existing) = self.get_mut(&metric.source, &metric.name) {
+            existing.value = existing.value.accumulate(metric.value);
+        } else {
+            self.insert(metric);
+        }
+    }
+
+    /// Returns the metric named `name` from `source`.
+    pub fn get(&self, source: &str, name: &str) -> Option<&Metric> {
+        let index = *self.index.get(source)?.get(name)?;
+        Some(&self.metrics[index])
+    }
+
+    fn get_mut(&mut self, source: &str, name: &str) -> Option<&mut Metric> {
+        let index = *self.index.get(source)?.get(name)?;
+        Some(&mut self.metrics[index])
+    }
+
+    /// Returns the value of the metric named `name` from `source`.
+    pub fn value(&self, source: &str, name: &str) -> Option<MetricValue> {
+        self.get(source, name).map(|metric| metric.value)
+    }
+
+    /// Iterates over the metrics in insertion order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Metric> {
+        self.metrics.iter()
+    }
+
+    /// Iterates over the metrics of a source.
+    pub fn by_source<'a>(&'a self, source: &'a str) -> impl Iterator<Item = &'a Metric> {
+        self.metrics
+            .iter()
+            .filter(move |metric| metric.source == source)
+    }
+
+    /// Iterates over the metrics named `name`, whatever their source.
+    pub fn by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Metric> {
+        self.metrics
+            .iter()
+            .filter(move |metric| metric.name == name)
+    }
+
+    /// Returns the metrics matching the predicate.
+    #[must_use]
+    pub fn filter<P: FnMut(&Metric) -> bool>(&self, mut predicate: P) -> Self {
+        self.metrics
+            .iter()
+            .filter(|metric| predicate(metric))
+            .cloned()
+            .collect()
+    }
+
+    /// Sums the metrics matching the predicate, converted into `unit`.
+    ///
+    /// Metrics whose base unit differs from the one of `unit` are skipped.
+    /// Returns `None` if no metric is summed.
+    pub fn sum<P: FnMut(&Metric) -> bool>(
+        &self,
+        unit: MetricUnit,
+        mut predicate: P,
+    ) -> Option<f64> {
+        self.metrics
+            .iter()
+            .filter(|metric| predicate(metric))
+            .filter_map(|metric| metric.unit.convert(metric.value.as_f64(), unit))
+            .reduce(|a, b| a + b)
+    }
+}
+
+impl From<Metrics> for IndexedMetrics {
+    fn from(metrics: Metrics) -> Self {
+        metrics.into_iter().collect()
+    }
+}
+
+impl From<IndexedMetrics> for Metrics {
+    fn from(metrics: IndexedMetrics) -> Self {
+        metrics.metrics
+    }
+}
+
+impl FromIterator<Metric> for IndexedMetrics {
+    /// Collects the metrics, the last metric with a given source and name replacing the previous ones.
+    fn from_iter<T: IntoIterator<Item = Metric>>(iter: T) -> Self {
+        let mut metrics = Self::new();
+        for metric in iter {
+            metrics.insert(metric);
+        }
+        metrics
+    }
+}
+
+impl IntoIterator for IndexedMetrics {
+    type Item = Metric;
+    type IntoIter = std::vec::IntoIter<Metric>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.metrics.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a IndexedMetrics {
+    type Item = &'a Metric;
+    type IntoIter = std::slice::Iter<'a, Metric>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.metrics.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit::{Unit, UnitPrefix};
+
+    fn metric(source: &str, name: &str, value: u64, prefix: UnitPrefix, unit: Unit) -> Metric {
+        Metric::new(name, value, MetricUnit { prefix, unit }, source)
+    }
+
+    fn energy(source: &str, name: &str, micro_joules: u64) -> Metric {
+        metric(source, name, micro_joules, UnitPrefix::Micro, Unit::Joule)
+    }
+
+    #[test]
+    fn lookup_by_source_and_name() {
+        let metrics = IndexedMetrics::from(vec![
+            energy("rapl", "PACKAGE-0", 10),
+            energy("other", "PACKAGE-0", 20),
+        ]);
+
+        assert_eq!(
+            metrics.value("other", "PACKAGE-0"),
+            Some(MetricValue::UnsignedInteger(20))
+        );
+        assert!(metrics.get("rapl", "DRAM-0").is_none());
+        assert!(metrics.get("nvml", "PACKAGE-0").is_none());
+        assert_eq!(metrics.by_name("PACKAGE-0").count(), 2);
+        assert_eq!(metrics.by_source("rapl").count(), 1);
+    }
+
+    #[test]
+    fn insert_replaces_and_accumulate_sums() {
+        let mut metrics = IndexedMetrics::new();
+        assert!(metrics.insert(energy("rapl", "PACKAGE-0", 10)).is_none());
+        metrics.insert(energy("rapl", "DRAM-0", 1));
+
+        let replaced = metrics.insert(energy("rapl", "PACKAGE-0", 20)).unwrap();
+        assert_eq!(replaced.value, MetricValue::UnsignedInteger(10));
+
+        metrics.accumulate(energy("rapl", "PACKAGE-0", 5));
+        metrics.accumulate(energy("rapl", "CORE-0", 3));
+
+        let names: Vec<_> = metrics.iter().map(|metric| metric.name.as_str()).collect();
+        assert_eq!(names, vec!["PACKAGE-0", "DRAM-0", "CORE-0"]);
+        assert_eq!(
+            metrics.value("rapl", "PACKAGE-0"),
+            Some(MetricValue::UnsignedInteger(25))
+        );
+    }
+
+    #[test]
+    fn sum_converts_units_and_skips_other_units() {
+        let metrics = IndexedMetrics::from(vec![
+            energy("rapl", "PACKAGE-0", 1_000_000),
+            metric("nvml", "GPU-0", 500, UnitPrefix::Milli, Unit::Joule),
+            metric(
+                "perf_event",
+                "INSTRUCTIONS",
+                42,
+                UnitPrefix::None,
+                Unit::Count,
+            ),
+        ]);
+        let joules = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit: Unit::Joule,
+        };
+
+        let total = metrics.sum(joules, |_| true).unwrap();
+        assert!((total - 1.5).abs() < 1e-12);
+        assert!(
+            metrics
+                .sum(joules, |metric| metric.source == "perf_event")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn filter_keeps_matching_metrics_indexed() {
+        let metrics = IndexedMetrics::from(vec![
+            energy("rapl", "PACKAGE-0", 10),
+            energy("nvml", "GPU-0", 20),
+        ]);
+
+        let gpus = metrics.filter(|metric| metric.source == "nvml");
+        assert_eq!(gpus.len(), 1);
+        assert!(gpus.get("nvml", "GPU-0").is_some());
+        assert_eq!(Metrics::from(gpus).len(), 1);
+    }
+}
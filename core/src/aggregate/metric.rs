@@ -1,7 +1,9 @@
 use std::fmt::Display;
 
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
+use crate::source::MetricScope;
 use crate::unit::MetricUnit;
 
 /// Represents a single measurable metric collected from a source.
@@ -14,7 +16,7 @@ use crate::unit::MetricUnit;
 /// let unit = MetricUnit { unit: Unit::Joule, prefix: UnitPrefix::Micro };
 /// let energy = Metric::new("energy_pkg", 123456u64, unit, "rapl");
 /// ```
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Metric {
     /// The metric name, (e.g. `energy_pkg`).
     pub name: String,
@@ -27,6 +29,12 @@ pub struct Metric {
 
     /// The source providing this metric (e.g. rapl).
     pub source: String,
+
+    /// Whether the metric is measured for the profiled process or the whole machine.
+    ///
+    /// Results saved before the scope was reported default to the whole machine.
+    #[serde(default)]
+    pub scope: MetricScope,
 }
 
 impl Metric {
@@ -41,6 +49,7 @@ impl Metric {
             value: value.into(),
             unit,
             source: source.into(),
+            scope: MetricScope::default(),
         }
     }
 }
@@ -51,13 +60,129 @@ pub type Metrics = Vec<Metric>;
 /// Enum representing the value of a metric,
 /// with this enum, a metric can be a signed or
 /// unsigned integer or a float.
-#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
 pub enum MetricValue {
     UnsignedInteger(u64),
     SignedInteger(i64),
     Float(f64),
 }
 
+impl MetricValue {
+    /// Adds two values, falling back to a float addition when their types differ.
+    ///
+    /// Integer additions saturate instead of overflowing.
+    pub(crate) fn accumulate(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (Self::UnsignedInteger(a), Self::UnsignedInteger(b)) => {
+                Self::UnsignedInteger(a.saturating_add(b))
+            }
+            (Self::SignedInteger(a), Self::SignedInteger(b)) => {
+                Self::SignedInteger(a.saturating_add(b))
+            }
+            (a, b) => Self::Float(a.as_f64() + b.as_f64()),
+        }
+    }
+
+    /// Converts the value into a float, possibly losing precision for large integers.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Self::UnsignedInteger(v) => v as f64,
+            Self::SignedInteger(v) => v as f64,
+            Self::Float(v) => v,
+        }
+    }
+
+    /// Adds two values, returning `None` on overflow.
+    ///
+    /// Integers of the same type keep their type, mixed integers give a signed integer, or an
+    /// unsigned one when the result only fits in it, and any float gives a float, which must be finite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use joule_profiler_core::types::MetricValue;
+    ///
+    /// let sum = MetricValue::from(2u64).checked_add(MetricValue::from(-3i64));
+    /// assert_eq!(sum, Some(MetricValue::SignedInteger(-1)));
+    /// assert_eq!(MetricValue::from(u64::MAX).checked_add(1u64.into()), None);
+    /// ```
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.checked_op(
+            rhs,
+            u64::checked_add,
+            i64::checked_add,
+            i128::checked_add,
+            |a, b| a + b,
+        )
+    }
+
+    /// Subtracts `rhs` from the value, returning `None` on overflow.
+    ///
+    /// The types follow the rules of [`MetricValue::checked_add`], an unsigned integer result
+    /// being negative thus overflows.
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_op(
+            rhs,
+            u64::checked_sub,
+            i64::checked_sub,
+            i128::checked_sub,
+            |a, b| a - b,
+        )
+    }
+
+    /// Applies an operation with the variant matching the operands, mixed integers being computed
+    /// in `i128` which holds both ranges.
+    fn checked_op(
+        self,
+        rhs: Self,
+        unsigned: fn(u64, u64) -> Option<u64>,
+        signed: fn(i64, i64) -> Option<i64>,
+        mixed: fn(i128, i128) -> Option<i128>,
+        float: fn(f64, f64) -> f64,
+    ) -> Option<Self> {
+        let narrow = |value: i128| {
+            i64::try_from(value)
+                .map(Self::SignedInteger)
+                .or_else(|_| u64::try_from(value).map(Self::UnsignedInteger))
+                .ok()
+        };
+        match (self, rhs) {
+            (Self::UnsignedInteger(a), Self::UnsignedInteger(b)) => {
+                unsigned(a, b).map(Self::UnsignedInteger)
+            }
+            (Self::SignedInteger(a), Self::SignedInteger(b)) => {
+                signed(a, b).map(Self::SignedInteger)
+            }
+            (Self::UnsignedInteger(a), Self::SignedInteger(b)) => {
+                mixed(a.into(), b.into()).and_then(narrow)
+            }
+            (Self::SignedInteger(a), Self::UnsignedInteger(b)) => {
+                mixed(a.into(), b.into()).and_then(narrow)
+            }
+            (a, b) => Some(float(a.as_f64(), b.as_f64()))
+                .filter(|v| v.is_finite())
+                .map(Self::Float),
+        }
+    }
+}
+
+/// Merges `other` into `metrics`, summing the values of the metrics sharing the same name and source.
+pub(crate) fn merge_metrics(metrics: &mut Metrics, other: Metrics) {
+    for metric in other {
+        if let Some(existing) = metrics
+            .iter_mut()
+            .find(|existing| existing.name == metric.name && existing.source == metric.source)
+        {
+            existing.value = existing.value.accumulate(metric.value);
+        } else {
+            metrics.push(metric);
+        }
+    }
+}
+
 impl From<u64> for MetricValue {
     fn from(v: u64) -> Self {
         Self::UnsignedInteger(v)
@@ -83,3 +208,92 @@ impl Display for MetricValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit::{Unit, UnitPrefix};
+
+    fn metric<V: Into<MetricValue>>(name: &str, value: V) -> Metric {
+        let unit = MetricUnit {
+            unit: Unit::Joule,
+            prefix: UnitPrefix::Micro,
+        };
+        Metric::new(name, value, unit, "rapl")
+    }
+
+    #[test]
+    fn accumulate_same_types_keeps_type() {
+        assert_eq!(
+            MetricValue::from(1u64).accumulate(2u64.into()),
+            MetricValue::UnsignedInteger(3)
+        );
+        assert_eq!(
+            MetricValue::from(-1i64).accumulate(2i64.into()),
+            MetricValue::SignedInteger(1)
+        );
+    }
+
+    #[test]
+    fn accumulate_integers_saturates() {
+        assert_eq!(
+            MetricValue::from(u64::MAX).accumulate(1u64.into()),
+            MetricValue::UnsignedInteger(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn accumulate_mixed_types_returns_float() {
+        assert_eq!(
+            MetricValue::from(1u64).accumulate(0.5.into()),
+            MetricValue::Float(1.5)
+        );
+    }
+
+    #[test]
+    fn checked_add_keeps_compatible_types() {
+        assert_eq!(
+            MetricValue::from(1u64).checked_add(2u64.into()),
+            Some(MetricValue::UnsignedInteger(3))
+        );
+        assert_eq!(
+            MetricValue::from(1u64).checked_add((-2i64).into()),
+            Some(MetricValue::SignedInteger(-1))
+        );
+        assert_eq!(
+            MetricValue::from(1i64).checked_add(0.5.into()),
+            Some(MetricValue::Float(1.5))
+        );
+    }
+
+    #[test]
+    fn checked_operations_detect_overflows() {
+        assert_eq!(MetricValue::from(1u64).checked_sub(2u64.into()), None);
+        assert_eq!(MetricValue::from(i64::MIN).checked_sub(1i64.into()), None);
+        assert_eq!(
+            MetricValue::from(u64::MAX).checked_add((-1i64).into()),
+            Some(MetricValue::UnsignedInteger(u64::MAX - 1))
+        );
+        assert_eq!(
+            MetricValue::from(i64::MIN).checked_sub(u64::MAX.into()),
+            None
+        );
+        assert_eq!(
+            MetricValue::from(f64::MAX).checked_add(f64::MAX.into()),
+            None
+        );
+    }
+
+    #[test]
+    fn merge_metrics_sums_same_metrics_and_appends_others() {
+        let mut metrics = vec![metric("PACKAGE-0", 100u64)];
+        merge_metrics(
+            &mut metrics,
+            vec![metric("PACKAGE-0", 50u64), metric("DRAM-0", 10u64)],
+        );
+
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].value, MetricValue::UnsignedInteger(150));
+        assert_eq!(metrics[1].name, "DRAM-0");
+    }
+}
@@ -6,8 +6,75 @@
 //! Metrics are only instantiated *after* measurements finish to avoid runtime
 //! overhead during collection.
 
+pub(crate) mod attribution;
+pub(crate) mod efficiency;
+pub(crate) mod energy;
+mod indexed;
 mod metric;
 pub(crate) mod phase;
+pub(crate) mod power;
 pub(crate) mod sensor_result;
 
+pub use indexed::IndexedMetrics;
+pub(crate) use metric::merge_metrics;
 pub use metric::{Metric, MetricValue, Metrics};
+
+use crate::aggregate::energy::TotalEnergy;
+
+/// Computes the metrics derived from the merged metrics of a phase lasting `duration_ms`.
+///
+/// Returns them along with the rule used to compute the [total energy](`TotalEnergy`) of the phase,
+/// if it has energy metrics. See also the [efficiency](`efficiency`) and [attribution](`attribution`) modules.
+pub(crate) fn derived_metrics(metrics: &Metrics, duration_ms: u128) -> (Metrics, Option<String>) {
+    let mut derived = Metrics::new();
+    let total = TotalEnergy::from_metrics(metrics);
+    if let Some(total) = &total {
+        derived.push(total.to_metric());
+        derived.extend(efficiency::efficiency_metrics(
+            metrics,
+            total.joules,
+            duration_ms,
+        ));
+    }
+    derived.extend(attribution::attributed_metrics(metrics));
+    (derived, total.map(|total| total.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+    fn metric(name: &str, value: u64, unit: Unit) -> Metric {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::None,
+            unit,
+        };
+        Metric::new(name, value, unit, "source")
+    }
+
+    #[test]
+    fn derived_metrics_use_total_energy() {
+        let metrics = vec![
+            metric("PACKAGE-0", 10, Unit::Joule),
+            metric("CORE-0", 6, Unit::Joule),
+            metric("INSTRUCTIONS", 20, Unit::Count),
+        ];
+
+        let (derived, rule) = derived_metrics(&metrics, 1000);
+        let value = |name: &str| derived.iter().find(|m| m.name == name).map(|m| m.value);
+        assert_eq!(value("TOTAL"), Some(MetricValue::Float(10.0)));
+        assert_eq!(
+            value("INSTRUCTIONS_PER_JOULE"),
+            Some(MetricValue::Float(2.0))
+        );
+        assert_eq!(rule.as_deref(), Some("PACKAGE-0 (excluding CORE-0)"));
+    }
+
+    #[test]
+    fn no_energy_metrics_without_energy() {
+        let (derived, rule) = derived_metrics(&vec![metric("INSTRUCTIONS", 20, Unit::Count)], 1000);
+        assert!(derived.is_empty());
+        assert!(rule.is_none());
+    }
+}
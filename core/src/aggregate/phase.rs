@@ -1,18 +1,35 @@
-use crate::aggregate::Metrics;
+use crate::aggregate::{Metrics, merge_metrics};
 use crate::source::types::RawPhase;
 use std::ops::{Add, AddAssign};
 
 /// Aggregated metrics for a sensor phase.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct SensorPhase {
     /// Metrics associated with this phase.
     pub metrics: Metrics,
+
+    /// Readings of the phase, empty if the sources are not polled.
+    pub samples: Vec<PhaseSample>,
+}
+
+/// Metrics read over an interval of a polled phase.
+#[derive(Debug, Clone)]
+pub struct PhaseSample {
+    /// Start timestamp of the interval in microsecond.
+    pub timestamp: u128,
+
+    /// Duration of the interval in microsecond.
+    pub duration_us: u128,
+
+    /// Metrics read over the interval.
+    pub metrics: Metrics,
 }
 
 impl AddAssign for SensorPhase {
     /// Merges metrics from another phase.
     fn add_assign(&mut self, rhs: Self) {
         self.metrics.extend(rhs.metrics);
+        self.samples.extend(rhs.samples);
     }
 }
 
@@ -30,8 +47,13 @@ where
     V: Into<Metrics>,
 {
     fn from(phase: RawPhase<V>) -> Self {
+        let mut metrics = phase.metrics.into();
+        for polled in phase.polled {
+            merge_metrics(&mut metrics, polled.into());
+        }
         SensorPhase {
-            metrics: phase.metrics.into(),
+            metrics,
+            samples: Vec::new(),
         }
     }
 }
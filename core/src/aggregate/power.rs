@@ -0,0 +1,272 @@
+//! Power distribution of the phases.
+//!
+//! When a source is polled, each phase is made of several energy readings, each one covering the
+//! interval between two measures. Dividing the energy of a reading by its interval gives a power
+//! sample, and the samples of a phase describe whether its power was steady or spiky.
+//!
+//! Samples are not weighted by the length of their interval, the last reading of a phase being
+//! usually shorter than the polling interval.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregate::Metrics;
+use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+const JOULE: MetricUnit = MetricUnit {
+    prefix: UnitPrefix::None,
+    unit: Unit::Joule,
+};
+
+/// Power samples of an energy sensor during a phase, in watts.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PowerSeries {
+    pub name: String,
+    pub source: String,
+    pub watts: Vec<f64>,
+}
+
+/// Builds the power series of the energy sensors from consecutive readings, each one given with
+/// the duration of its interval in microseconds.
+///
+/// Readings of a null interval are skipped.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn power_series<'a>(
+    readings: impl IntoIterator<Item = (&'a Metrics, u128)>,
+) -> Vec<PowerSeries> {
+    let mut series: Vec<PowerSeries> = Vec::new();
+    for (metrics, duration_us) in readings {
+        if duration_us == 0 {
+            continue;
+        }
+        let seconds = duration_us as f64 / 1e6;
+        for metric in metrics {
+            let Some(joules) = metric.unit.convert(metric.value.as_f64(), JOULE) else {
+                continue;
+            };
+            let watts = joules / seconds;
+            match series
+                .iter_mut()
+                .find(|s| s.source == metric.source && s.name == metric.name)
+            {
+                Some(s) => s.watts.push(watts),
+                None => series.push(PowerSeries {
+                    name: metric.name.clone(),
+                    source: metric.source.clone(),
+                    watts: vec![watts],
+                }),
+            }
+        }
+    }
+    series
+}
+
+/// Histogram of the power samples, with buckets of equal width from the minimum to the maximum.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct PowerHistogram {
+    /// Lower bound of the first bucket in watts.
+    pub lower: f64,
+
+    /// Width of the buckets in watts.
+    pub bucket_width: f64,
+
+    /// Number of samples in each bucket.
+    pub counts: Vec<usize>,
+}
+
+/// Distribution of the power of an energy sensor during a phase, in watts.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct PowerStats {
+    /// Name of the energy sensor.
+    pub name: String,
+
+    /// Source of the energy sensor.
+    pub source: String,
+
+    /// Number of power samples.
+    pub samples: usize,
+
+    /// Minimum power.
+    pub min: f64,
+
+    /// Maximum power.
+    pub max: f64,
+
+    /// Mean power.
+    pub mean: f64,
+
+    /// Standard deviation of the power.
+    pub std_dev: f64,
+
+    /// Median power.
+    pub p50: f64,
+
+    /// 90th percentile of the power.
+    pub p90: f64,
+
+    /// 99th percentile of the power.
+    pub p99: f64,
+
+    /// Histogram of the samples, if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<PowerHistogram>,
+}
+
+impl PowerStats {
+    /// Computes the distribution of the series, with a histogram of `histogram_buckets` buckets.
+    ///
+    /// Returns `None` with less than two samples, a single reading giving no distribution.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub(crate) fn from_series(
+        series: &PowerSeries,
+        histogram_buckets: Option<usize>,
+    ) -> Option<Self> {
+        if series.watts.len() < 2 {
+            return None;
+        }
+
+        let mut sorted = series.watts.clone();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let variance = sorted.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / n;
+        let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+
+        let histogram = histogram_buckets
+            .filter(|buckets| *buckets > 0)
+            .map(|buckets| {
+                let bucket_width = (max - min) / buckets as f64;
+                let mut counts = vec![0; buckets];
+                for watts in &sorted {
+                    let bucket = if bucket_width > 0.0 {
+                        ((watts - min) / bucket_width) as usize
+                    } else {
+                        0
+                    };
+                    counts[bucket.min(buckets - 1)] += 1;
+                }
+                PowerHistogram {
+                    lower: min,
+                    bucket_width,
+                    counts,
+                }
+            });
+
+        Some(Self {
+            name: series.name.clone(),
+            source: series.source.clone(),
+            samples: sorted.len(),
+            min,
+            max,
+            mean,
+            std_dev: variance.sqrt(),
+            p50: percentile(&sorted, 0.50),
+            p90: percentile(&sorted, 0.90),
+            p99: percentile(&sorted, 0.99),
+            histogram,
+        })
+    }
+}
+
+/// Percentile of sorted samples, linearly interpolated between the closest ranks.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let rank = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - rank.floor())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::Metric;
+
+    fn reading(millijoules: u64) -> Metrics {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Milli,
+            unit: Unit::Joule,
+        };
+        vec![
+            Metric::new("PACKAGE-0", millijoules, unit, "rapl"),
+            Metric::new(
+                "INSTRUCTIONS",
+                10u64,
+                MetricUnit {
+                    prefix: UnitPrefix::None,
+                    unit: Unit::Count,
+                },
+                "perf",
+            ),
+        ]
+    }
+
+    fn series(watts: &[f64]) -> PowerSeries {
+        PowerSeries {
+            name: "PACKAGE-0".into(),
+            source: "rapl".into(),
+            watts: watts.to_vec(),
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn power_is_energy_over_interval() {
+        let readings = [reading(1000), reading(500), reading(100)];
+        let series = power_series([
+            (&readings[0], 100_000),
+            (&readings[1], 0),
+            (&readings[2], 50_000),
+        ]);
+
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].name, "PACKAGE-0");
+        assert_eq!(series[0].watts.len(), 2);
+        assert_close(series[0].watts[0], 10.0);
+        assert_close(series[0].watts[1], 2.0);
+    }
+
+    #[test]
+    fn stats_describe_the_distribution() {
+        let watts: Vec<f64> = (1..=11).map(f64::from).collect();
+        let stats = PowerStats::from_series(&series(&watts), None).unwrap();
+
+        assert_eq!(stats.samples, 11);
+        assert_close(stats.min, 1.0);
+        assert_close(stats.max, 11.0);
+        assert_close(stats.mean, 6.0);
+        assert_close(stats.std_dev, 10f64.sqrt());
+        assert_close(stats.p50, 6.0);
+        assert_close(stats.p90, 10.0);
+        assert_close(stats.p99, 10.9);
+        assert!(stats.histogram.is_none());
+    }
+
+    #[test]
+    fn histogram_buckets_span_min_to_max() {
+        let stats = PowerStats::from_series(&series(&[10.0, 11.0, 12.0, 20.0]), Some(2)).unwrap();
+        let histogram = stats.histogram.unwrap();
+
+        assert_close(histogram.lower, 10.0);
+        assert_close(histogram.bucket_width, 5.0);
+        assert_eq!(histogram.counts, [3, 1]);
+
+        let steady = PowerStats::from_series(&series(&[5.0, 5.0]), Some(3)).unwrap();
+        assert_eq!(steady.histogram.unwrap().counts, [2, 0, 0]);
+    }
+
+    #[test]
+    fn single_sample_has_no_distribution() {
+        assert!(PowerStats::from_series(&series(&[5.0]), None).is_none());
+    }
+}
@@ -50,7 +50,10 @@ mod tests {
     }
 
     fn phase(metrics: Vec<Metric>) -> SensorPhase {
-        SensorPhase { metrics }
+        SensorPhase {
+            metrics,
+            samples: Vec::new(),
+        }
     }
 
     fn result(phases: Vec<SensorPhase>) -> SensorResult {
@@ -0,0 +1,451 @@
+//! Energy budget assertions.
+//!
+//! A [`Budget`] bounds the value of a metric, over the whole run or for the phases matching a name,
+//! e.g. `PACKAGE-0 < 50J` or `phase(__COMPUTE__).GPU-0 < 2kJ`. Budgets are checked against the
+//! profiling results once the run is over, producing a [`BudgetReport`] which tells whether any
+//! budget is exceeded, to make a CI job fail when a benchmark regresses.
+//!
+//! # Syntax
+//!
+//! ```text
+//! [phase(NAME).]METRIC OPERATOR VALUE[UNIT]
+//! ```
+//!
+//! - `NAME` matches the phases by name (e.g. `__A__ -> __B__`) or by start token (e.g. `__A__`).
+//!   Without a phase, the metric is summed over all the phases.
+//! - `OPERATOR` is one of `<`, `<=`, `>` and `>=`.
+//! - `UNIT` is any metric unit with an optional prefix (e.g. `J`, `kJ`, `ms`), the value being
+//!   compared with the metric converted into it. Without a unit, the metric is compared as is.
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufReader;
+use std::str::FromStr;
+
+use log::debug;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregate::IndexedMetrics;
+use crate::profiler::JouleProfilerError;
+use crate::profiler::types::{Phase, ProfilerResults, Result};
+use crate::unit::MetricUnit;
+
+/// Comparison operator of a budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetOperator {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl BudgetOperator {
+    /// Tells whether `value` satisfies the operator against `threshold`.
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            BudgetOperator::Less => value < threshold,
+            BudgetOperator::LessOrEqual => value <= threshold,
+            BudgetOperator::Greater => value > threshold,
+            BudgetOperator::GreaterOrEqual => value >= threshold,
+        }
+    }
+}
+
+impl Display for BudgetOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BudgetOperator::Less => "<",
+            BudgetOperator::LessOrEqual => "<=",
+            BudgetOperator::Greater => ">",
+            BudgetOperator::GreaterOrEqual => ">=",
+        })
+    }
+}
+
+/// A bound on the value of a metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    /// Name or start token of the checked phases, `None` for the whole run.
+    pub phase: Option<String>,
+
+    /// Name of the bounded metric.
+    pub metric: String,
+
+    /// Comparison operator.
+    pub operator: BudgetOperator,
+
+    /// Bound of the metric value.
+    pub threshold: f64,
+
+    /// Unit of the threshold, `None` to compare with the metric in its own unit.
+    pub unit: Option<MetricUnit>,
+}
+
+impl Budget {
+    /// Checks the budget against the results.
+    ///
+    /// Returns a check for the whole run, or one check per matching phase. A budget whose phase or
+    /// metric is not found fails, so that a renamed phase or a missing source does not silently
+    /// disable a CI gate.
+    pub fn check(&self, results: &ProfilerResults) -> Vec<BudgetCheck> {
+        let Some(phase) = &self.phase else {
+            let phases: Vec<&Phase> = results.phases.iter().collect();
+            return vec![self.check_phases(None, &phases)];
+        };
+
+        let checks: Vec<BudgetCheck> = results
+            .phases
+            .iter()
+            .filter(|p| p.get_name() == *phase || p.start_token.to_string() == *phase)
+            .map(|p| self.check_phases(Some(p.get_name()), &[p]))
+            .collect();
+
+        if checks.is_empty() {
+            return vec![self.failed(None, format!("phase {phase} not found"))];
+        }
+        checks
+    }
+
+    /// Checks the budget against the metric summed over `phases`.
+    fn check_phases(&self, phase: Option<String>, phases: &[&Phase]) -> BudgetCheck {
+        let metrics: IndexedMetrics = phases
+            .iter()
+            .flat_map(|p| p.metrics.iter())
+            .filter(|metric| metric.name == self.metric)
+            .fold(IndexedMetrics::new(), |mut metrics, metric| {
+                metrics.accumulate(metric.clone());
+                metrics
+            });
+
+        let Some(first) = metrics.iter().next() else {
+            return self.failed(phase, format!("metric {} not found", self.metric));
+        };
+        let unit = self.unit.unwrap_or(first.unit);
+        let Some(value) = metrics.sum(unit, |_| true) else {
+            return self.failed(
+                phase,
+                format!("metric {} cannot be converted into {unit}", self.metric),
+            );
+        };
+
+        BudgetCheck {
+            assertion: self.to_string(),
+            phase,
+            value: Some(value),
+            unit: Some(unit),
+            threshold: Some(self.threshold),
+            passed: self.operator.holds(value, self.threshold),
+            reason: None,
+        }
+    }
+
+    fn failed(&self, phase: Option<String>, reason: String) -> BudgetCheck {
+        BudgetCheck {
+            assertion: self.to_string(),
+            phase,
+            value: None,
+            unit: self.unit,
+            threshold: Some(self.threshold),
+            passed: false,
+            reason: Some(reason),
+        }
+    }
+}
+
+impl FromStr for Budget {
+    type Err = JouleProfilerError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |msg: &str| JouleProfilerError::InvalidBudget(format!("{s}: {msg}"));
+
+        let (phase, rest) = match s.trim_start().strip_prefix("phase(") {
+            Some(rest) => {
+                let (phase, rest) = rest
+                    .split_once(").")
+                    .ok_or_else(|| invalid("expected phase(NAME).METRIC"))?;
+                (Some(phase.trim().to_string()), rest)
+            }
+            None => (None, s),
+        };
+
+        let index = rest
+            .find(['<', '>'])
+            .ok_or_else(|| invalid("expected one of <, <=, > and >="))?;
+        let (metric, rest) = rest.split_at(index);
+        let (operator, rhs) = match (rest.starts_with('<'), rest[1..].strip_prefix('=')) {
+            (true, None) => (BudgetOperator::Less, &rest[1..]),
+            (true, Some(rhs)) => (BudgetOperator::LessOrEqual, rhs),
+            (false, None) => (BudgetOperator::Greater, &rest[1..]),
+            (false, Some(rhs)) => (BudgetOperator::GreaterOrEqual, rhs),
+        };
+
+        let metric = metric.trim();
+        if metric.is_empty() || metric.contains(char::is_whitespace) {
+            return Err(invalid("invalid metric name"));
+        }
+
+        let rhs = rhs.trim();
+        let (threshold, unit) = (1..=rhs.len())
+            .rev()
+            .filter(|&i| rhs.is_char_boundary(i))
+            .find_map(|i| {
+                let threshold = rhs[..i].parse::<f64>().ok()?;
+                Some((threshold, rhs[i..].trim()))
+            })
+            .ok_or_else(|| invalid("expected a number"))?;
+        if !threshold.is_finite() {
+            return Err(invalid("the value must be finite"));
+        }
+        let unit = (!unit.is_empty())
+            .then(|| MetricUnit::try_from(unit))
+            .transpose()
+            .map_err(|_| invalid("unknown unit"))?;
+
+        Ok(Self {
+            phase,
+            metric: metric.to_string(),
+            operator,
+            threshold,
+            unit,
+        })
+    }
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(phase) = &self.phase {
+            write!(f, "phase({phase}).")?;
+        }
+        write!(f, "{} {} {}", self.metric, self.operator, self.threshold)?;
+        if let Some(unit) = self.unit {
+            write!(f, "{unit}")?;
+        }
+        Ok(())
+    }
+}
+
+/// File of budgets, as a JSON object.
+///
+/// # Examples
+///
+/// ```json
+/// {
+///     "budgets": ["PACKAGE-0 < 50J", "phase(__COMPUTE__).GPU-0 < 2kJ"]
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BudgetsFile {
+    budgets: Vec<String>,
+}
+
+/// Loads and parses the budgets of a JSON file.
+pub fn budgets_from_file(path: &str) -> Result<Vec<Budget>> {
+    debug!("Loading budgets from {path}");
+    let reader = BufReader::new(File::open(path)?);
+    let file: BudgetsFile = serde_json::from_reader(reader)
+        .map_err(|err| JouleProfilerError::InvalidBudget(format!("{path}: {err}")))?;
+
+    file.budgets.iter().map(|budget| budget.parse()).collect()
+}
+
+/// Outcome of a budget for the whole run or a phase.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct BudgetCheck {
+    /// The checked budget.
+    pub assertion: String,
+
+    /// Name of the checked phase, `None` for the whole run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+
+    /// Value of the metric, `None` if it could not be computed.
+    pub value: Option<f64>,
+
+    /// Unit of the value.
+    pub unit: Option<MetricUnit>,
+
+    /// Bound of the value, in its unit when known. `None` in results saved before it was reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+
+    /// Whether the budget is respected.
+    pub passed: bool,
+
+    /// Why the budget could not be checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Outcome of all the budgets of a run.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq)]
+pub struct BudgetReport {
+    /// Checks in budget order.
+    pub checks: Vec<BudgetCheck>,
+}
+
+impl BudgetReport {
+    /// Checks the budgets against the results.
+    pub fn check(budgets: &[Budget], results: &ProfilerResults) -> Self {
+        Self {
+            checks: budgets
+                .iter()
+                .flat_map(|budget| budget.check(results))
+                .collect(),
+        }
+    }
+
+    /// Tells whether every budget is respected.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Checks of the exceeded budgets.
+    pub fn violations(&self) -> impl Iterator<Item = &BudgetCheck> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase::PhaseToken;
+    use crate::types::Metric;
+    use crate::unit::{Unit, UnitPrefix};
+
+    const MICRO_JOULE: MetricUnit = MetricUnit {
+        prefix: UnitPrefix::Micro,
+        unit: Unit::Joule,
+    };
+
+    fn phase(start: &str, end: &str, metrics: &[(&str, u64)]) -> Phase {
+        Phase {
+            index: 0,
+            start_token: PhaseToken::Token(start.into()),
+            end_token: PhaseToken::Token(end.into()),
+            timestamp: 0,
+            duration_ms: 0,
+            start_token_line: None,
+            end_token_line: None,
+            metrics: metrics
+                .iter()
+                .map(|(name, value)| Metric::new(*name, *value, MICRO_JOULE, "rapl"))
+                .collect(),
+            total_energy_rule: None,
+            power: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn results() -> ProfilerResults {
+        ProfilerResults {
+            timestamp: 0,
+            duration_ms: 0,
+            exit_code: 0,
+            phases: vec![
+                phase("__INIT__", "__COMPUTE__", &[("PACKAGE-0", 10_000_000)]),
+                phase(
+                    "__COMPUTE__",
+                    "__END__",
+                    &[("PACKAGE-0", 30_000_000), ("GPU-0", 5_000)],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn budget_is_parsed() {
+        let budget: Budget = "phase(__COMPUTE__).GPU-0 <= 2kJ".parse().unwrap();
+        assert_eq!(budget.phase.as_deref(), Some("__COMPUTE__"));
+        assert_eq!(budget.metric, "GPU-0");
+        assert_eq!(budget.operator, BudgetOperator::LessOrEqual);
+        assert!((budget.threshold - 2.0).abs() < f64::EPSILON);
+        assert_eq!(budget.unit.unwrap().to_string(), "kJ");
+        assert_eq!(budget.to_string(), "phase(__COMPUTE__).GPU-0 <= 2kJ");
+
+        let budget: Budget = "INSTRUCTIONS>1e9".parse().unwrap();
+        assert_eq!(budget.operator, BudgetOperator::Greater);
+        assert!((budget.threshold - 1e9).abs() < f64::EPSILON);
+        assert_eq!(budget.unit, None);
+    }
+
+    #[test]
+    fn invalid_budgets_are_rejected() {
+        for budget in [
+            "PACKAGE-0 50J",
+            "PACKAGE-0 < J",
+            "PACKAGE-0 < 50furlongs",
+            "< 50J",
+            "phase(A GPU-0 < 1J",
+        ] {
+            assert!(
+                matches!(
+                    budget.parse::<Budget>(),
+                    Err(JouleProfilerError::InvalidBudget(_))
+                ),
+                "{budget}"
+            );
+        }
+    }
+
+    #[test]
+    fn run_budget_sums_the_phases() {
+        let budget: Budget = "PACKAGE-0 < 50J".parse().unwrap();
+        let checks = budget.check(&results());
+        assert_eq!(checks.len(), 1);
+        assert!(checks[0].passed);
+        assert!((checks[0].value.unwrap() - 40.0).abs() < 1e-9);
+
+        let budget: Budget = "PACKAGE-0 < 40J".parse().unwrap();
+        assert!(!budget.check(&results())[0].passed);
+    }
+
+    #[test]
+    fn phase_budget_checks_the_matching_phases() {
+        let budget: Budget = "phase(__COMPUTE__).PACKAGE-0 < 20J".parse().unwrap();
+        let checks = budget.check(&results());
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].phase.as_deref(), Some("__COMPUTE__ -> __END__"));
+        assert!(!checks[0].passed);
+
+        let budget: Budget = "phase(__INIT__ -> __COMPUTE__).PACKAGE-0 < 20J"
+            .parse()
+            .unwrap();
+        assert!(budget.check(&results())[0].passed);
+    }
+
+    #[test]
+    fn budget_without_unit_uses_the_metric_unit() {
+        let budget: Budget = "phase(__COMPUTE__).GPU-0 >= 5000".parse().unwrap();
+        let checks = budget.check(&results());
+        assert!(checks[0].passed);
+        assert_eq!(checks[0].unit, Some(MICRO_JOULE));
+    }
+
+    #[test]
+    fn missing_phase_or_metric_fails() {
+        let report = BudgetReport::check(
+            &[
+                "phase(__MISSING__).PACKAGE-0 < 1J".parse().unwrap(),
+                "DRAM-0 < 1J".parse().unwrap(),
+                "PACKAGE-0 < 1s".parse().unwrap(),
+            ],
+            &results(),
+        );
+        assert!(!report.passed());
+        assert_eq!(report.violations().count(), 3);
+        assert!(report.checks.iter().all(|check| check.reason.is_some()));
+    }
+
+    #[test]
+    fn budgets_are_loaded_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("budgets.json");
+        std::fs::write(&path, r#"{"budgets": ["PACKAGE-0 < 50J", "GPU-0 < 2kJ"]}"#).unwrap();
+
+        let budgets = budgets_from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(budgets.len(), 2);
+        assert_eq!(budgets[1].metric, "GPU-0");
+    }
+}
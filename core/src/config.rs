@@ -13,6 +13,7 @@
 //!     cmd: vec!["sleep".into(), "1".into()],
 //!     token_pattern: "__[A-Z0-9_]+__".into(),
 //!     use_root: false,
+//!     metrics_record_file: None,
 //! };
 //!
 //! let config = Config {
@@ -22,13 +23,18 @@
 //! ```
 
 use derive_builder::Builder;
+use serde::Serialize;
+
+use crate::aggregate::energy::TOTAL_ENERGY;
+use crate::unit::MetricUnit;
 
 const PHASE_TOKEN_DEFAULT_REGEX_PATTERN: &str = "__[A-Z0-9_]+__";
 
 /// Top-level configuration for Joule Profiler.
 #[derive(Debug)]
 pub struct Config {
-    /// Action to run (profile a program or list sensors).
+    /// Action to run (profile a program, list sensors, replay a recording, compare or report
+    /// results).
     pub command: Command,
 
     /// Override the base path used to read Intel RAPL counters.
@@ -43,6 +49,18 @@ pub enum Command {
 
     /// List available sensors.
     ListSensors,
+
+    /// Replay a recorded profiling run.
+    Replay(ReplayConfig),
+
+    /// Compare saved profiling results.
+    Compare(CompareConfig),
+
+    /// Render saved profiling results.
+    Report(ReportConfig),
+
+    /// Print the JSON Schema of the saved results.
+    Schema,
 }
 
 /// Configuration for program profiling.
@@ -61,4 +79,102 @@ pub struct ProfileConfig {
 
     /// Executes the profiled command with root privileges if true and Joule Profiler is launched as root.
     pub use_root: bool,
+
+    /// Optional file to record the metrics converted by the sources into, to replay them later.
+    #[builder(default, setter(strip_option))]
+    pub metrics_record_file: Option<String>,
+}
+
+/// Configuration for the comparison of saved results.
+#[derive(Debug, Clone)]
+pub struct CompareConfig {
+    /// The JSON results files, the first one being the baseline.
+    pub results_files: Vec<String>,
+
+    /// Significance level of the statistical tests (e.g. 0.05).
+    pub significance_level: f64,
+
+    /// Handling of the warm-up and outlier iterations.
+    pub outlier_filter: OutlierFilter,
+}
+
+/// Statistical filter of the outlier iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlierMethod {
+    /// Excludes the iterations outside of `[Q1 - 1.5 IQR, Q3 + 1.5 IQR]`.
+    Iqr,
+
+    /// Excludes the iterations whose modified z-score, based on the median absolute deviation,
+    /// is greater than 3.5.
+    Mad,
+}
+
+/// Handling of the warm-up and outlier iterations of the compared phases.
+///
+/// The filters are applied in order: the warm-up iterations are dropped first, then the outliers,
+/// and the best iterations are finally kept among the remaining ones.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutlierFilter {
+    /// Number of first iterations of each phase to drop.
+    pub warmup: usize,
+
+    /// Statistical filter of the outlier iterations, if any.
+    pub method: Option<OutlierMethod>,
+
+    /// Number of iterations to keep, the ones with the lowest value of the metric.
+    pub keep_best: Option<usize>,
+
+    /// Name of the metric deciding which iterations are outliers.
+    pub metric: String,
+}
+
+impl Default for OutlierFilter {
+    fn default() -> Self {
+        Self {
+            warmup: 0,
+            method: None,
+            keep_best: None,
+            metric: TOTAL_ENERGY.to_string(),
+        }
+    }
+}
+
+impl OutlierFilter {
+    /// Tells whether the filter keeps every iteration.
+    pub fn is_disabled(&self) -> bool {
+        self.warmup == 0 && self.method.is_none() && self.keep_best.is_none()
+    }
+}
+
+/// Configuration for the replay of a recording.
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// The recording file to replay.
+    pub recording_file: String,
+}
+
+/// Configuration for the rendering of saved results.
+#[derive(Debug, Clone)]
+pub struct ReportConfig {
+    /// The JSON results file to render.
+    pub results_file: String,
+
+    /// Selection and conversion of the rendered metrics.
+    pub filter: ReportFilter,
+}
+
+/// Selection of the phases and sensors of saved results, and conversion of their units.
+///
+/// An empty list keeps every phase or sensor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportFilter {
+    /// Names or start tokens of the kept phases.
+    pub phases: Vec<String>,
+
+    /// Names of the kept sensors, optionally prefixed by their source (e.g. `rapl/PACKAGE-0`).
+    pub sensors: Vec<String>,
+
+    /// Units the metrics are converted into, each applying to the metrics of the same base unit.
+    pub units: Vec<MetricUnit>,
 }
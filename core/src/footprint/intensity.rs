@@ -0,0 +1,133 @@
+//! Carbon intensity of the electricity grid.
+
+use std::fs;
+
+use crate::profiler::JouleProfilerError;
+use crate::profiler::types::Result;
+
+/// Carbon intensity of the consumed electricity, in gCO2e/kWh.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CarbonIntensity {
+    /// The same intensity for every phase.
+    Constant(f64),
+
+    /// Intensities varying over time, sorted by timestamp.
+    TimeSeries(Vec<IntensitySample>),
+}
+
+/// Carbon intensity of the grid from a given instant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntensitySample {
+    /// Unix timestamp in microseconds.
+    pub timestamp: u128,
+
+    /// Carbon intensity in gCO2e/kWh.
+    pub intensity: f64,
+}
+
+impl CarbonIntensity {
+    /// Loads a time series from a CSV file of `timestamp,intensity` lines.
+    ///
+    /// Timestamps are Unix timestamps in seconds, possibly fractional, and intensities are in
+    /// gCO2e/kWh. A header line is allowed, as well as empty lines and `#` comments.
+    pub fn from_csv_file(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::from_csv(&content)
+            .map_err(|err| JouleProfilerError::InvalidFootprintConfig(format!("{path}: {err}")))
+    }
+
+    fn from_csv(content: &str) -> std::result::Result<Self, String> {
+        let mut samples = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let sample = parse_sample(line);
+            match sample {
+                Some(sample) => samples.push(sample),
+                None if index == 0 => {}
+                None => return Err(format!("invalid line {}: {line}", index + 1)),
+            }
+        }
+
+        if samples.is_empty() {
+            return Err("no carbon intensity sample".into());
+        }
+        samples.sort_by_key(|sample| sample.timestamp);
+        Ok(Self::TimeSeries(samples))
+    }
+
+    /// Returns the intensity at `timestamp` in microseconds.
+    ///
+    /// A time series gives the last sample made at or before the timestamp, or the first sample
+    /// if the timestamp precedes the whole series.
+    pub fn at(&self, timestamp: u128) -> f64 {
+        match self {
+            Self::Constant(intensity) => *intensity,
+            Self::TimeSeries(samples) => {
+                let index = samples.partition_point(|sample| sample.timestamp <= timestamp);
+                samples[index.saturating_sub(1)].intensity
+            }
+        }
+    }
+}
+
+fn parse_sample(line: &str) -> Option<IntensitySample> {
+    let (timestamp, intensity) = line.split_once(',')?;
+    let timestamp = timestamp.trim().parse::<f64>().ok()?;
+    let intensity = intensity.trim().parse::<f64>().ok()?;
+    if !timestamp.is_finite() || timestamp < 0.0 || !intensity.is_finite() || intensity < 0.0 {
+        return None;
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let timestamp = (timestamp * 1e6).round() as u128;
+    Some(IntensitySample {
+        timestamp,
+        intensity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u128 = 1_000_000;
+
+    #[test]
+    fn parse_csv_with_header_and_comments() {
+        let intensity =
+            CarbonIntensity::from_csv("timestamp,intensity\n# grid\n20,300\n10,100.5\n\n").unwrap();
+        assert_eq!(
+            intensity,
+            CarbonIntensity::TimeSeries(vec![
+                IntensitySample {
+                    timestamp: 10 * SECOND,
+                    intensity: 100.5
+                },
+                IntensitySample {
+                    timestamp: 20 * SECOND,
+                    intensity: 300.0
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_csv_is_rejected() {
+        assert!(CarbonIntensity::from_csv("10,100\n20,abc\n").is_err());
+        assert!(CarbonIntensity::from_csv("10,-5\n").is_err());
+        assert!(CarbonIntensity::from_csv("timestamp,intensity\n").is_err());
+    }
+
+    #[test]
+    fn time_series_matches_last_sample_before_timestamp() {
+        let intensity = CarbonIntensity::from_csv("10,100\n20,200\n30,300\n").unwrap();
+        assert!((intensity.at(5 * SECOND) - 100.0).abs() < f64::EPSILON);
+        assert!((intensity.at(20 * SECOND) - 200.0).abs() < f64::EPSILON);
+        assert!((intensity.at(29 * SECOND) - 200.0).abs() < f64::EPSILON);
+        assert!((intensity.at(100 * SECOND) - 300.0).abs() < f64::EPSILON);
+    }
+}
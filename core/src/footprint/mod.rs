@@ -0,0 +1,349 @@
+//! Carbon and cost estimation from the measured energy.
+//!
+//! A [`Footprint`] is an optional post-processing stage converting the [total energy] of each phase
+//! into CO2 equivalent emissions and electricity cost. The energy is first scaled by the power usage
+//! effectiveness (PUE) of the facility, then converted to kWh and multiplied by the carbon intensity
+//! of the grid and the electricity price.
+//!
+//! The carbon intensity is either constant, or a time series matched with the middle of each phase.
+//!
+//! [total energy]: `crate::types::TotalEnergy`
+
+use std::fs::File;
+use std::io::BufReader;
+
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::aggregate::Metric;
+use crate::aggregate::energy::TotalEnergy;
+use crate::profiler::JouleProfilerError;
+use crate::profiler::types::{Phase, ProfilerResults, Result};
+use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+mod intensity;
+
+pub use intensity::{CarbonIntensity, IntensitySample};
+
+/// Source name of the footprint metrics.
+pub const FOOTPRINT_SOURCE_NAME: &str = "footprint";
+
+/// Number of joules in a kilowatt-hour.
+const JOULES_PER_KWH: f64 = 3.6e6;
+
+/// Configuration of the carbon and cost estimation.
+///
+/// Every field is optional, so that a configuration file can be partially overridden by the CLI.
+///
+/// # Examples
+///
+/// ```json
+/// {
+///     "carbon_intensity_file": "grid.csv",
+///     "price_per_kwh": 0.25,
+///     "pue": 1.4
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FootprintConfig {
+    /// Constant carbon intensity of the grid in gCO2e/kWh.
+    pub carbon_intensity: Option<f64>,
+
+    /// CSV file of the carbon intensity time series, see [`CarbonIntensity::from_csv_file`].
+    pub carbon_intensity_file: Option<String>,
+
+    /// Electricity price per kWh.
+    pub price_per_kwh: Option<f64>,
+
+    /// Power usage effectiveness of the facility, at least 1.
+    pub pue: Option<f64>,
+}
+
+impl FootprintConfig {
+    /// Loads a configuration from a JSON file.
+    pub fn from_file(path: &str) -> Result<Self> {
+        debug!("Loading footprint configuration from {path}");
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader)
+            .map_err(|err| JouleProfilerError::InvalidFootprintConfig(format!("{path}: {err}")))
+    }
+
+    /// Overrides the configured values with the ones set in `overrides`.
+    ///
+    /// A carbon intensity, constant or time series, replaces both carbon intensity settings.
+    #[must_use]
+    pub fn override_with(self, overrides: Self) -> Self {
+        let intensity_overridden =
+            overrides.carbon_intensity.is_some() || overrides.carbon_intensity_file.is_some();
+        let (carbon_intensity, carbon_intensity_file) = if intensity_overridden {
+            (overrides.carbon_intensity, overrides.carbon_intensity_file)
+        } else {
+            (self.carbon_intensity, self.carbon_intensity_file)
+        };
+
+        Self {
+            carbon_intensity,
+            carbon_intensity_file,
+            price_per_kwh: overrides.price_per_kwh.or(self.price_per_kwh),
+            pue: overrides.pue.or(self.pue),
+        }
+    }
+}
+
+/// Carbon and cost estimation stage.
+#[derive(Debug, Clone)]
+pub struct Footprint {
+    carbon_intensity: Option<CarbonIntensity>,
+    price_per_kwh: Option<f64>,
+    pue: Option<f64>,
+}
+
+impl Footprint {
+    /// Builds the estimation stage from its configuration, loading the carbon intensity time series.
+    ///
+    /// Returns `None` if the configuration does not set any value.
+    pub fn new(config: &FootprintConfig) -> Result<Option<Self>> {
+        let invalid = |msg: &str| Err(JouleProfilerError::InvalidFootprintConfig(msg.into()));
+
+        let carbon_intensity = match (config.carbon_intensity, &config.carbon_intensity_file) {
+            (Some(_), Some(_)) => {
+                return invalid("a constant carbon intensity and a time series are both set");
+            }
+            (Some(intensity), None) if !is_at_least(intensity, 0.0) => {
+                return invalid("the carbon intensity must be a positive number");
+            }
+            (Some(intensity), None) => Some(CarbonIntensity::Constant(intensity)),
+            (None, Some(path)) => Some(CarbonIntensity::from_csv_file(path)?),
+            (None, None) => None,
+        };
+        if config
+            .price_per_kwh
+            .is_some_and(|price| !is_at_least(price, 0.0))
+        {
+            return invalid("the electricity price must be a positive number");
+        }
+        if config.pue.is_some_and(|pue| !is_at_least(pue, 1.0)) {
+            return invalid("the PUE must be greater than or equal to 1");
+        }
+
+        if carbon_intensity.is_none() && config.price_per_kwh.is_none() && config.pue.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            carbon_intensity,
+            price_per_kwh: config.price_per_kwh,
+            pue: config.pue,
+        }))
+    }
+
+    /// Adds the footprint metrics to the phases whose total energy is known.
+    ///
+    /// The facility energy is added if a PUE is configured, the emissions if a carbon intensity
+    /// is configured and the cost if an electricity price is configured.
+    pub fn apply(&self, results: &mut ProfilerResults) {
+        info!("Estimating the carbon footprint and cost of the phases");
+        for phase in &mut results.phases {
+            self.apply_to_phase(phase);
+        }
+    }
+
+    /// Adds the footprint metrics to a phase whose total energy is known.
+    pub fn apply_to_phase(&self, phase: &mut Phase) {
+        let Some(total) = TotalEnergy::from_metrics(&phase.metrics) else {
+            debug!("Phase {} has no energy metrics", phase.get_name());
+            return;
+        };
+        let facility_energy = total.joules * self.pue.unwrap_or(1.0);
+        let energy_kwh = facility_energy / JOULES_PER_KWH;
+
+        if self.pue.is_some() {
+            phase
+                .metrics
+                .push(metric("FACILITY_ENERGY", facility_energy, Unit::Joule));
+        }
+        if let Some(carbon_intensity) = &self.carbon_intensity {
+            let middle = phase.timestamp + phase.duration_ms * 1000 / 2;
+            let emissions = energy_kwh * carbon_intensity.at(middle);
+            phase
+                .metrics
+                .push(metric("CARBON_EMISSIONS", emissions, Unit::GramCo2e));
+        }
+        if let Some(price_per_kwh) = self.price_per_kwh {
+            phase.metrics.push(metric(
+                "ENERGY_COST",
+                energy_kwh * price_per_kwh,
+                Unit::Currency,
+            ));
+        }
+
+        phase.metrics.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+/// Whether `value` is a finite number greater than or equal to `min`.
+fn is_at_least(value: f64, min: f64) -> bool {
+    value.is_finite() && value >= min
+}
+
+fn metric(name: &str, value: f64, unit: Unit) -> Metric {
+    let unit = MetricUnit {
+        prefix: UnitPrefix::None,
+        unit,
+    };
+    Metric::new(name, value, unit, FOOTPRINT_SOURCE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase::PhaseToken;
+    use crate::types::MetricValue;
+
+    fn results() -> ProfilerResults {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        ProfilerResults {
+            timestamp: 0,
+            duration_ms: 1000,
+            exit_code: 0,
+            phases: vec![Phase {
+                index: 0,
+                start_token: PhaseToken::Start,
+                end_token: PhaseToken::End,
+                timestamp: 0,
+                duration_ms: 1000,
+                start_token_line: None,
+                end_token_line: None,
+                // 1.8 MJ, i.e. 0.5 kWh.
+                metrics: vec![Metric::new("PACKAGE-0", 1_800_000_000_000u64, unit, "rapl")],
+                total_energy_rule: None,
+                power: Vec::new(),
+                samples: Vec::new(),
+            }],
+        }
+    }
+
+    fn value(results: &ProfilerResults, name: &str) -> Option<f64> {
+        results.phases[0]
+            .metrics
+            .iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| match metric.value {
+                MetricValue::Float(v) => v,
+                _ => panic!("footprint metrics are floats"),
+            })
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn empty_config_has_no_footprint() {
+        assert!(
+            Footprint::new(&FootprintConfig::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn emissions_and_cost_include_pue() {
+        let footprint = Footprint::new(&FootprintConfig {
+            carbon_intensity: Some(100.0),
+            price_per_kwh: Some(0.2),
+            pue: Some(1.5),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap();
+
+        let mut results = results();
+        footprint.apply(&mut results);
+
+        assert_close(value(&results, "FACILITY_ENERGY"), 2_700_000.0);
+        assert_close(value(&results, "CARBON_EMISSIONS"), 75.0);
+        assert_close(value(&results, "ENERGY_COST"), 0.15);
+    }
+
+    #[test]
+    fn only_configured_metrics_are_added() {
+        let footprint = Footprint::new(&FootprintConfig {
+            price_per_kwh: Some(0.2),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap();
+
+        let mut results = results();
+        footprint.apply(&mut results);
+
+        assert_close(value(&results, "ENERGY_COST"), 0.1);
+        assert!(value(&results, "FACILITY_ENERGY").is_none());
+        assert!(value(&results, "CARBON_EMISSIONS").is_none());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        for config in [
+            FootprintConfig {
+                pue: Some(0.9),
+                ..Default::default()
+            },
+            FootprintConfig {
+                carbon_intensity: Some(f64::NAN),
+                ..Default::default()
+            },
+            FootprintConfig {
+                carbon_intensity: Some(100.0),
+                carbon_intensity_file: Some("grid.csv".into()),
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                Footprint::new(&config),
+                Err(JouleProfilerError::InvalidFootprintConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn overrides_replace_carbon_intensity_settings() {
+        let file = FootprintConfig {
+            carbon_intensity_file: Some("grid.csv".into()),
+            price_per_kwh: Some(0.2),
+            ..Default::default()
+        };
+        let config = file.override_with(FootprintConfig {
+            carbon_intensity: Some(50.0),
+            pue: Some(1.2),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            config,
+            FootprintConfig {
+                carbon_intensity: Some(50.0),
+                carbon_intensity_file: None,
+                price_per_kwh: Some(0.2),
+                pue: Some(1.2),
+            }
+        );
+    }
+
+    #[test]
+    fn config_file_is_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("footprint.json");
+        std::fs::write(&path, r#"{"carbon_intensity": 56, "pue": 1.3}"#).unwrap();
+
+        let config = FootprintConfig::from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(config.carbon_intensity, Some(56.0));
+        assert_eq!(config.pue, Some(1.3));
+    }
+}
@@ -1,8 +1,11 @@
 mod aggregate;
+pub mod budget;
 pub mod config;
+pub mod footprint;
 mod orchestrator;
 mod phase;
 mod profiler;
+pub mod recording;
 pub mod sensor;
 
 mod util;
@@ -10,11 +13,18 @@ pub use util::fs;
 
 pub mod source;
 
-pub use profiler::{JouleProfiler, JouleProfilerError};
+pub use profiler::{JouleProfiler, JouleProfilerError, ProfilingSession};
 
 pub mod unit;
 pub mod types {
-    pub use super::aggregate::{Metric, MetricValue, Metrics, sensor_result::SensorResult};
+    pub use super::aggregate::energy::{EnergyDomain, TOTAL_ENERGY, TotalEnergy};
+    pub use super::aggregate::phase::PhaseSample;
+    pub use super::aggregate::power::{PowerHistogram, PowerStats};
+    pub use super::aggregate::{
+        IndexedMetrics, Metric, MetricValue, Metrics, sensor_result::SensorResult,
+    };
     pub use super::phase::PhaseToken;
-    pub use super::profiler::types::{Phase, Phases, ProfilerResults};
+    pub use super::profiler::types::{
+        Phase, PhaseListener, Phases, ProfilerResults, RESULTS_SCHEMA_VERSION, SavedResults,
+    };
 }
@@ -2,11 +2,13 @@
 //!
 //! This module defines the core logic for metric sources orchestration through [`SourceOrchestrator`] structure.
 
+use crate::aggregate::phase::SensorPhase;
 use crate::aggregate::sensor_result::SensorResult;
 use crate::orchestrator::error::OrchestratorError;
 use crate::source::types::SourceEvent;
-use crate::source::{MetricSource, MetricSourceError};
+use crate::source::{MetricSource, MetricSourceError, SourceCapabilities};
 use futures::future::try_join_all;
+use log::debug;
 use tokio::{
     sync::{mpsc, oneshot},
     task::JoinHandle,
@@ -33,6 +35,12 @@ struct SourceHandle {
 #[derive(Default)]
 pub struct SourceOrchestrator {
     handles: Vec<SourceHandle>,
+
+    /// The name and capabilities of each running source.
+    capabilities: Vec<(&'static str, SourceCapabilities)>,
+
+    /// Receivers of the phases streamed by each source, empty if the phases are not streamed.
+    phase_receivers: Vec<mpsc::UnboundedReceiver<SensorPhase>>,
 }
 
 impl SourceOrchestrator {
@@ -48,8 +56,16 @@ impl SourceOrchestrator {
 
         let nb_sources = sources.len();
         let mut handles = Vec::with_capacity(nb_sources);
+        let mut capabilities = Vec::with_capacity(nb_sources);
 
         for source in sources {
+            let source_capabilities = source.capabilities();
+            debug!(
+                "Source {} capabilities: {source_capabilities:?}",
+                source.name()
+            );
+            capabilities.push((source.name(), source_capabilities));
+
             let (handle, control_sender, init_sender) = source.run();
             handles.push(SourceHandle {
                 handle,
@@ -59,10 +75,42 @@ impl SourceOrchestrator {
         }
 
         self.handles = handles;
+        self.capabilities = capabilities;
 
         Ok(())
     }
 
+    /// Streams the phases of the next run of the sources as they complete.
+    ///
+    /// Must be called before [`run`](Self::run), the completed phases are then retrieved with
+    /// [`next_phase`](Self::next_phase).
+    pub fn stream_phases(&mut self, sources: &mut [Box<dyn MetricSource>]) {
+        self.phase_receivers = sources
+            .iter_mut()
+            .map(|source| source.stream_phases())
+            .collect();
+    }
+
+    /// Waits for the last completed phase of every source and merges them.
+    ///
+    /// Returns `None` if the phases are not streamed or if a source stopped, its error being
+    /// returned when the sources are finalized.
+    pub async fn next_phase(&mut self) -> Option<SensorPhase> {
+        if self.phase_receivers.is_empty() {
+            return None;
+        }
+        let mut merged = SensorPhase::default();
+        for receiver in &mut self.phase_receivers {
+            merged += receiver.recv().await?;
+        }
+        Some(merged)
+    }
+
+    /// Returns the name and capabilities of the sources of the last run.
+    pub fn capabilities(&self) -> &[(&'static str, SourceCapabilities)] {
+        &self.capabilities
+    }
+
     /// Measures the metrics of each metric source.
     #[inline]
     pub async fn measure(&mut self) -> Result<(), OrchestratorError> {
@@ -91,6 +139,12 @@ impl SourceOrchestrator {
         self.send_event(SourceEvent::NewPhase).await
     }
 
+    /// Polls each metric source within the current phase.
+    #[inline]
+    pub async fn poll(&mut self) -> Result<(), OrchestratorError> {
+        self.send_event(SourceEvent::Poll).await
+    }
+
     /// Retrieves and merge results from all sources.
     ///
     /// Returns a tuple containing the aggregated results and the list of the metric sources in order to reuse them.
@@ -102,6 +156,7 @@ impl SourceOrchestrator {
     pub async fn finalize(
         &mut self,
     ) -> Result<(SensorResult, Vec<Box<dyn MetricSource>>), OrchestratorError> {
+        self.phase_receivers.clear();
         let (results, sources) = self.join_all().await?;
         let merged = SensorResult::merge(results).ok_or(OrchestratorError::NotEnoughSnapshots)?;
         Ok((merged, sources))
@@ -185,7 +240,7 @@ mod tests {
     use crate::{sensor::Sensors, source::MetricReader, types::Metrics};
 
     use super::*;
-    use std::sync::{Arc, Mutex};
+    use std::sync::{Arc, Mutex, Once};
 
     #[derive(Debug)]
     pub struct MockError;
@@ -223,7 +278,18 @@ mod tests {
         measure: usize,
     }
 
+    /// Registers the `get_name` expectation once for all tests, static expectations being global.
+    fn expect_mock_name() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            let context = MockMetricReader::get_name_context();
+            context.expect().return_const("mock");
+            std::mem::forget(context);
+        });
+    }
+
     fn mock_reader() -> (MockMetricReader, Arc<Mutex<State>>) {
+        expect_mock_name();
         let state_arc = Arc::new(Mutex::new(State::default()));
         let mut mock = MockMetricReader::new();
 
@@ -247,9 +313,10 @@ mod tests {
             Ok(())
         });
 
+        mock.expect_retrieve().returning(|| Ok(()));
         mock.expect_get_sensors().returning(|| Ok(vec![]));
         mock.expect_to_metrics()
-            .returning(|_| Ok(Metrics::default()));
+            .returning(|()| Ok(Metrics::default()));
 
         (mock, state_arc)
     }
@@ -272,6 +339,26 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn next_phase_waits_for_every_source() {
+        let (first, _) = mock_source();
+        let (second, _) = mock_source();
+        let mut sources = vec![first, second];
+        let mut orchestrator = SourceOrchestrator::default();
+        orchestrator.stream_phases(&mut sources);
+        orchestrator.run(sources).unwrap();
+        orchestrator.init(0).unwrap();
+
+        orchestrator.measure().await.unwrap();
+        orchestrator.measure().await.unwrap();
+        orchestrator.new_phase().await.unwrap();
+
+        assert!(orchestrator.next_phase().await.is_some());
+        let (result, _) = orchestrator.finalize().await.unwrap();
+        assert_eq!(result.phases.len(), 1);
+        assert!(orchestrator.next_phase().await.is_none());
+    }
+
     #[tokio::test]
     async fn run_orchestrator_with_no_source_returns_error() {
         let mut orchestrator = SourceOrchestrator::default();
@@ -318,6 +405,7 @@ mod tests {
 
     #[tokio::test]
     async fn measure_error_in_worker_propagates_to_orchestrator() {
+        expect_mock_name();
         let mut reader = MockMetricReader::new();
         reader.expect_init().returning(|_| Ok(()));
         reader.expect_measure().returning(|| Err(MockError));
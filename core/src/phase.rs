@@ -1,6 +1,8 @@
+use std::borrow::Cow;
 use std::fmt::Display;
 
-use serde::Serialize;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Serialize};
 
 /// Represents a phase marker, indicating the beginning or the end of a phase.
 #[derive(Debug, Clone, PartialEq)]
@@ -24,6 +26,33 @@ impl Serialize for PhaseToken {
     }
 }
 
+impl<'de> Deserialize<'de> for PhaseToken {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let token = String::deserialize(deserializer)?;
+        Ok(match token.as_str() {
+            "START" => PhaseToken::Start,
+            "END" => PhaseToken::End,
+            _ => PhaseToken::Token(token),
+        })
+    }
+}
+
+impl JsonSchema for PhaseToken {
+    fn schema_name() -> Cow<'static, str> {
+        "PhaseToken".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "START, END or the token detected in the standard output."
+        })
+    }
+}
+
 impl Display for PhaseToken {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -44,7 +73,7 @@ impl From<PhaseToken> for Option<String> {
 }
 
 /// Detected phase with timestamp and optional line number.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhaseInfo {
     /// Phase token detected.
     pub token: PhaseToken,
@@ -46,6 +46,26 @@ pub enum JouleProfilerError {
     #[error("Invalid metric unit: {0}")]
     InvalidUnit(String),
 
+    /// The recording file cannot be read or written.
+    #[error("Invalid recording: {0}")]
+    InvalidRecording(String),
+
+    /// The saved results file cannot be parsed.
+    #[error("Invalid results file: {0}")]
+    InvalidResults(String),
+
+    /// The saved results were written with a newer, unsupported format version.
+    #[error("Unsupported results schema version: {0}")]
+    UnsupportedSchemaVersion(u32),
+
+    /// The carbon and cost estimation configuration is invalid.
+    #[error("Invalid footprint configuration: {0}")]
+    InvalidFootprintConfig(String),
+
+    /// A budget assertion or budgets file is invalid.
+    #[error("Invalid budget: {0}")]
+    InvalidBudget(String),
+
     /// Generic I/O error.
     #[error("I/O error")]
     IoError(
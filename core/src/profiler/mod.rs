@@ -4,28 +4,41 @@
 //! the execution of commands, collecting metrics from various sources (e.g. RAPL, `perf_event`, NVML, etc.),
 //! and aggregate them into a clean common structure.
 
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use regex::Regex;
 use std::io::BufWriter;
 use std::os::unix::process::CommandExt;
 use std::process::{Child, Command};
+use std::time::Duration;
 use std::{
     io::{BufRead, BufReader, ErrorKind, Write},
     process::{self, Stdio},
 };
 
 pub mod error;
+mod session;
 
+use crate::aggregate::derived_metrics;
+use crate::aggregate::phase::SensorPhase;
+use crate::aggregate::power::{PowerStats, power_series};
+use crate::aggregate::sensor_result::SensorResult;
 use crate::config::ProfileConfig;
 use crate::orchestrator::SourceOrchestrator;
+use crate::orchestrator::error::OrchestratorError;
 use crate::phase::{PhaseInfo, PhaseToken};
-use crate::profiler::types::{MeasurePhasesReturnType, Phase, ProfilerResults, Result};
+use crate::profiler::types::{
+    MeasurePhasesReturnType, Phase, PhaseListener, ProfilerResults, Result,
+};
+use crate::recording::replay::replay_source;
+use crate::recording::{RecordedEvent, Recording};
 use crate::sensor::{Sensor, Sensors};
-use crate::source::{MetricReader, MetricSource, MetricSourceError};
+use crate::source::runtime::MetricSourceRuntime;
+use crate::source::{MetricReader, MetricSource, MetricSourceError, SourceCapabilities};
 use crate::util::fs::create_file_with_user_permissions;
 use crate::util::sys::{get_uid_from_username, geteuid, signal};
 use crate::util::time::get_timestamp_micros;
 pub use error::JouleProfilerError;
+pub use session::ProfilingSession;
 
 pub mod types;
 
@@ -53,6 +66,7 @@ pub mod types;
 ///     token_pattern: "__PHASE__".to_string(),
 ///     stdout_file: None,
 ///     use_root: false,
+///     metrics_record_file: None,
 /// };
 ///
 /// let results = profiler.profile(&config).await.unwrap();
@@ -65,6 +79,12 @@ pub struct JouleProfiler {
 
     /// The different metric sources.
     sources: Vec<Box<dyn MetricSource>>,
+
+    /// Number of buckets of the power histograms of the phases, `None` to skip them.
+    power_histogram_buckets: Option<usize>,
+
+    /// Listener called with each phase as soon as it completes, `None` if phases are not streamed.
+    phase_listener: Option<PhaseListener>,
 }
 
 impl JouleProfiler {
@@ -84,6 +104,49 @@ impl JouleProfiler {
         self.sources.push(reader.into());
     }
 
+    /// Adds a custom metric source polled by the profiler every `interval`.
+    ///
+    /// The source is measured periodically during phases, and the polled results are accumulated
+    /// into the phase metrics. It protects counters from wrapping unnoticed during long phases.
+    pub fn add_source_with_polling<T>(&mut self, reader: T, interval: Duration)
+    where
+        T: MetricReader,
+    {
+        debug!(
+            "Registering additional metric source: {} (polling every {interval:?})",
+            T::get_name()
+        );
+        if reader.capabilities().is_sampling_too_fast(interval) {
+            warn!(
+                "Polling {} every {interval:?} is faster than its counters update",
+                T::get_name()
+            );
+        }
+        self.sources
+            .push(Box::new(MetricSourceRuntime::with_polling(
+                reader, interval,
+            )));
+    }
+
+    /// Sets the number of buckets of the power histograms computed for the polled energy sensors,
+    /// `None` to only compute their power statistics.
+    pub fn set_power_histogram_buckets(&mut self, buckets: Option<usize>) {
+        self.power_histogram_buckets = buckets;
+    }
+
+    /// Sets the listener called with each phase as soon as it completes during [`profile`](Self::profile)
+    /// and [sessions](ProfilingSession).
+    ///
+    /// The sources then convert their metrics at each phase boundary instead of at the end of the
+    /// run, which slightly delays the handling of the phase tokens. The phases given to the listener
+    /// are the same as the ones of the final results.
+    pub fn set_phase_listener<F>(&mut self, listener: F)
+    where
+        F: FnMut(&Phase) + Send + 'static,
+    {
+        self.phase_listener = Some(Box::new(listener));
+    }
+
     /// List the sensors of the provided sources.
     pub fn list_sensors(&mut self) -> Result<Sensors> {
         debug!("Listing sensors from {} source(s)", self.sources.len());
@@ -112,7 +175,19 @@ impl JouleProfiler {
         info!("Running phase-based profiling");
         debug!("Phase regex: {}", config.token_pattern);
 
-        let sources = std::mem::take(&mut self.sources);
+        let mut sources = std::mem::take(&mut self.sources);
+        if config.metrics_record_file.is_some() {
+            debug!("Recording sources metrics");
+            for source in &mut sources {
+                source.record_metrics();
+            }
+        }
+
+        if self.phase_listener.is_some() {
+            debug!("Streaming the phases");
+            self.orchestrator.stream_phases(&mut sources);
+        }
+
         trace!("Starting orchestrator with {} source(s)", sources.len());
         self.orchestrator.run(sources)?;
 
@@ -120,55 +195,102 @@ impl JouleProfiler {
         let (command_duration_ms, timestamp, exit_code, detected_phases) =
             self.measure_phases(config).await?;
 
-        let (sources_results, sources) = self.orchestrator.finalize().await?;
-        self.sources = sources;
+        let (sources_results, mut sources) = self.orchestrator.finalize().await?;
 
-        let mut phases: Vec<_> = detected_phases
-            .windows(2)
-            .enumerate()
-            .zip(&sources_results.phases)
-            .map(|((index, window), real_phase)| {
-                let (d1, d2) = (&window[0], &window[1]);
-                let mut phase_metrics = real_phase.metrics.clone();
-                phase_metrics.sort_by(|a, b| a.name.cmp(&b.name));
-                Phase {
-                    index,
-                    metrics: phase_metrics,
-                    start_token: d1.token.clone(),
-                    end_token: d2.token.clone(),
-                    timestamp: d1.timestamp,
-                    duration_ms: (d2.timestamp - d1.timestamp) / 1000,
-                    start_token_line: d1.line_number,
-                    end_token_line: d2.line_number,
-                }
-            })
-            .collect();
+        let phases = build_phases(
+            &detected_phases,
+            sources_results,
+            timestamp,
+            command_duration_ms,
+            self.power_histogram_buckets,
+        );
 
-        if phases.is_empty()
-            && let Some(end_phase) = sources_results.phases.into_iter().last()
-        {
-            let phase = Phase {
-                index: 0,
-                metrics: end_phase.metrics,
-                start_token: PhaseToken::Start,
-                end_token: PhaseToken::End,
-                timestamp,
-                duration_ms: command_duration_ms,
-                start_token_line: None,
-                end_token_line: None,
-            };
-            phases.push(phase);
-        }
+        warn_wrapped_counters(self.orchestrator.capabilities(), &phases);
 
         debug!("Collected {} sensor phase(s)", phases.len());
-        Ok(ProfilerResults {
+        let results = ProfilerResults {
             timestamp,
             duration_ms: command_duration_ms,
             exit_code,
             phases,
+        };
+
+        if let Some(record_file) = &config.metrics_record_file {
+            Recording::collect(
+                config,
+                &results,
+                detected_phases,
+                self.orchestrator.capabilities(),
+                &mut sources,
+            )
+            .write_to_file(record_file)?;
+        }
+
+        self.sources = sources;
+        Ok(results)
+    }
+
+    /// Replays a recorded profiling run and returns the regenerated results.
+    ///
+    /// The recorded metrics are fed back through the sources runtime, producing the same results
+    /// as the recorded run without requiring the recorded hardware. The profiler sources are not used.
+    ///
+    /// Each source is replayed on its own, going through its recorded polls and phases in order.
+    pub async fn replay(&mut self, recording: &Recording) -> Result<ProfilerResults> {
+        info!("Replaying recording of {:?}", recording.command);
+
+        let mut results = Vec::with_capacity(recording.sources.len());
+        for source in &recording.sources {
+            trace!(
+                "Replaying {} recorded metrics of {}",
+                source.metrics.len(),
+                source.name
+            );
+            self.orchestrator.run(vec![replay_source(source)])?;
+            self.orchestrator.init(0)?;
+
+            self.orchestrator.measure().await?;
+            for recorded in &source.metrics {
+                match recorded.event {
+                    RecordedEvent::Poll => self.orchestrator.poll().await?,
+                    RecordedEvent::NewPhase => {
+                        self.orchestrator.measure().await?;
+                        self.orchestrator.new_phase().await?;
+                    }
+                }
+            }
+
+            let (result, _) = self.orchestrator.finalize().await?;
+            results.push(result);
+        }
+
+        let sources_results =
+            SensorResult::merge(results).ok_or(OrchestratorError::NotEnoughSnapshots)?;
+        let phases = build_phases(
+            &recording.phases,
+            sources_results,
+            recording.timestamp,
+            recording.duration_ms,
+            self.power_histogram_buckets,
+        );
+
+        debug!("Replayed {} sensor phase(s)", phases.len());
+        Ok(ProfilerResults {
+            timestamp: recording.timestamp,
+            duration_ms: recording.duration_ms,
+            exit_code: recording.exit_code,
+            phases,
         })
     }
 
+    /// Starts an in-process profiling session, measuring the current process.
+    ///
+    /// The sources are started immediately and the first measure is made, the session phases
+    /// are then delimited with [`ProfilingSession::phase`] until [`ProfilingSession::finish`] is called.
+    pub async fn start_session(&mut self) -> Result<ProfilingSession<'_>> {
+        ProfilingSession::start(self).await
+    }
+
     /// Spawn the configured command and profile it, separating its execution into phases through tokens matching
     /// a configured regular expression.
     ///
@@ -235,6 +357,7 @@ impl JouleProfiler {
         let duration_ms = (end_timestamp - begin_timestamp) / 1000;
 
         detected_phases.push(PhaseInfo::end(end_timestamp));
+        self.notify_phase(&detected_phases).await;
 
         let exit_code = wait_for_child_exit(&mut child)?;
 
@@ -304,6 +427,7 @@ impl JouleProfiler {
                 };
 
                 phases.push(phase_info);
+                self.notify_phase(phases).await;
             }
 
             line_number += 1;
@@ -311,6 +435,139 @@ impl JouleProfiler {
 
         Ok(())
     }
+
+    /// Calls the phase listener with the phase completed by the last detected phase marker.
+    ///
+    /// Does nothing if the phases are not streamed.
+    async fn notify_phase(&mut self, detected_phases: &[PhaseInfo]) {
+        if self.phase_listener.is_none() {
+            return;
+        }
+        let Some(real_phase) = self.orchestrator.next_phase().await else {
+            return;
+        };
+        let [.., start, end] = detected_phases else {
+            return;
+        };
+        let phase = build_phase(
+            detected_phases.len() - 2,
+            start,
+            end,
+            &real_phase,
+            self.power_histogram_buckets,
+        );
+        if let Some(listener) = self.phase_listener.as_mut() {
+            listener(&phase);
+        }
+    }
+}
+
+/// Builds the profiler phases from the detected phase markers and the sources results.
+///
+/// Each pair of consecutive markers delimits a phase, associated with the source metrics measured in between.
+/// If no phase can be built, a single `START -> END` phase is created from the last sources phase.
+///
+/// The [derived metrics](`derived_metrics`) of each phase are added to its metrics, and the
+/// [power distribution](`PowerStats`) of its polled energy sensors is computed, with a histogram
+/// of `histogram_buckets` buckets if set.
+fn build_phases(
+    detected_phases: &[PhaseInfo],
+    sources_results: SensorResult,
+    timestamp: u128,
+    duration_ms: u128,
+    histogram_buckets: Option<usize>,
+) -> Vec<Phase> {
+    let mut phases: Vec<_> = detected_phases
+        .windows(2)
+        .enumerate()
+        .zip(&sources_results.phases)
+        .map(|((index, window), real_phase)| {
+            build_phase(index, &window[0], &window[1], real_phase, histogram_buckets)
+        })
+        .collect();
+
+    if phases.is_empty()
+        && let Some(end_phase) = sources_results.phases.into_iter().last()
+    {
+        let power = power_stats(&end_phase, histogram_buckets);
+        let mut metrics = end_phase.metrics;
+        let (derived, total_energy_rule) = derived_metrics(&metrics, duration_ms);
+        metrics.extend(derived);
+        let phase = Phase {
+            index: 0,
+            metrics,
+            start_token: PhaseToken::Start,
+            end_token: PhaseToken::End,
+            timestamp,
+            duration_ms,
+            start_token_line: None,
+            end_token_line: None,
+            total_energy_rule,
+            power,
+            samples: end_phase.samples,
+        };
+        phases.push(phase);
+    }
+
+    phases
+}
+
+/// Builds the phase delimited by two phase markers from the source metrics measured in between.
+fn build_phase(
+    index: usize,
+    start: &PhaseInfo,
+    end: &PhaseInfo,
+    real_phase: &SensorPhase,
+    histogram_buckets: Option<usize>,
+) -> Phase {
+    let duration_ms = (end.timestamp - start.timestamp) / 1000;
+    let (derived, total_energy_rule) = derived_metrics(&real_phase.metrics, duration_ms);
+    let mut metrics = real_phase.metrics.clone();
+    metrics.extend(derived);
+    metrics.sort_by(|a, b| a.name.cmp(&b.name));
+    Phase {
+        index,
+        metrics,
+        start_token: start.token.clone(),
+        end_token: end.token.clone(),
+        timestamp: start.timestamp,
+        duration_ms,
+        start_token_line: start.line_number,
+        end_token_line: end.line_number,
+        total_energy_rule,
+        power: power_stats(real_phase, histogram_buckets),
+        samples: real_phase.samples.clone(),
+    }
+}
+
+/// Computes the power distribution of the polled energy sensors of a phase.
+fn power_stats(phase: &SensorPhase, histogram_buckets: Option<usize>) -> Vec<PowerStats> {
+    power_series(
+        phase
+            .samples
+            .iter()
+            .map(|sample| (&sample.metrics, sample.duration_us)),
+    )
+    .iter()
+    .filter_map(|series| PowerStats::from_series(series, histogram_buckets))
+    .collect()
+}
+
+/// Warns about the sources whose counters may have wrapped around during a phase.
+///
+/// A wrap happening between two measures cannot be detected, thus the phase metrics are unreliable.
+fn warn_wrapped_counters(capabilities: &[(&'static str, SourceCapabilities)], phases: &[Phase]) {
+    for phase in phases {
+        let duration = Duration::from_millis(u64::try_from(phase.duration_ms).unwrap_or(u64::MAX));
+        for (name, source_capabilities) in capabilities {
+            if source_capabilities.may_wrap_within(duration) {
+                warn!(
+                    "Phase {} lasted longer than {name} counters wrap period, its metrics may be wrong, consider enabling polling",
+                    phase.get_name()
+                );
+            }
+        }
+    }
 }
 
 /// Checks whether a line matches the specified regular expression.
@@ -433,12 +690,15 @@ mod tests {
     use regex::Regex;
     use std::fs;
     use std::io::{BufReader, Cursor, Read};
+    use std::sync::Once;
     use tempfile::TempDir;
 
     fn joule_profiler() -> JouleProfiler {
         JouleProfiler {
             orchestrator: SourceOrchestrator::default(),
             sources: Vec::new(),
+            power_histogram_buckets: None,
+            phase_listener: None,
         }
     }
 
@@ -448,6 +708,7 @@ mod tests {
             token_pattern: "__PHASE__".to_string(),
             stdout_file: None,
             use_root: false,
+            metrics_record_file: None,
         }
     }
 
@@ -479,6 +740,16 @@ mod tests {
         }
     }
 
+    /// Registers the `get_name` expectation once for all tests, static expectations being global.
+    fn expect_mock_name() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            let context = MockMetricReader::get_name_context();
+            context.expect().return_const("mock");
+            std::mem::forget(context);
+        });
+    }
+
     #[tokio::test]
     async fn detect_multiple_phases() {
         let mut profiler = joule_profiler();
@@ -676,7 +947,9 @@ mod tests {
             token_pattern: "[[invalid[[[regex[[".to_string(),
             stdout_file: None,
             use_root: false,
+            metrics_record_file: None,
         };
+        expect_mock_name();
         profiler.add_source(MockMetricReader::new());
         let result = profiler.profile(&config).await;
         assert!(matches!(result, Err(JouleProfilerError::InvalidPattern(_))));
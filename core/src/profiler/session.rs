@@ -0,0 +1,143 @@
+//! In-process profiling sessions.
+//!
+//! A [`ProfilingSession`] measures the current process instead of a spawned command,
+//! allowing Rust applications using Joule Profiler as a library to profile their own code.
+//! Phases are delimited explicitly by calling [`ProfilingSession::phase`].
+
+use log::{debug, info, trace};
+
+use crate::JouleProfiler;
+use crate::phase::{PhaseInfo, PhaseToken};
+use crate::profiler::types::{ProfilerResults, Result};
+use crate::profiler::{build_phases, warn_wrapped_counters};
+use crate::util::time::get_timestamp_micros;
+
+/// A running profiling session of the current process.
+///
+/// Created with [`JouleProfiler::start_session`], the session keeps the metric sources
+/// running until [`ProfilingSession::finish`] is called. The sources supporting pid filtering
+/// (e.g. `perf_event`) are initialized with the current process id.
+///
+/// The sources are given back to the profiler when the session is finished, a dropped session
+/// loses its sources.
+///
+/// The [phase listener](JouleProfiler::set_phase_listener) is called as in
+/// [`profile`](JouleProfiler::profile), but sessions cannot be recorded: a [`Recording`] replays
+/// a spawned command, which a session does not have.
+///
+/// [`Recording`]: crate::recording::Recording
+///
+/// # Examples
+///
+/// ```no_run
+/// use joule_profiler_core::JouleProfiler;
+///
+/// # tokio_test::block_on(async {
+/// let mut profiler = JouleProfiler::new();
+///
+/// // Add sources using profiler.add_source(source)
+///
+/// let mut session = profiler.start_session().await.unwrap();
+/// // load data
+/// session.phase("compute").await.unwrap();
+/// // heavy computation
+/// let results = session.finish().await.unwrap();
+/// # });
+/// ```
+pub struct ProfilingSession<'a> {
+    /// The profiler owning the orchestrator and the sources.
+    profiler: &'a mut JouleProfiler,
+
+    /// Phase markers detected since the beginning of the session.
+    detected_phases: Vec<PhaseInfo>,
+
+    /// Timestamp of the first measure in microsecond.
+    begin_timestamp: u128,
+}
+
+impl<'a> ProfilingSession<'a> {
+    /// Starts the orchestrator with the profiler sources and makes the first measure.
+    pub(crate) async fn start(profiler: &'a mut JouleProfiler) -> Result<Self> {
+        info!("Starting in-process profiling session");
+
+        let mut sources = std::mem::take(&mut profiler.sources);
+        if profiler.phase_listener.is_some() {
+            debug!("Streaming the phases");
+            profiler.orchestrator.stream_phases(&mut sources);
+        }
+
+        trace!("Starting orchestrator with {} source(s)", sources.len());
+        profiler.orchestrator.run(sources)?;
+
+        let pid = std::process::id().cast_signed();
+        profiler.orchestrator.init(pid)?;
+
+        let begin_timestamp = get_timestamp_micros();
+        trace!("Begin timestamp: {begin_timestamp}");
+        profiler.orchestrator.measure().await?;
+
+        Ok(Self {
+            profiler,
+            detected_phases: vec![PhaseInfo::start(begin_timestamp)],
+            begin_timestamp,
+        })
+    }
+
+    /// Ends the current phase and begins a new one with the given name.
+    pub async fn phase<N>(&mut self, name: N) -> Result<()>
+    where
+        N: Into<String>,
+    {
+        let timestamp = get_timestamp_micros();
+        let name = name.into();
+        debug!("New session phase '{name}'");
+
+        self.profiler.orchestrator.measure().await?;
+        self.profiler.orchestrator.new_phase().await?;
+
+        self.detected_phases.push(PhaseInfo {
+            token: PhaseToken::Token(name),
+            timestamp,
+            line_number: None,
+        });
+        self.profiler.notify_phase(&self.detected_phases).await;
+
+        Ok(())
+    }
+
+    /// Makes the last measure, stops the sources and returns the aggregated results.
+    ///
+    /// The exit code of the results is always 0, as the profiled process is still running.
+    pub async fn finish(mut self) -> Result<ProfilerResults> {
+        let end_timestamp = get_timestamp_micros();
+        trace!("End timestamp: {end_timestamp}");
+
+        self.profiler.orchestrator.measure().await?;
+        self.profiler.orchestrator.new_phase().await?;
+        self.detected_phases.push(PhaseInfo::end(end_timestamp));
+        self.profiler.notify_phase(&self.detected_phases).await;
+
+        let (sources_results, sources) = self.profiler.orchestrator.finalize().await?;
+        self.profiler.sources = sources;
+
+        let duration_ms = (end_timestamp - self.begin_timestamp) / 1000;
+        let phases = build_phases(
+            &self.detected_phases,
+            sources_results,
+            self.begin_timestamp,
+            duration_ms,
+            self.profiler.power_histogram_buckets,
+        );
+
+        warn_wrapped_counters(self.profiler.orchestrator.capabilities(), &phases);
+
+        info!("Session finished: duration={duration_ms} ms");
+        debug!("Collected {} sensor phase(s)", phases.len());
+        Ok(ProfilerResults {
+            timestamp: self.begin_timestamp,
+            duration_ms,
+            exit_code: 0,
+            phases,
+        })
+    }
+}
@@ -1,15 +1,25 @@
+use std::fs::File;
+use std::io::BufReader;
+
 use crate::JouleProfilerError;
 use crate::aggregate::Metrics;
+use crate::aggregate::phase::PhaseSample;
+use crate::aggregate::power::PowerStats;
+use crate::budget::BudgetReport;
 use crate::phase::{PhaseInfo, PhaseToken};
-use serde::Serialize;
+use schemars::{JsonSchema, Schema, schema_for};
+use serde::{Deserialize, Serialize};
 
 /// Result type for profiler operations.
 pub type Result<T> = std::result::Result<T, JouleProfilerError>;
 
 pub type MeasurePhasesReturnType = (u128, u128, i32, Vec<PhaseInfo>);
 
+/// Listener called with each phase as soon as it completes.
+pub type PhaseListener = Box<dyn FnMut(&Phase) + Send>;
+
 /// Represents a profiling phase with metrics and timing.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Phase {
     /// The index of the phase.
     pub index: usize,
@@ -36,6 +46,19 @@ pub struct Phase {
 
     /// Metrics collected during the phase.
     pub metrics: Metrics,
+
+    /// Rule used to compute the `TOTAL` energy of the phase, which domains are summed and which are
+    /// excluded because already included in another one (e.g. `PSYS + DRAM-0 (excluding PACKAGE-0)`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_energy_rule: Option<String>,
+
+    /// Power distribution of the energy sensors, when their source is polled during the phase.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub power: Vec<PowerStats>,
+
+    /// Readings of the phase when its sources are polled, kept out of the saved results.
+    #[serde(skip)]
+    pub samples: Vec<PhaseSample>,
 }
 
 impl Phase {
@@ -47,7 +70,7 @@ impl Phase {
 pub type Phases = Vec<Phase>;
 
 /// Represents the results of a program's profiling.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ProfilerResults {
     /// Timestamp of the first measure in microsecond.
     pub timestamp: u128,
@@ -61,3 +84,218 @@ pub struct ProfilerResults {
     /// Phases detected in the program's standard output.
     pub phases: Phases,
 }
+
+/// Version of the saved results format, incremented whenever the format changes.
+pub const RESULTS_SCHEMA_VERSION: u32 = 1;
+
+/// Profiling results saved along with their context, as written by the JSON output.
+///
+/// The format is versioned with [`RESULTS_SCHEMA_VERSION`] and described by the JSON Schema
+/// returned by [`SavedResults::schema`].
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[schemars(description = "Profiling results saved by the Joule Profiler JSON output.")]
+pub struct SavedResults {
+    /// Version of the format, 0 for files written before the format was versioned.
+    #[serde(default)]
+    pub schema_version: u32,
+
+    /// The profiled command.
+    pub command: String,
+
+    /// Regex used to detect phase tokens.
+    pub token_pattern: String,
+
+    /// Timestamp of the first measure in microsecond.
+    #[serde(default)]
+    pub timestamp: u128,
+
+    /// Duration of the program in millisecond.
+    #[serde(default)]
+    pub duration_ms: u128,
+
+    /// Exit code of the profiled command.
+    pub exit_code: i32,
+
+    /// Phases detected in the program's standard output.
+    pub phases: Phases,
+
+    /// Outcome of the budgets checked against the results, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budgets: Option<BudgetReport>,
+}
+
+impl SavedResults {
+    /// Wraps profiling results with their context, using the current format version.
+    pub fn new(cmd: &[String], token_pattern: &str, results: &ProfilerResults) -> Self {
+        Self {
+            schema_version: RESULTS_SCHEMA_VERSION,
+            command: cmd.join(" "),
+            token_pattern: token_pattern.to_string(),
+            timestamp: results.timestamp,
+            duration_ms: results.duration_ms,
+            exit_code: results.exit_code,
+            phases: results.phases.clone(),
+            budgets: None,
+        }
+    }
+
+    /// Attaches the outcome of the budgets to the saved results.
+    #[must_use]
+    pub fn with_budgets(mut self, budgets: Option<&BudgetReport>) -> Self {
+        self.budgets = budgets.cloned();
+        self
+    }
+
+    /// Loads saved results from a JSON file.
+    ///
+    /// Files written with a newer format version are rejected.
+    pub fn from_file(path: &str) -> Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let saved: Self = serde_json::from_reader(reader)
+            .map_err(|err| JouleProfilerError::InvalidResults(format!("{path}: {err}")))?;
+
+        if saved.schema_version > RESULTS_SCHEMA_VERSION {
+            return Err(JouleProfilerError::UnsupportedSchemaVersion(
+                saved.schema_version,
+            ));
+        }
+        Ok(saved)
+    }
+
+    /// Generates the JSON Schema of the saved results.
+    pub fn schema() -> Schema {
+        schema_for!(SavedResults)
+    }
+}
+
+impl From<SavedResults> for ProfilerResults {
+    fn from(saved: SavedResults) -> Self {
+        Self {
+            timestamp: saved.timestamp,
+            duration_ms: saved.duration_ms,
+            exit_code: saved.exit_code,
+            phases: saved.phases,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MetricScope;
+    use crate::types::{Metric, MetricValue};
+    use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+    fn results() -> ProfilerResults {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+
+        ProfilerResults {
+            timestamp: 1000,
+            duration_ms: 12,
+            exit_code: 0,
+            phases: vec![Phase {
+                index: 0,
+                start_token: PhaseToken::Start,
+                end_token: PhaseToken::Token("__PHASE__".into()),
+                timestamp: 1000,
+                duration_ms: 12,
+                start_token_line: None,
+                end_token_line: Some(3),
+                metrics: vec![Metric::new("PACKAGE-0", 42u64, unit, "rapl")],
+                total_energy_rule: None,
+                power: Vec::new(),
+                samples: Vec::new(),
+            }],
+        }
+    }
+
+    fn write(content: &str) -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.json");
+        std::fs::write(&path, content).unwrap();
+        let path = path.to_str().unwrap().to_string();
+        (dir, path)
+    }
+
+    #[test]
+    fn saved_results_round_trip() {
+        let cmd = vec!["sleep".to_string(), "1".to_string()];
+        let saved = SavedResults::new(&cmd, "__PHASE__", &results());
+        let (_dir, path) = write(&serde_json::to_string(&saved).unwrap());
+
+        let loaded = SavedResults::from_file(&path).unwrap();
+        assert_eq!(loaded.schema_version, RESULTS_SCHEMA_VERSION);
+        assert_eq!(loaded.command, "sleep 1");
+
+        let results = ProfilerResults::from(loaded);
+        let phase = &results.phases[0];
+        assert_eq!(phase.start_token, PhaseToken::Start);
+        assert_eq!(phase.end_token, PhaseToken::Token("__PHASE__".into()));
+        assert_eq!(phase.start_token_line, None);
+        assert_eq!(phase.end_token_line, Some(3));
+        assert_eq!(phase.metrics[0].unit.to_string(), "µJ");
+    }
+
+    #[test]
+    fn unversioned_results_are_loaded() {
+        // Written by the JSON output before the format was versioned.
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/results_v0.json"
+        );
+
+        let loaded = SavedResults::from_file(path).unwrap();
+        assert_eq!(loaded.schema_version, 0);
+        assert_eq!(loaded.exit_code, 0);
+        assert_eq!(loaded.timestamp, 0);
+        assert!(loaded.budgets.is_none());
+
+        let names: Vec<String> = loaded.phases.iter().map(Phase::get_name).collect();
+        assert_eq!(names, ["START -> __A__", "__A__ -> __B__", "__B__ -> END"]);
+        let metric = &loaded.phases[1].metrics[0];
+        assert_eq!(metric.name, "PACKAGE-0");
+        assert_eq!(metric.source, "RAPL (Powercap)");
+        assert_eq!(metric.unit.to_string(), "µJ");
+        assert_eq!(metric.value, MetricValue::UnsignedInteger(1_250_000));
+        assert_eq!(metric.scope, MetricScope::Machine);
+        assert!(loaded.phases.iter().all(|phase| phase.power.is_empty()));
+    }
+
+    #[test]
+    fn newer_schema_version_is_rejected() {
+        let (_dir, path) = write(
+            r#"{"schema_version": 1000, "command": "ls", "token_pattern": "__P__", "exit_code": 0, "phases": []}"#,
+        );
+
+        assert!(matches!(
+            SavedResults::from_file(&path),
+            Err(JouleProfilerError::UnsupportedSchemaVersion(1000))
+        ));
+    }
+
+    #[test]
+    fn invalid_unit_is_rejected() {
+        let mut saved = serde_json::to_value(SavedResults::new(&[], "", &results())).unwrap();
+        saved["phases"][0]["metrics"][0]["unit"] = "Hz".into();
+        let (_dir, path) = write(&saved.to_string());
+
+        assert!(matches!(
+            SavedResults::from_file(&path),
+            Err(JouleProfilerError::InvalidResults(_))
+        ));
+    }
+
+    #[test]
+    fn committed_schema_is_up_to_date() {
+        let committed: serde_json::Value =
+            serde_json::from_str(include_str!("../../schema/results.schema.json")).unwrap();
+        assert_eq!(
+            committed,
+            SavedResults::schema().to_value(),
+            "run `joule-profiler schema > core/schema/results.schema.json` to update it"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Recording and replay of the metrics converted by the sources.
+//!
+//! When a [`ProfileConfig`] has a `metrics_record_file`, the metrics converted from every snapshot
+//! retrieved from the metric sources are saved with their event and measured interval, alongside
+//! the detected phases. The resulting [`Recording`] can be replayed with
+//! [`JouleProfiler::replay`](`crate::JouleProfiler::replay`) to regenerate the exact same results
+//! on a machine without the recorded hardware.
+//!
+//! Only the converted metrics are recorded, not the raw snapshots of the readers: converting a
+//! snapshot takes the reader itself, which cannot be created without the recorded hardware. A
+//! replay therefore gives back the conversions of the recorded run, and does not benefit from a
+//! change of a reader conversion.
+//!
+//! The metrics of each phase and poll are stored on their own, before being merged into their
+//! phase, so that the replay goes through the same polls and regenerates the phases samples and
+//! power statistics.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::aggregate::Metrics;
+use crate::config::ProfileConfig;
+use crate::phase::PhaseInfo;
+use crate::profiler::JouleProfilerError;
+use crate::profiler::types::{ProfilerResults, Result};
+use crate::source::{MetricScope, MetricSource, SourceCapabilities};
+use crate::util::fs::create_file_with_user_permissions;
+
+pub(crate) mod replay;
+
+/// Event of the source runtime which retrieved the recorded metrics.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// The metrics end a phase.
+    NewPhase,
+
+    /// The metrics have been polled during a phase.
+    Poll,
+}
+
+/// Metrics converted from a snapshot retrieved from a metric source.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordedMetrics {
+    /// Event which retrieved the snapshot.
+    pub event: RecordedEvent,
+
+    /// Timestamp of the measure starting the snapshot interval in microseconds.
+    pub timestamp: u128,
+
+    /// Duration of the snapshot interval in microseconds, up to the measure ending it.
+    pub duration_us: u128,
+
+    /// Metrics converted from the raw snapshot by the reader.
+    pub metrics: Metrics,
+}
+
+/// Metrics recorded from a single metric source.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordedSource {
+    /// The name of the source.
+    pub name: String,
+
+    /// Scope of the source metrics.
+    pub scope: MetricScope,
+
+    /// Metrics of each snapshot, in retrieval order.
+    pub metrics: Vec<RecordedMetrics>,
+}
+
+/// A profiling run recorded with the metrics of all its sources.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Recording {
+    /// The profiled command.
+    pub command: Vec<String>,
+
+    /// Regex used to detect phase tokens.
+    pub token_pattern: String,
+
+    /// Timestamp of the first measure in microseconds.
+    pub timestamp: u128,
+
+    /// Duration of the profiled command in milliseconds.
+    pub duration_ms: u128,
+
+    /// Exit code of the profiled command.
+    pub exit_code: i32,
+
+    /// Phase markers detected during the run.
+    pub(crate) phases: Vec<PhaseInfo>,
+
+    /// Recorded sources, in registration order.
+    pub sources: Vec<RecordedSource>,
+}
+
+impl Recording {
+    /// Collects the metrics recorded by the sources of a profiling run.
+    ///
+    /// The `capabilities` are the ones of the orchestrator, given in the same order as `sources`.
+    pub(crate) fn collect(
+        config: &ProfileConfig,
+        results: &ProfilerResults,
+        phases: Vec<PhaseInfo>,
+        capabilities: &[(&'static str, SourceCapabilities)],
+        sources: &mut [Box<dyn MetricSource>],
+    ) -> Self {
+        let sources = capabilities
+            .iter()
+            .zip(sources.iter_mut())
+            .map(|((name, capabilities), source)| RecordedSource {
+                name: (*name).to_string(),
+                scope: capabilities.scope,
+                metrics: source.take_recorded_metrics().unwrap_or_default(),
+            })
+            .collect();
+
+        Self {
+            command: config.cmd.clone(),
+            token_pattern: config.token_pattern.clone(),
+            timestamp: results.timestamp,
+            duration_ms: results.duration_ms,
+            exit_code: results.exit_code,
+            phases,
+            sources,
+        }
+    }
+
+    /// Loads a recording from a JSON file.
+    pub fn from_file(path: &str) -> Result<Self> {
+        debug!("Loading recording from {path}");
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader)
+            .map_err(|err| JouleProfilerError::InvalidRecording(format!("{path}: {err}")))
+    }
+
+    /// Writes the recording to a JSON file.
+    pub fn write_to_file(&self, path: &str) -> Result<()> {
+        let mut writer = BufWriter::new(create_file_with_user_permissions(path)?);
+        serde_json::to_writer(&mut writer, self)
+            .map_err(|err| JouleProfilerError::InvalidRecording(format!("{path}: {err}")))?;
+        writer.flush()?;
+
+        info!("Recording written to {path}");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phase::PhaseToken;
+    use crate::types::Metric;
+    use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+    fn recording() -> Recording {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+
+        Recording {
+            command: vec!["sleep".into(), "1".into()],
+            token_pattern: "__[A-Z0-9_]+__".into(),
+            timestamp: 1000,
+            duration_ms: 1,
+            exit_code: 0,
+            phases: vec![PhaseInfo::start(1000), PhaseInfo::end(2000)],
+            sources: vec![RecordedSource {
+                name: "rapl".into(),
+                scope: MetricScope::Machine,
+                metrics: vec![RecordedMetrics {
+                    event: RecordedEvent::NewPhase,
+                    timestamp: 1000,
+                    duration_us: 1000,
+                    metrics: vec![Metric::new("PACKAGE-0", 42u64, unit, "rapl")],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn recording_round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.json");
+        let path = path.to_str().unwrap();
+
+        recording().write_to_file(path).unwrap();
+        let loaded = Recording::from_file(path).unwrap();
+
+        assert_eq!(loaded.command, vec!["sleep", "1"]);
+        assert_eq!(loaded.phases[0].token, PhaseToken::Start);
+        assert_eq!(loaded.phases[1].token, PhaseToken::End);
+
+        let recorded = &loaded.sources[0].metrics[0];
+        assert_eq!(recorded.event, RecordedEvent::NewPhase);
+        assert_eq!(recorded.duration_us, 1000);
+        assert_eq!(recorded.metrics[0].name, "PACKAGE-0");
+        assert_eq!(recorded.metrics[0].unit.to_string(), "µJ");
+    }
+
+    #[test]
+    fn invalid_recording_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.json");
+        std::fs::write(&path, "{}").unwrap();
+
+        let err = Recording::from_file(path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(err, JouleProfilerError::InvalidRecording(_)));
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+
+use log::trace;
+use thiserror::Error;
+
+use crate::aggregate::Metrics;
+use crate::recording::RecordedSource;
+use crate::sensor::{Sensor, Sensors};
+use crate::source::runtime::MetricSourceRuntime;
+use crate::source::{MetricReader, MetricScope, MetricSource, SourceCapabilities};
+
+/// Errors of the replay reader.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    /// More phases were requested than recorded.
+    #[error("No metrics left in the recording of {0}")]
+    RecordingExhausted(String),
+}
+
+/// Metric reader feeding back the metrics recorded from a source.
+///
+/// Each retrieval returns the next recorded metrics, converting them as is, the runtime being driven through the same
+/// polls and phases as during the recorded run.
+#[derive(Debug)]
+pub struct ReplayReader {
+    /// Name of the recorded source.
+    name: String,
+
+    /// Scope of the recorded source.
+    scope: MetricScope,
+
+    /// Metrics not replayed yet.
+    metrics: VecDeque<Metrics>,
+}
+
+impl ReplayReader {
+    pub fn new(source: &RecordedSource) -> Self {
+        Self {
+            name: source.name.clone(),
+            scope: source.scope,
+            metrics: source
+                .metrics
+                .iter()
+                .map(|recorded| recorded.metrics.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Creates the runtime of a replayed source, its measures being timestamped as recorded.
+///
+/// The first measure starts the interval of the first recorded metrics, each following one ends
+/// the interval of the next ones.
+pub(crate) fn replay_source(source: &RecordedSource) -> Box<dyn MetricSource> {
+    let first_measure = source.metrics.first().map(|recorded| recorded.timestamp);
+    let timestamps = first_measure.into_iter().chain(
+        source
+            .metrics
+            .iter()
+            .map(|recorded| recorded.timestamp + recorded.duration_us),
+    );
+    Box::new(MetricSourceRuntime::with_replayed_measures(
+        ReplayReader::new(source),
+        timestamps,
+    ))
+}
+
+impl MetricReader for ReplayReader {
+    type Type = Metrics;
+    type Error = ReplayError;
+
+    async fn measure(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn retrieve(&mut self) -> Result<Self::Type, Self::Error> {
+        trace!(
+            "Replaying {} metrics ({} left)",
+            self.name,
+            self.metrics.len()
+        );
+        self.metrics
+            .pop_front()
+            .ok_or_else(|| ReplayError::RecordingExhausted(self.name.clone()))
+    }
+
+    fn get_sensors(&self) -> Result<Sensors, Self::Error> {
+        Ok(self
+            .metrics
+            .front()
+            .map(|metrics| {
+                metrics
+                    .iter()
+                    .map(|metric| Sensor::new(&metric.name, metric.unit, &metric.source))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn to_metrics(&self, result: Self::Type) -> Result<Metrics, Self::Error> {
+        Ok(result)
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            scope: self.scope,
+            ..Default::default()
+        }
+    }
+
+    fn get_name() -> &'static str {
+        "replay"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::{RecordedEvent, RecordedMetrics};
+    use crate::types::{Metric, MetricValue};
+    use crate::unit::{MetricUnit, Unit, UnitPrefix};
+
+    fn recorded(event: RecordedEvent, value: u64) -> RecordedMetrics {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        RecordedMetrics {
+            event,
+            timestamp: 0,
+            duration_us: 0,
+            metrics: vec![Metric::new("PACKAGE-0", value, unit, "rapl")],
+        }
+    }
+
+    fn reader(metrics: Vec<RecordedMetrics>) -> ReplayReader {
+        ReplayReader::new(&RecordedSource {
+            name: "rapl".into(),
+            scope: MetricScope::Machine,
+            metrics,
+        })
+    }
+
+    #[tokio::test]
+    async fn retrieve_returns_recorded_metrics_one_by_one() {
+        let mut reader = reader(vec![
+            recorded(RecordedEvent::Poll, 10),
+            recorded(RecordedEvent::NewPhase, 5),
+        ]);
+
+        let polled = reader.retrieve().await.unwrap();
+        assert_eq!(polled[0].value, MetricValue::UnsignedInteger(10));
+
+        let phase = reader.retrieve().await.unwrap();
+        assert_eq!(phase[0].value, MetricValue::UnsignedInteger(5));
+    }
+
+    #[tokio::test]
+    async fn retrieve_fails_when_recording_is_exhausted() {
+        let mut reader = reader(vec![recorded(RecordedEvent::Poll, 10)]);
+        reader.retrieve().await.unwrap();
+        assert!(matches!(
+            reader.retrieve().await,
+            Err(ReplayError::RecordingExhausted(_))
+        ));
+    }
+}
@@ -4,7 +4,8 @@
 //! by `JouleProfiler`. Sensors are associated with metric sources and are
 //! used to represent individual measurements.
 
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use crate::unit::MetricUnit;
 
@@ -31,7 +32,7 @@ use crate::unit::MetricUnit;
 /// assert_eq!(sensor.unit.to_string(), "µJ");
 /// assert_eq!(sensor.source, "powercap");
 /// ```
-#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
 pub struct Sensor {
     /// The name of the sensor.
     pub name: String,
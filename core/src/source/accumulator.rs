@@ -7,6 +7,9 @@ use log::{debug, trace};
 pub struct MetricAccumulator<R: MetricReader> {
     /// Already completed phases.
     phases: Vec<RawPhase<R::Type>>,
+
+    /// Results polled during the current phase.
+    polled: Vec<R::Type>,
 }
 
 impl<R: MetricReader> MetricAccumulator<R> {
@@ -20,8 +23,19 @@ impl<R: MetricReader> MetricAccumulator<R> {
     pub fn new_phase(&mut self, snapshot: R::Type) {
         debug!("Starting new phase (current phases: {})", self.phases.len());
 
-        trace!("Phase counters retrieved");
-        self.phases.push(RawPhase { metrics: snapshot });
+        trace!(
+            "Phase counters retrieved ({} polled result(s))",
+            self.polled.len()
+        );
+        self.phases.push(RawPhase {
+            metrics: snapshot,
+            polled: std::mem::take(&mut self.polled),
+        });
+    }
+
+    /// Store a result polled during the current phase.
+    pub fn poll(&mut self, snapshot: R::Type) {
+        self.polled.push(snapshot);
     }
 
     /// Retrieve all sensors measures.
@@ -35,6 +49,7 @@ impl<R: MetricReader> Default for MetricAccumulator<R> {
     fn default() -> Self {
         Self {
             phases: Vec::default(),
+            polled: Vec::default(),
         }
     }
 }
@@ -0,0 +1,419 @@
+//! Adapter for metric readers with synchronous measurements.
+//!
+//! Reading sysfs files, calling FFI libraries or issuing syscalls blocks the calling thread.
+//! Done inside the source worker future, a slow device would block a tokio worker thread.
+//! A [`BlockingMetricReader`] wrapped into a [`BlockingAdapter`] runs these calls on a thread
+//! dedicated to the reader instead, and fails with a [`MetricSourceError::Timeout`] when a call
+//! exceeds its deadline.
+
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use log::{trace, warn};
+use tokio::{sync::oneshot, time::timeout};
+
+use crate::aggregate::Metrics;
+use crate::sensor::Sensors;
+use crate::source::error::IntoMetricSourceError;
+use crate::source::{
+    MetricReader, MetricReaderErrorBound, MetricReaderTypeBound, MetricSourceError,
+    SourceCapabilities,
+};
+
+/// Default deadline of the blocking operations.
+pub const DEFAULT_BLOCKING_DEADLINE: Duration = Duration::from_secs(1);
+
+/// Synchronous counterpart of [`MetricReader`].
+///
+/// The semantics of each method is the same as its [`MetricReader`] equivalent, the reader
+/// being used through a [`BlockingAdapter`]. The sensors, capabilities and converter are taken
+/// once when the reader is wrapped, so that they never wait for a blocking operation: they must
+/// only depend on the state set at the reader creation.
+pub trait BlockingMetricReader: Send + 'static {
+    /// Type of metrics returned by the reader.
+    type Type: MetricReaderTypeBound + 'static;
+
+    /// Error type produced by the reader.
+    type Error: MetricReaderErrorBound + 'static;
+
+    /// State converting the reader data to metrics, kept outside of the reader thread.
+    type Converter: Send + Sync + 'static;
+
+    /// Init the source if it implements custom logic underneath.
+    fn init(&mut self, _pid: i32) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Join the source if it implements custom logic underneath.
+    fn join(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Measure the sensors metrics and update internal state.
+    fn measure(&mut self) -> Result<(), Self::Error>;
+
+    /// Retrieve the current metrics as the reader type.
+    fn retrieve(&mut self) -> Result<Self::Type, Self::Error>;
+
+    /// Return all sensors available from this reader.
+    fn get_sensors(&self) -> Result<Sensors, Self::Error>;
+
+    /// Return the state needed to convert the reader data to metrics.
+    fn converter(&self) -> Self::Converter;
+
+    /// Convert the metric reader data to metrics.
+    fn to_metrics(converter: &Self::Converter, result: Self::Type) -> Result<Metrics, Self::Error>;
+
+    /// Describe the capabilities of the source.
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities::default()
+    }
+
+    /// Get the name of the metric source.
+    fn get_name() -> &'static str;
+}
+
+/// Operation run on the reader thread.
+type Operation<R> = Box<dyn FnOnce(&mut R) + Send>;
+
+/// Runs a [`BlockingMetricReader`] as a [`MetricReader`].
+///
+/// The reader is moved to a dedicated thread, on which `init`, `join`, `measure` and `retrieve`
+/// are executed in order, each of them failing with a [`MetricSourceError::Timeout`] if it lasts
+/// more than the deadline. A timed out operation keeps the thread busy until it completes, the
+/// following operations waiting for it within their own deadline. The sensors, capabilities and
+/// converter of the reader are taken when it is wrapped, so that they are available at any time.
+///
+/// The reader thread is detached: dropping the adapter stops it once its current operation
+/// completes, and a reader stuck in an operation never delays the shutdown of the tokio runtime.
+///
+/// Moving the calls to another thread delays the measures by a few microseconds, readers with
+/// fast and reliable measurements should rather implement [`MetricReader`] directly.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use joule_profiler_core::JouleProfiler;
+/// use joule_profiler_core::source::BlockingAdapter;
+/// # use joule_profiler_core::{sensor::Sensors, source::BlockingMetricReader, types::Metrics};
+/// # struct SysfsReader;
+/// # impl BlockingMetricReader for SysfsReader {
+/// #     type Type = ();
+/// #     type Error = std::io::Error;
+/// #     type Converter = ();
+/// #     fn measure(&mut self) -> Result<(), Self::Error> { Ok(()) }
+/// #     fn retrieve(&mut self) -> Result<(), Self::Error> { Ok(()) }
+/// #     fn get_sensors(&self) -> Result<Sensors, Self::Error> { Ok(vec![]) }
+/// #     fn converter(&self) {}
+/// #     fn to_metrics(_: &(), _: ()) -> Result<Metrics, Self::Error> { Ok(vec![]) }
+/// #     fn get_name() -> &'static str { "sysfs" }
+/// # }
+///
+/// let mut profiler = JouleProfiler::new();
+/// profiler.add_source(BlockingAdapter::with_deadline(SysfsReader, Duration::from_millis(100)));
+/// ```
+pub struct BlockingAdapter<R: BlockingMetricReader> {
+    /// Sender of the operations to the reader thread, stopping it once dropped.
+    operations: mpsc::Sender<Operation<R>>,
+
+    /// Converter of the reader data, taken when the reader was wrapped.
+    converter: R::Converter,
+
+    /// Sensors of the reader, or the message of the error listing them.
+    sensors: Result<Sensors, String>,
+
+    /// Capabilities of the reader.
+    capabilities: SourceCapabilities,
+
+    /// Maximum duration of a blocking operation.
+    deadline: Duration,
+}
+
+impl<R: BlockingMetricReader> BlockingAdapter<R> {
+    /// Wraps the reader with the [default deadline](`DEFAULT_BLOCKING_DEADLINE`).
+    pub fn new(reader: R) -> Self {
+        Self::with_deadline(reader, DEFAULT_BLOCKING_DEADLINE)
+    }
+
+    /// Wraps the reader, its blocking operations failing after `deadline`.
+    ///
+    /// The sensors of the reader are listed right away, before moving it to its thread.
+    pub fn with_deadline(mut reader: R, deadline: Duration) -> Self {
+        let converter = reader.converter();
+        let sensors = reader.get_sensors().map_err(|err| err.to_string());
+        let capabilities = reader.capabilities();
+
+        let (operations, receiver) = mpsc::channel::<Operation<R>>();
+        thread::spawn(move || {
+            for operation in receiver {
+                // A panic is reported by the dropped reply of the operation, the thread going on
+                // with the next operations.
+                let _ = catch_unwind(AssertUnwindSafe(|| operation(&mut reader)));
+            }
+            trace!("Reader thread of source {} stopped", R::get_name());
+        });
+
+        Self {
+            operations,
+            converter,
+            sensors,
+            capabilities,
+            deadline,
+        }
+    }
+
+    /// Runs a reader operation on the reader thread, within the deadline.
+    async fn run_blocking<T, F>(
+        &self,
+        operation: &'static str,
+        f: F,
+    ) -> Result<T, MetricSourceError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut R) -> Result<T, R::Error> + Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        let panicked = || MetricSourceError::OperationPanicked {
+            source_name: R::get_name(),
+            operation,
+        };
+        self.operations
+            .send(Box::new(move |reader: &mut R| {
+                let _ = reply.send(f(reader));
+            }))
+            .map_err(|_| panicked())?;
+
+        let Ok(replied) = timeout(self.deadline, result).await else {
+            warn!(
+                "{operation} of source {} exceeded its {:?} deadline",
+                R::get_name(),
+                self.deadline
+            );
+            return Err(MetricSourceError::Timeout {
+                source_name: R::get_name(),
+                operation,
+                deadline: self.deadline,
+            });
+        };
+
+        replied
+            .map_err(|_| panicked())?
+            .map_err(IntoMetricSourceError::into_metric_source_error)
+    }
+}
+
+impl<R: BlockingMetricReader> MetricReader for BlockingAdapter<R> {
+    type Type = R::Type;
+    type Error = MetricSourceError;
+
+    async fn init(&mut self, pid: i32) -> Result<(), Self::Error> {
+        self.run_blocking("init", move |reader| reader.init(pid))
+            .await
+    }
+
+    async fn join(&mut self) -> Result<(), Self::Error> {
+        self.run_blocking("join", R::join).await
+    }
+
+    async fn measure(&mut self) -> Result<(), Self::Error> {
+        self.run_blocking("measure", R::measure).await
+    }
+
+    async fn retrieve(&mut self) -> Result<Self::Type, Self::Error> {
+        self.run_blocking("retrieve", R::retrieve).await
+    }
+
+    fn get_sensors(&self) -> Result<Sensors, Self::Error> {
+        self.sensors
+            .clone()
+            .map_err(|message| MetricSourceError::SourceError(message.into()))
+    }
+
+    fn to_metrics(&self, result: Self::Type) -> Result<Metrics, Self::Error> {
+        R::to_metrics(&self.converter, result)
+            .map_err(IntoMetricSourceError::into_metric_source_error)
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        self.capabilities
+    }
+
+    fn get_name() -> &'static str {
+        R::get_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct MockError;
+
+    impl std::fmt::Display for MockError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "mock error")
+        }
+    }
+    impl std::error::Error for MockError {}
+
+    /// Reader whose measures last `measure_duration`, failing if `fail` is set and panicking
+    /// if `panic` is set.
+    struct SlowReader {
+        measure_duration: Duration,
+        fail: bool,
+        panic: bool,
+        measures: u64,
+    }
+
+    impl SlowReader {
+        fn new(measure_duration: Duration) -> Self {
+            Self {
+                measure_duration,
+                fail: false,
+                panic: false,
+                measures: 0,
+            }
+        }
+    }
+
+    impl BlockingMetricReader for SlowReader {
+        type Type = u64;
+        type Error = MockError;
+        type Converter = &'static str;
+
+        fn measure(&mut self) -> Result<(), MockError> {
+            std::thread::sleep(self.measure_duration);
+            assert!(!self.panic, "measure panicked");
+            if self.fail {
+                return Err(MockError);
+            }
+            self.measures += 1;
+            Ok(())
+        }
+
+        fn retrieve(&mut self) -> Result<u64, MockError> {
+            Ok(self.measures)
+        }
+
+        fn get_sensors(&self) -> Result<Sensors, MockError> {
+            Ok(vec![])
+        }
+
+        fn converter(&self) -> &'static str {
+            "MEASURES"
+        }
+
+        fn to_metrics(name: &&'static str, measures: u64) -> Result<Metrics, MockError> {
+            let unit = crate::unit::MetricUnit {
+                prefix: crate::unit::UnitPrefix::None,
+                unit: crate::unit::Unit::Count,
+            };
+            Ok(vec![crate::types::Metric::new(
+                *name, measures, unit, "slow",
+            )])
+        }
+
+        fn get_name() -> &'static str {
+            "slow"
+        }
+    }
+
+    #[tokio::test]
+    async fn operations_within_deadline_succeed() {
+        let mut adapter = BlockingAdapter::new(SlowReader::new(Duration::ZERO));
+
+        adapter.measure().await.unwrap();
+        adapter.measure().await.unwrap();
+        assert_eq!(adapter.retrieve().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn operation_exceeding_deadline_times_out() {
+        let mut adapter = BlockingAdapter::with_deadline(
+            SlowReader::new(Duration::from_millis(200)),
+            Duration::from_millis(10),
+        );
+
+        let err = adapter.measure().await.unwrap_err();
+        assert!(matches!(
+            err,
+            MetricSourceError::Timeout {
+                source_name: "slow",
+                operation: "measure",
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn reader_errors_are_propagated() {
+        let mut reader = SlowReader::new(Duration::ZERO);
+        reader.fail = true;
+        let mut adapter = BlockingAdapter::new(reader);
+
+        let err = adapter.measure().await.unwrap_err();
+        assert!(matches!(err, MetricSourceError::SourceError(_)));
+    }
+
+    #[tokio::test]
+    async fn conversion_does_not_wait_for_a_stuck_operation() {
+        let mut adapter = BlockingAdapter::with_deadline(
+            SlowReader::new(Duration::from_secs(5)),
+            Duration::from_millis(10),
+        );
+        assert!(adapter.measure().await.is_err());
+
+        let start = std::time::Instant::now();
+        assert!(adapter.get_sensors().unwrap().is_empty());
+        assert_eq!(adapter.to_metrics(3).unwrap()[0].name, "MEASURES");
+        assert!(!adapter.capabilities().supports_polling);
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        assert!(matches!(
+            adapter.retrieve().await,
+            Err(MetricSourceError::Timeout {
+                operation: "retrieve",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn runtime_shutdown_does_not_wait_for_a_stuck_operation() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let mut adapter = BlockingAdapter::with_deadline(
+            SlowReader::new(Duration::from_secs(5)),
+            Duration::from_millis(10),
+        );
+        assert!(runtime.block_on(adapter.measure()).is_err());
+
+        let start = std::time::Instant::now();
+        drop(adapter);
+        drop(runtime);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn reader_thread_survives_a_panic() {
+        let mut reader = SlowReader::new(Duration::ZERO);
+        reader.panic = true;
+        let mut adapter = BlockingAdapter::new(reader);
+
+        let err = adapter.measure().await.unwrap_err();
+        assert!(matches!(
+            err,
+            MetricSourceError::OperationPanicked {
+                source_name: "slow",
+                operation: "measure",
+            }
+        ));
+        assert_eq!(adapter.retrieve().await.unwrap(), 0);
+    }
+}
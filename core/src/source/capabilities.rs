@@ -0,0 +1,115 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Scope of the metrics measured by a source.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricScope {
+    /// Metrics are filtered on the profiled process (e.g. `perf_event`).
+    Process,
+
+    /// Metrics are measured for the whole machine (e.g. RAPL, NVML).
+    #[default]
+    Machine,
+}
+
+impl Display for MetricScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MetricScope::Process => "process",
+            MetricScope::Machine => "machine",
+        })
+    }
+}
+
+/// Describes what a [`MetricReader`](`super::MetricReader`) is able to do.
+///
+/// The defaults describe a machine-wide source without polling support nor known
+/// counter limitations, which is always a safe assumption.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use joule_profiler_core::source::{MetricScope, SourceCapabilities};
+///
+/// let capabilities = SourceCapabilities {
+///     scope: MetricScope::Process,
+///     min_sampling_interval: Some(Duration::from_millis(1)),
+///     ..Default::default()
+/// };
+/// assert!(!capabilities.supports_polling);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceCapabilities {
+    /// Whether the metrics are filtered on the profiled process or measured for the whole machine.
+    pub scope: MetricScope,
+
+    /// Whether the source can be measured periodically during a phase.
+    pub supports_polling: bool,
+
+    /// Duration after which the source counters can wrap around if they are not measured.
+    ///
+    /// `None` if the counters never wrap in practice or if the source already handles wraps itself.
+    pub counter_wrap_period: Option<Duration>,
+
+    /// Minimum interval between two measures for them to be meaningful (e.g. the counters update rate).
+    pub min_sampling_interval: Option<Duration>,
+}
+
+impl SourceCapabilities {
+    /// Checks whether a measure lasting `duration` may have missed a counter wrap.
+    pub fn may_wrap_within(&self, duration: Duration) -> bool {
+        self.counter_wrap_period
+            .is_some_and(|wrap_period| duration >= wrap_period)
+    }
+
+    /// Checks whether sampling the source every `interval` is faster than its counters update.
+    pub fn is_sampling_too_fast(&self, interval: Duration) -> bool {
+        self.min_sampling_interval
+            .is_some_and(|min_interval| interval < min_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_capabilities_are_machine_wide_without_limits() {
+        let capabilities = SourceCapabilities::default();
+        assert_eq!(capabilities.scope, MetricScope::Machine);
+        assert!(!capabilities.supports_polling);
+        assert!(!capabilities.may_wrap_within(Duration::MAX));
+        assert!(!capabilities.is_sampling_too_fast(Duration::ZERO));
+    }
+
+    #[test]
+    fn may_wrap_within_compares_with_wrap_period() {
+        let capabilities = SourceCapabilities {
+            counter_wrap_period: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        assert!(!capabilities.may_wrap_within(Duration::from_secs(29)));
+        assert!(capabilities.may_wrap_within(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn is_sampling_too_fast_compares_with_min_interval() {
+        let capabilities = SourceCapabilities {
+            min_sampling_interval: Some(Duration::from_millis(1)),
+            ..Default::default()
+        };
+        assert!(capabilities.is_sampling_too_fast(Duration::from_micros(500)));
+        assert!(!capabilities.is_sampling_too_fast(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn metric_scope_display() {
+        assert_eq!(MetricScope::Process.to_string(), "process");
+        assert_eq!(MetricScope::Machine.to_string(), "machine");
+    }
+}
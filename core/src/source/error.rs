@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Errors that can occur when reading or aggregating metrics from a source.
@@ -15,6 +16,29 @@ pub enum MetricSourceError {
     #[error("Source initialization timeout.")]
     InitTimeout,
 
+    /// A blocking operation of the source did not complete before its deadline.
+    #[error("{operation} of source {source_name} timed out after {deadline:?}")]
+    Timeout {
+        /// The name of the source.
+        source_name: &'static str,
+
+        /// The timed out operation (e.g. measure).
+        operation: &'static str,
+
+        /// The exceeded deadline.
+        deadline: Duration,
+    },
+
+    /// A blocking operation of the source panicked.
+    #[error("{operation} of source {source_name} panicked")]
+    OperationPanicked {
+        /// The name of the source.
+        source_name: &'static str,
+
+        /// The panicked operation (e.g. measure).
+        operation: &'static str,
+    },
+
     /// Error propagated from a custom metric source.
     #[error(transparent)]
     SourceError(#[from] Box<dyn std::error::Error + Send + Sync>),
@@ -23,7 +47,7 @@ pub enum MetricSourceError {
 /// Converts any compatible error into a [`MetricSourceError`].
 ///
 /// Implemented for all types that are [`std::error::Error`] + [`Send`] + [`Sync`] + `'static`,
-/// wrapping them in [`MetricSourceError::SourceError`].
+/// wrapping them in [`MetricSourceError::SourceError`]. A [`MetricSourceError`] is returned as is.
 pub trait IntoMetricSourceError {
     fn into_metric_source_error(self) -> MetricSourceError;
 }
@@ -33,6 +57,27 @@ where
     T: std::error::Error + Send + Sync + 'static,
 {
     fn into_metric_source_error(self) -> MetricSourceError {
-        MetricSourceError::SourceError(Box::new(self))
+        let err: Box<dyn std::error::Error + Send + Sync> = Box::new(self);
+        match err.downcast::<MetricSourceError>() {
+            Ok(err) => *err,
+            Err(err) => MetricSourceError::SourceError(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_source_error_is_not_wrapped() {
+        let err = MetricSourceError::InitTimeout.into_metric_source_error();
+        assert!(matches!(err, MetricSourceError::InitTimeout));
+    }
+
+    #[test]
+    fn custom_error_is_wrapped() {
+        let err = std::io::Error::other("custom").into_metric_source_error();
+        assert!(matches!(err, MetricSourceError::SourceError(_)));
     }
 }
@@ -8,14 +8,20 @@
 use tokio::sync::{mpsc, oneshot};
 
 pub(crate) mod accumulator;
+pub mod blocking;
+mod capabilities;
 pub mod error;
 pub mod reader;
 pub(crate) mod runtime;
 pub(crate) mod types;
 
+use crate::aggregate::phase::SensorPhase;
+use crate::recording::RecordedMetrics;
 use crate::sensor::Sensors;
 use crate::source::runtime::MetricSourceRuntime;
 use crate::source::types::{SourceEvent, SourceWorkerHandle};
+pub use blocking::{BlockingAdapter, BlockingMetricReader};
+pub use capabilities::{MetricScope, SourceCapabilities};
 pub use error::MetricSourceError;
 pub use reader::MetricReader;
 pub use types::{MetricReaderErrorBound, MetricReaderTypeBound};
@@ -37,6 +43,21 @@ pub(crate) trait MetricSource: Send {
 
     /// List sensors exposed by this source.
     fn list_sensors(&self) -> Result<Sensors, MetricSourceError>;
+
+    /// Get the name of the source.
+    fn name(&self) -> &'static str;
+
+    /// Get the capabilities of the source.
+    fn capabilities(&self) -> SourceCapabilities;
+
+    /// Record the metrics converted during the next run.
+    fn record_metrics(&mut self);
+
+    /// Take the metrics recorded during the last run, `None` if they were not recorded.
+    fn take_recorded_metrics(&mut self) -> Option<Vec<RecordedMetrics>>;
+
+    /// Stream the phases of the next run as they complete, returning the receiver of the phases.
+    fn stream_phases(&mut self) -> mpsc::UnboundedReceiver<SensorPhase>;
 }
 
 impl<R> MetricSource for MetricSourceRuntime<R>
@@ -65,6 +86,31 @@ where
     fn list_sensors(&self) -> Result<Sensors, MetricSourceError> {
         self.get_source_sensors()
     }
+
+    /// Get the name of the underlying reader.
+    fn name(&self) -> &'static str {
+        R::get_name()
+    }
+
+    /// Get the capabilities of the underlying reader.
+    fn capabilities(&self) -> SourceCapabilities {
+        self.get_source_capabilities()
+    }
+
+    /// Enable the metrics recording of the runtime.
+    fn record_metrics(&mut self) {
+        self.enable_recording();
+    }
+
+    /// Take the metrics recorded by the runtime.
+    fn take_recorded_metrics(&mut self) -> Option<Vec<RecordedMetrics>> {
+        self.take_recording()
+    }
+
+    /// Enable the phases streaming of the runtime.
+    fn stream_phases(&mut self) -> mpsc::UnboundedReceiver<SensorPhase> {
+        self.enable_streaming()
+    }
 }
 
 /// Converts a [`MetricReader`] into a boxed [`MetricSource`].
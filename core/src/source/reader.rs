@@ -1,6 +1,6 @@
 use crate::aggregate::Metrics;
 use crate::sensor::Sensors;
-use crate::source::{MetricReaderErrorBound, MetricReaderTypeBound};
+use crate::source::{MetricReaderErrorBound, MetricReaderTypeBound, SourceCapabilities};
 
 /// Trait implemented by a metric source reader.
 ///
@@ -27,6 +27,7 @@ use crate::source::{MetricReaderErrorBound, MetricReaderTypeBound};
 ///
 /// - [`MetricReader::init`] — Source initialization logic if there is one, called before the measurements.
 /// - [`MetricReader::join`] — Source destruction logic if there is one, called before the measurements (no Drop implementation because the source is reusable).
+/// - [`MetricReader::capabilities`] — Describe the source scope, polling support and counters limitations.
 pub trait MetricReader: Send + 'static {
     /// Type of metrics returned by the reader.
     type Type: MetricReaderTypeBound;
@@ -56,6 +57,13 @@ pub trait MetricReader: Send + 'static {
     /// Convert the metric reader data to metrics.
     fn to_metrics(&self, result: Self::Type) -> Result<Metrics, Self::Error>;
 
+    /// Describe the capabilities of the source.
+    ///
+    /// Defaults to a machine-wide source without polling support nor known counter limitations.
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities::default()
+    }
+
     /// Get the name of the metric source.
     fn get_name() -> &'static str;
 }
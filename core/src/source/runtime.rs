@@ -1,24 +1,50 @@
+use std::collections::VecDeque;
 use std::time::Duration;
 
-use log::debug;
+use log::{debug, trace};
 use tokio::{
     sync::{mpsc, oneshot},
-    time::timeout,
+    time::{Instant, Interval, MissedTickBehavior, interval_at, timeout},
 };
 
 use crate::{
-    aggregate::{phase::SensorPhase, sensor_result::SensorResult},
+    aggregate::{
+        Metrics, merge_metrics,
+        phase::{PhaseSample, SensorPhase},
+        sensor_result::SensorResult,
+    },
+    recording::{RecordedEvent, RecordedMetrics},
     sensor::Sensors,
     source::{
-        MetricReader, MetricSource, MetricSourceError, accumulator::MetricAccumulator,
-        error::IntoMetricSourceError, types::SourceEvent,
+        MetricReader, MetricSource, MetricSourceError, SourceCapabilities,
+        accumulator::MetricAccumulator, error::IntoMetricSourceError, types::SourceEvent,
     },
+    util::time::get_timestamp_micros,
 };
 
 /// Orchestrate a metric source and handle the conversion between raw source results to metrics.
 pub struct MetricSourceRuntime<R: MetricReader> {
     accumulator: MetricAccumulator<R>,
     source: R,
+
+    /// Interval of the optional polling, measuring the source periodically during phases.
+    poll_interval: Option<Duration>,
+
+    /// Metrics recorded during the last run, `None` if the recording is disabled.
+    recording: Option<Vec<RecordedMetrics>>,
+
+    /// Timestamps of the measures since the last results retrieval, only tracked when polling
+    /// to compute the power samples, or when recording to timestamp the recorded metrics.
+    measure_timestamps: Vec<u128>,
+
+    /// Timestamps of the recorded measures, used instead of the clock when replaying a recording.
+    replayed_measures: Option<VecDeque<u128>>,
+
+    /// Sender of the phases converted as soon as they complete, `None` if they are not streamed.
+    phase_sender: Option<mpsc::UnboundedSender<SensorPhase>>,
+
+    /// Phases already streamed during the current run, returned with the results.
+    streamed: Vec<SensorPhase>,
 }
 
 impl<R: MetricReader> MetricSourceRuntime<R> {
@@ -29,12 +55,43 @@ impl<R: MetricReader> MetricSourceRuntime<R> {
         Self {
             accumulator: MetricAccumulator::new(),
             source: reader,
+            poll_interval: None,
+            recording: None,
+            measure_timestamps: Vec::new(),
+            replayed_measures: None,
+            phase_sender: None,
+            streamed: Vec::new(),
+        }
+    }
+
+    /// Initialize a [`MetricSourceRuntime`] polling the reader every `interval`.
+    ///
+    /// Polling measures and retrieves the source periodically, the polled results being accumulated
+    /// into the current phase. It prevents counters from wrapping unnoticed during long phases.
+    pub fn with_polling(reader: R, interval: Duration) -> Self {
+        Self {
+            poll_interval: Some(interval),
+            ..Self::new(reader)
+        }
+    }
+
+    /// Initialize a [`MetricSourceRuntime`] timestamping its measures with the recorded `timestamps`.
+    ///
+    /// The polls are then triggered by [`SourceEvent::Poll`] events instead of a ticker, so that
+    /// the samples of a recorded run are regenerated with their original intervals.
+    pub fn with_replayed_measures(reader: R, timestamps: impl IntoIterator<Item = u128>) -> Self {
+        Self {
+            replayed_measures: Some(timestamps.into_iter().collect()),
+            ..Self::new(reader)
         }
     }
 
     /// Runs the worker responsible for source and accumulator management.
     ///
     /// It listens for events through a channel and execute them.
+    ///
+    /// If polling is enabled, it starts after the first measure. A poll is skipped when a measure
+    /// is waiting for its phase to be retrieved, to keep the phases boundaries untouched.
     pub async fn run_worker(
         mut self,
         mut receiver: mpsc::Receiver<SourceEvent>,
@@ -47,11 +104,39 @@ impl<R: MetricReader> MetricSourceRuntime<R> {
 
         self.init_source(pid).await?;
 
+        let mut ticker: Option<Interval> = None;
+        let mut phase_boundary_pending = false;
+
         loop {
-            if let Some(event) = receiver.recv().await {
+            let event = if let Some(ticker) = ticker.as_mut() {
+                tokio::select! {
+                    event = receiver.recv() => event,
+                    _ = ticker.tick() => {
+                        if !phase_boundary_pending {
+                            self.poll_source().await?;
+                        }
+                        continue;
+                    }
+                }
+            } else {
+                receiver.recv().await
+            };
+
+            if let Some(event) = event {
                 match event {
-                    SourceEvent::Measure => self.measure_source().await?,
-                    SourceEvent::NewPhase => self.init_new_phase().await?,
+                    SourceEvent::Measure => {
+                        self.measure_source().await?;
+                        if ticker.is_none() {
+                            ticker = self.poll_interval.map(polling_ticker);
+                        } else {
+                            phase_boundary_pending = true;
+                        }
+                    }
+                    SourceEvent::NewPhase => {
+                        self.init_new_phase().await?;
+                        phase_boundary_pending = false;
+                    }
+                    SourceEvent::Poll => self.poll_source().await?,
                     SourceEvent::JoinWorker => break,
                 }
             }
@@ -62,8 +147,36 @@ impl<R: MetricReader> MetricSourceRuntime<R> {
             .await
             .map_err(IntoMetricSourceError::into_metric_source_error)?;
 
-        let result = self.retrieve()?;
-        Ok((result, self.source.into()))
+        let mut result = self.retrieve()?;
+        if !self.streamed.is_empty() {
+            self.streamed.append(&mut result.phases);
+            result.phases = self.streamed;
+        }
+        let source = Self {
+            accumulator: MetricAccumulator::new(),
+            source: self.source,
+            poll_interval: self.poll_interval,
+            recording: self.recording,
+            measure_timestamps: Vec::new(),
+            replayed_measures: None,
+            phase_sender: None,
+            streamed: Vec::new(),
+        };
+        Ok((result, Box::new(source)))
+    }
+
+    /// Measure the source and store the results retrieved since the last retrieval.
+    #[inline]
+    async fn poll_source(&mut self) -> Result<(), MetricSourceError> {
+        trace!("Polling source");
+        self.measure_source().await?;
+        let result = self
+            .source
+            .retrieve()
+            .await
+            .map_err(IntoMetricSourceError::into_metric_source_error)?;
+        self.accumulator.poll(result);
+        Ok(())
     }
 
     /// Make a measurement.
@@ -72,7 +185,14 @@ impl<R: MetricReader> MetricSourceRuntime<R> {
         self.source
             .measure()
             .await
-            .map_err(IntoMetricSourceError::into_metric_source_error)
+            .map_err(IntoMetricSourceError::into_metric_source_error)?;
+        if let Some(replayed) = self.replayed_measures.as_mut() {
+            self.measure_timestamps
+                .push(replayed.pop_front().unwrap_or_default());
+        } else if self.poll_interval.is_some() || self.recording.is_some() {
+            self.measure_timestamps.push(get_timestamp_micros());
+        }
+        Ok(())
     }
 
     /// Init the source with the profiled program pid.
@@ -85,6 +205,9 @@ impl<R: MetricReader> MetricSourceRuntime<R> {
     }
 
     /// Initialize a new phase.
+    ///
+    /// If the phases are streamed, the completed phase is converted and sent right away, the
+    /// measure closing it being kept as the start of the next phase first sample.
     #[inline]
     async fn init_new_phase(&mut self) -> Result<(), MetricSourceError> {
         let result = self
@@ -93,26 +216,127 @@ impl<R: MetricReader> MetricSourceRuntime<R> {
             .await
             .map_err(IntoMetricSourceError::into_metric_source_error)?;
         self.accumulator.new_phase(result);
+
+        if let Some(sender) = self.phase_sender.clone() {
+            let boundary = self.measure_timestamps.last().copied();
+            let result = self.retrieve()?;
+            self.measure_timestamps.extend(boundary);
+            for phase in result.phases {
+                let _ = sender.send(phase.clone());
+                self.streamed.push(phase);
+            }
+        }
         Ok(())
     }
 
+    /// Enable the recording of the metrics converted during the next run.
+    pub fn enable_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    /// Take the metrics recorded during the last run, disabling the recording.
+    pub fn take_recording(&mut self) -> Option<Vec<RecordedMetrics>> {
+        self.recording.take()
+    }
+
+    /// Stream the phases of the next run as they complete, returning the receiver of the phases.
+    pub fn enable_streaming(&mut self) -> mpsc::UnboundedReceiver<SensorPhase> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.phase_sender = Some(sender);
+        receiver
+    }
+
     /// Retrieve the results from the accumulator and convert them into metrics.
+    ///
+    /// If the polling is enabled, each snapshot of a phase gives a sample over the interval
+    /// between its measure and the previous one. If the recording is enabled, the metrics converted
+    /// from every snapshot are recorded with this interval before being merged into its phase.
     #[inline]
     fn retrieve(&mut self) -> Result<SensorResult, MetricSourceError> {
-        let result = self
-            .accumulator
-            .retrieve()
-            .into_iter()
-            .map(|phase| {
-                Ok(SensorPhase {
-                    metrics: self
-                        .source
-                        .to_metrics(phase.metrics)
-                        .map_err(IntoMetricSourceError::into_metric_source_error)?,
-                })
-            })
-            .collect::<Result<Vec<_>, MetricSourceError>>()?;
-        Ok(SensorResult { phases: result })
+        let scope = self.source.capabilities().scope;
+        let measure_timestamps = std::mem::take(&mut self.measure_timestamps);
+        let mut intervals = measure_timestamps
+            .windows(2)
+            .map(|window| (window[0], window[1].saturating_sub(window[0])));
+        let raw_phases = self.accumulator.retrieve();
+        let mut phases = Vec::with_capacity(raw_phases.len());
+
+        for phase in raw_phases {
+            let mut polled = phase
+                .polled
+                .into_iter()
+                .map(|polled| self.to_metrics(polled))
+                .collect::<Result<Vec<_>, MetricSourceError>>()?;
+            let mut metrics = self.to_metrics(phase.metrics)?;
+
+            for metric in polled.iter_mut().flatten().chain(&mut metrics) {
+                metric.scope = scope;
+            }
+
+            let phase_intervals: Vec<(u128, u128)> =
+                intervals.by_ref().take(polled.len() + 1).collect();
+
+            if let Some(recording) = self.recording.as_mut() {
+                let events = std::iter::repeat_n(RecordedEvent::Poll, polled.len())
+                    .chain(std::iter::once(RecordedEvent::NewPhase));
+                let converted = polled.iter().chain(std::iter::once(&metrics));
+                for (index, (event, metrics)) in events.zip(converted).enumerate() {
+                    let (timestamp, duration_us) =
+                        phase_intervals.get(index).copied().unwrap_or_default();
+                    recording.push(RecordedMetrics {
+                        event,
+                        timestamp,
+                        duration_us,
+                        metrics: metrics.clone(),
+                    });
+                }
+            }
+            let samples = if polled.is_empty() {
+                Vec::new()
+            } else {
+                polled
+                    .iter()
+                    .chain(std::iter::once(&metrics))
+                    .zip(phase_intervals)
+                    .map(|(metrics, (timestamp, duration_us))| PhaseSample {
+                        timestamp,
+                        duration_us,
+                        metrics: metrics.clone(),
+                    })
+                    .collect()
+            };
+
+            for polled in polled {
+                merge_metrics(&mut metrics, polled);
+            }
+            phases.push(SensorPhase { metrics, samples });
+        }
+
+        Ok(SensorResult { phases })
+    }
+
+    /// Convert a reader result into metrics.
+    #[inline]
+    fn to_metrics(&self, result: R::Type) -> Result<Metrics, MetricSourceError> {
+        self.source
+            .to_metrics(result)
+            .map_err(IntoMetricSourceError::into_metric_source_error)
+    }
+
+    /// Retrieve source capabilities.
+    ///
+    /// The counters wrap period is dropped if the polling is faster, as wraps are then handled by the runtime.
+    #[inline]
+    pub fn get_source_capabilities(&self) -> SourceCapabilities {
+        let mut capabilities = self.source.capabilities();
+        if let Some(interval) = self.poll_interval
+            && capabilities
+                .counter_wrap_period
+                .is_some_and(|wrap_period| interval < wrap_period)
+        {
+            capabilities.counter_wrap_period = None;
+        }
+        capabilities
     }
 
     /// Retrieve source sensors.
@@ -124,12 +348,20 @@ impl<R: MetricReader> MetricSourceRuntime<R> {
     }
 }
 
+/// Creates the polling ticker, its first tick happening one interval after its creation.
+fn polling_ticker(interval: Duration) -> Interval {
+    let mut ticker = interval_at(Instant::now() + interval, interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::aggregate::Metrics;
     use crate::sensor::Sensors;
-    use crate::source::MetricReader;
+    use crate::source::{MetricReader, MetricScope};
+    use crate::types::Metric;
+    use crate::unit::{MetricUnit, Unit, UnitPrefix};
     use mockall::mock;
     use std::sync::{Arc, Mutex};
     use tokio::sync::mpsc;
@@ -155,6 +387,7 @@ mod tests {
             async fn retrieve(&mut self) -> Result<(), MockError>;
             fn get_sensors(&self) -> Result<Sensors, MockError>;
             fn to_metrics(&self, v: ()) -> Result<Metrics, MockError>;
+            fn capabilities(&self) -> SourceCapabilities;
             fn get_name() -> &'static str;
         }
     }
@@ -202,7 +435,9 @@ mod tests {
         });
 
         m.expect_get_sensors().returning(|| Ok(vec![]));
-        m.expect_to_metrics().returning(|_| Ok(Metrics::default()));
+        m.expect_to_metrics().returning(|()| Ok(Metrics::default()));
+        m.expect_capabilities()
+            .returning(SourceCapabilities::default);
 
         (m, counts)
     }
@@ -294,4 +529,158 @@ mod tests {
         assert_eq!(c.retrieve, 1);
         assert_eq!(c.join, 1);
     }
+
+    #[tokio::test]
+    async fn run_worker_labels_metrics_with_source_scope() {
+        let mut reader = MockMetricReader::new();
+        reader.expect_init().returning(|_| Ok(()));
+        reader.expect_join().returning(|| Ok(()));
+        reader.expect_retrieve().returning(|| Ok(()));
+        reader.expect_to_metrics().returning(|()| {
+            let unit = MetricUnit {
+                prefix: UnitPrefix::None,
+                unit: Unit::Count,
+            };
+            Ok(vec![Metric::new("CPU_CYCLES", 42u64, unit, "mock")])
+        });
+        reader
+            .expect_capabilities()
+            .returning(|| SourceCapabilities {
+                scope: MetricScope::Process,
+                ..Default::default()
+            });
+        let rt = MetricSourceRuntime::new(reader);
+        let (tx, rx) = mpsc::channel(16);
+
+        tx.send(SourceEvent::NewPhase).await.unwrap();
+        tx.send(SourceEvent::JoinWorker).await.unwrap();
+
+        let (result, _) = rt.run_worker(rx, pid(0)).await.unwrap();
+        assert_eq!(result.phases[0].metrics[0].scope, MetricScope::Process);
+    }
+
+    #[tokio::test]
+    async fn run_worker_polling_measures_periodically() {
+        let (reader, counts) = mock_reader_counted();
+        let rt = MetricSourceRuntime::with_polling(reader, Duration::from_millis(1));
+        let (tx, rx) = mpsc::channel(16);
+        let worker = tokio::spawn(rt.run_worker(rx, pid(0)));
+
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tx.send(SourceEvent::NewPhase).await.unwrap();
+        tx.send(SourceEvent::JoinWorker).await.unwrap();
+
+        let (result, _) = worker.await.unwrap().unwrap();
+
+        let c = counts.lock().unwrap();
+        assert!(c.measure > 2);
+        assert_eq!(c.retrieve, c.measure - 1);
+        assert_eq!(result.phases.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn run_worker_polling_produces_samples() {
+        let mut reader = MockMetricReader::new();
+        reader.expect_init().returning(|_| Ok(()));
+        reader.expect_join().returning(|| Ok(()));
+        reader.expect_measure().returning(|| Ok(()));
+        let polls = Arc::new(Mutex::new(0));
+        let counted = polls.clone();
+        reader.expect_retrieve().returning(move || {
+            *counted.lock().unwrap() += 1;
+            Ok(())
+        });
+        reader.expect_to_metrics().returning(|()| {
+            let unit = MetricUnit {
+                prefix: UnitPrefix::Milli,
+                unit: Unit::Joule,
+            };
+            Ok(vec![Metric::new("PACKAGE-0", 10u64, unit, "mock")])
+        });
+        reader
+            .expect_capabilities()
+            .returning(SourceCapabilities::default);
+        let rt = MetricSourceRuntime::with_polling(reader, Duration::from_millis(2));
+        let (tx, rx) = mpsc::channel(16);
+        let worker = tokio::spawn(rt.run_worker(rx, pid(0)));
+
+        tx.send(SourceEvent::Measure).await.unwrap();
+        while *polls.lock().unwrap() < 2 {
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tx.send(SourceEvent::NewPhase).await.unwrap();
+        tx.send(SourceEvent::JoinWorker).await.unwrap();
+
+        let (result, _) = worker.await.unwrap().unwrap();
+        let samples = &result.phases[0].samples;
+        assert!(samples.len() > 1);
+        assert!(
+            samples
+                .windows(2)
+                .all(|w| w[1].timestamp == w[0].timestamp + w[0].duration_us)
+        );
+        assert!(samples.iter().all(|sample| sample.metrics.len() == 1));
+    }
+
+    #[tokio::test]
+    async fn run_worker_replays_polls_with_recorded_timestamps() {
+        let (reader, counts) = mock_reader_counted();
+        let rt = MetricSourceRuntime::with_replayed_measures(reader, [100, 150, 300]);
+        let (tx, rx) = mpsc::channel(16);
+
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tx.send(SourceEvent::Poll).await.unwrap();
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tx.send(SourceEvent::NewPhase).await.unwrap();
+        tx.send(SourceEvent::JoinWorker).await.unwrap();
+
+        let (result, _) = rt.run_worker(rx, pid(0)).await.unwrap();
+        assert_eq!(counts.lock().unwrap().retrieve, 2);
+        let samples: Vec<(u128, u128)> = result.phases[0]
+            .samples
+            .iter()
+            .map(|sample| (sample.timestamp, sample.duration_us))
+            .collect();
+        assert_eq!(samples, [(100, 50), (150, 150)]);
+    }
+
+    #[tokio::test]
+    async fn run_worker_streams_completed_phases() {
+        let (reader, _) = mock_reader_counted();
+        let mut rt = MetricSourceRuntime::new(reader);
+        let mut phases = rt.enable_streaming();
+        let (tx, rx) = mpsc::channel(16);
+        let worker = tokio::spawn(rt.run_worker(rx, pid(0)));
+
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tx.send(SourceEvent::NewPhase).await.unwrap();
+        assert!(phases.recv().await.is_some());
+
+        tx.send(SourceEvent::Measure).await.unwrap();
+        tx.send(SourceEvent::NewPhase).await.unwrap();
+        assert!(phases.recv().await.is_some());
+
+        tx.send(SourceEvent::JoinWorker).await.unwrap();
+        let (result, _) = worker.await.unwrap().unwrap();
+        assert_eq!(result.phases.len(), 2);
+        assert!(phases.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn polling_faster_than_wrap_period_drops_it_from_capabilities() {
+        let mut reader = MockMetricReader::new();
+        reader
+            .expect_capabilities()
+            .returning(|| SourceCapabilities {
+                counter_wrap_period: Some(Duration::from_secs(10)),
+                ..Default::default()
+            });
+
+        let rt = MetricSourceRuntime::with_polling(reader, Duration::from_secs(1));
+        assert_eq!(rt.get_source_capabilities().counter_wrap_period, None);
+    }
 }
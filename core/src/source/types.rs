@@ -37,6 +37,9 @@ pub enum SourceEvent {
     /// Starts a new measurement phase.
     NewPhase,
 
+    /// Measures and retrieves the source within the current phase, as its polling ticker does.
+    Poll,
+
     /// Signals the worker to finish and join.
     JoinWorker,
 }
@@ -46,4 +49,7 @@ pub enum SourceEvent {
 pub(crate) struct RawPhase<V> {
     /// Metrics collected during the phase.
     pub metrics: V,
+
+    /// Metrics collected by the polling task during the phase, preceding `metrics`.
+    pub polled: Vec<V>,
 }
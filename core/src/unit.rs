@@ -3,13 +3,15 @@
 //! This module defines basic units, SI prefixes, and their composition
 //! into metric units used throughout the profiler.
 
-use serde::{Serialize, Serializer};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::fmt::Display;
 
 use crate::JouleProfilerError;
 
 /// SI prefixes used to scale metric units.
-#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
 pub enum UnitPrefix {
     /// Nano prefix (10^-9).
     Nano,
@@ -47,8 +49,23 @@ impl Display for UnitPrefix {
     }
 }
 
+impl UnitPrefix {
+    /// Multiplier converting a prefixed value into the base unit.
+    pub fn factor(self) -> f64 {
+        match self {
+            UnitPrefix::Nano => 1e-9,
+            UnitPrefix::Micro => 1e-6,
+            UnitPrefix::Milli => 1e-3,
+            UnitPrefix::None => 1.0,
+            UnitPrefix::Kilo => 1e3,
+            UnitPrefix::Mega => 1e6,
+            UnitPrefix::Giga => 1e9,
+        }
+    }
+}
+
 /// Base measurement units.
-#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
 pub enum Unit {
     /// Energy unit.
     Joule,
@@ -67,6 +84,24 @@ pub enum Unit {
 
     /// Percentage.
     Percent,
+
+    /// Energy-delay product.
+    JouleSecond,
+
+    /// Energy-delay-squared product.
+    JouleSecondSquared,
+
+    /// Energy per counted event (e.g. per instruction).
+    JoulePerCount,
+
+    /// Counted events per energy (e.g. instructions per joule).
+    CountPerJoule,
+
+    /// Mass of CO2 equivalent emissions.
+    GramCo2e,
+
+    /// Amount of money, in the currency of the configured electricity price.
+    Currency,
 }
 
 impl Display for Unit {
@@ -78,6 +113,12 @@ impl Display for Unit {
             Unit::Count => "count",
             Unit::Byte => "B",
             Unit::Percent => "%",
+            Unit::JouleSecond => "J·s",
+            Unit::JouleSecondSquared => "J·s²",
+            Unit::JoulePerCount => "J/count",
+            Unit::CountPerJoule => "count/J",
+            Unit::GramCo2e => "gCO2e",
+            Unit::Currency => "¤",
         })
     }
 }
@@ -101,6 +142,50 @@ impl Serialize for MetricUnit {
     }
 }
 
+impl<'de> Deserialize<'de> for MetricUnit {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let unit = String::deserialize(deserializer)?;
+        MetricUnit::try_from(unit.as_str()).map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for MetricUnit {
+    fn schema_name() -> Cow<'static, str> {
+        "MetricUnit".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "SI prefix followed by the base unit (e.g. µJ, mW, count).",
+            "examples": ["µJ", "mW", "count"]
+        })
+    }
+}
+
+impl MetricUnit {
+    /// Converts a value expressed in this unit into the `target` unit.
+    ///
+    /// Returns `None` if the base units differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use joule_profiler_core::unit::MetricUnit;
+    ///
+    /// let micro_joules = MetricUnit::try_from("µJ").unwrap();
+    /// let joules = MetricUnit::try_from("J").unwrap();
+    /// assert_eq!(micro_joules.convert(2_000_000.0, joules), Some(2.0));
+    /// assert_eq!(micro_joules.convert(1.0, MetricUnit::try_from("W").unwrap()), None);
+    /// ```
+    pub fn convert(self, value: f64, target: MetricUnit) -> Option<f64> {
+        (self.unit == target.unit).then(|| value * self.prefix.factor() / target.prefix.factor())
+    }
+}
+
 impl Display for MetricUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", self.prefix, self.unit)
@@ -141,10 +226,16 @@ impl TryFrom<&str> for MetricUnit {
             "count" => Unit::Count,
             "B" => Unit::Byte,
             "%" => Unit::Percent,
+            "J·s" => Unit::JouleSecond,
+            "J·s²" => Unit::JouleSecondSquared,
+            "J/count" => Unit::JoulePerCount,
+            "count/J" => Unit::CountPerJoule,
+            "gCO2e" => Unit::GramCo2e,
+            "¤" => Unit::Currency,
             _ => return Err(JouleProfilerError::InvalidUnit(s.into())),
         };
 
-        if matches!(unit, Unit::Count) && prefix != UnitPrefix::None {
+        if matches!(unit, Unit::Count | Unit::CountPerJoule) && prefix != UnitPrefix::None {
             return Err(JouleProfilerError::InvalidUnit(s.into()));
         }
 
@@ -219,11 +310,15 @@ mod tests {
         assert!(MetricUnit::try_from("Hz").is_err());
         assert!(MetricUnit::try_from("k").is_err());
         assert!(MetricUnit::try_from("kcount").is_err());
+        assert!(MetricUnit::try_from("kcount/J").is_err());
     }
 
     #[test]
     fn test_backward_conversion() {
-        for s in ["J", "mW", "ns", "kB", "GJ", "count", "%"] {
+        for s in [
+            "J", "mW", "ns", "kB", "GJ", "count", "%", "J·s", "µJ·s²", "nJ/count", "count/J",
+            "gCO2e", "kgCO2e", "¤",
+        ] {
             assert_eq!(parse(s).to_string(), s);
         }
     }
@@ -1,11 +1,20 @@
 use joule_profiler_core::{
     JouleProfiler,
     config::ProfileConfig,
+    recording::{RecordedEvent, Recording},
     sensor::Sensors,
     source::MetricReader,
-    types::{Metrics, PhaseToken},
+    types::{Metric, Metrics, Phase, PhaseToken},
+    unit::{MetricUnit, Unit, UnitPrefix},
 };
 use mockall::mock;
+use std::{
+    sync::{
+        Arc, Mutex, Once,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
 #[derive(Debug)]
 pub struct MockError;
@@ -34,7 +43,18 @@ mock! {
     }
 }
 
+/// Registers the `get_name` expectation once for all tests, static expectations being global.
+fn expect_mock_name() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let context = MockMetricReader::get_name_context();
+        context.expect().return_const("mock");
+        std::mem::forget(context);
+    });
+}
+
 fn mock_reader() -> MockMetricReader {
+    expect_mock_name();
     let mut mock = MockMetricReader::new();
     mock.expect_init().returning(|_| Ok(()));
     mock.expect_join().returning(|| Ok(()));
@@ -43,7 +63,7 @@ fn mock_reader() -> MockMetricReader {
     mock.expect_get_sensors()
         .returning(|| Ok(Sensors::default()));
     mock.expect_to_metrics()
-        .returning(|_| Ok(Metrics::default()));
+        .returning(|()| Ok(Metrics::default()));
     mock
 }
 
@@ -53,6 +73,7 @@ fn config(cmd: Vec<String>, pattern: &str) -> ProfileConfig {
         token_pattern: pattern.to_string(),
         stdout_file: None,
         use_root: false,
+        metrics_record_file: None,
     }
 }
 
@@ -150,3 +171,128 @@ async fn profile_nonzero_exit_code_is_reported() {
     let result = profiler.profile(&config).await.unwrap();
     assert_eq!(result.exit_code, 42);
 }
+
+#[tokio::test]
+async fn session_without_phases_returns_single_phase() {
+    let mut profiler = JouleProfiler::new();
+    profiler.add_source(mock_reader());
+    let session = profiler.start_session().await.unwrap();
+    let results = session.finish().await.unwrap();
+    assert_eq!(results.exit_code, 0);
+    assert_eq!(results.phases.len(), 1);
+    assert_eq!(results.phases[0].start_token, PhaseToken::Start);
+    assert_eq!(results.phases[0].end_token, PhaseToken::End);
+}
+
+#[tokio::test]
+async fn session_phases_are_delimited_by_names() {
+    let mut profiler = JouleProfiler::new();
+    profiler.add_source(mock_reader());
+    let mut session = profiler.start_session().await.unwrap();
+    session.phase("load").await.unwrap();
+    session.phase("compute").await.unwrap();
+    let results = session.finish().await.unwrap();
+
+    let phases = &results.phases;
+    assert_eq!(phases.len(), 3);
+    assert_eq!(phases[0].start_token, PhaseToken::Start);
+    assert_eq!(phases[0].end_token, PhaseToken::Token("load".into()));
+    assert_eq!(phases[1].end_token, PhaseToken::Token("compute".into()));
+    assert_eq!(phases[2].end_token, PhaseToken::End);
+    assert!(phases.iter().all(|phase| phase.start_token_line.is_none()));
+}
+
+#[tokio::test]
+async fn session_gives_sources_back_to_profiler() {
+    let mut profiler = JouleProfiler::new();
+    profiler.add_source(mock_reader());
+    let session = profiler.start_session().await.unwrap();
+    session.finish().await.unwrap();
+
+    let session = profiler.start_session().await.unwrap();
+    assert!(session.finish().await.is_ok());
+}
+
+#[tokio::test]
+async fn session_streams_phases_to_listener() {
+    let streamed = Arc::new(Mutex::new(Vec::new()));
+    let mut profiler = JouleProfiler::new();
+    profiler.add_source(mock_reader());
+    let listener_streamed = Arc::clone(&streamed);
+    profiler.set_phase_listener(move |phase| {
+        listener_streamed.lock().unwrap().push(phase.get_name());
+    });
+
+    let mut session = profiler.start_session().await.unwrap();
+    session.phase("compute").await.unwrap();
+    let results = session.finish().await.unwrap();
+
+    let names: Vec<String> = results.phases.iter().map(Phase::get_name).collect();
+    assert_eq!(*streamed.lock().unwrap(), names);
+    assert_eq!(names, ["START -> compute", "compute -> END"]);
+}
+
+/// The phases are detected from a blocking read of the command output, the sources being polled
+/// meanwhile on the other workers.
+#[tokio::test(flavor = "multi_thread")]
+async fn replay_regenerates_recorded_results() {
+    let dir = tempfile::tempdir().unwrap();
+    let recording_file = dir.path().join("recording.json");
+
+    expect_mock_name();
+    let mut reader = MockMetricReader::new();
+    let counter = AtomicU64::new(0);
+    reader.expect_init().returning(|_| Ok(()));
+    reader.expect_join().returning(|| Ok(()));
+    reader.expect_measure().returning(|| Ok(()));
+    reader.expect_retrieve().returning(|| Ok(()));
+    reader.expect_to_metrics().returning(move |()| {
+        let unit = MetricUnit {
+            prefix: UnitPrefix::Micro,
+            unit: Unit::Joule,
+        };
+        let value = counter.fetch_add(10, Ordering::Relaxed);
+        Ok(vec![Metric::new("PACKAGE-0", value, unit, "mock")])
+    });
+
+    let mut profiler = JouleProfiler::new();
+    profiler.add_source_with_polling(reader, Duration::from_millis(5));
+    profiler.add_source(mock_reader());
+    let mut config = config(
+        vec![
+            "sh".into(),
+            "-c".into(),
+            "sleep 0.05; echo __PHASE_1__; sleep 0.05".into(),
+        ],
+        "__PHASE_[0-9]+__",
+    );
+    config.metrics_record_file = Some(recording_file.to_str().unwrap().to_string());
+    let results = profiler.profile(&config).await.unwrap();
+
+    let recording = Recording::from_file(recording_file.to_str().unwrap()).unwrap();
+    assert_eq!(recording.sources.len(), 2);
+    assert!(
+        recording.sources[0]
+            .metrics
+            .iter()
+            .any(|recorded| recorded.event == RecordedEvent::Poll)
+    );
+    assert!(results.phases.iter().all(|phase| !phase.power.is_empty()));
+
+    let replayed = JouleProfiler::new().replay(&recording).await.unwrap();
+    assert_eq!(
+        serde_json::to_value(&replayed).unwrap(),
+        serde_json::to_value(&results).unwrap()
+    );
+    for (replayed, recorded) in replayed.phases.iter().zip(&results.phases) {
+        assert_eq!(replayed.samples.len(), recorded.samples.len());
+        for (replayed, recorded) in replayed.samples.iter().zip(&recorded.samples) {
+            assert_eq!(replayed.timestamp, recorded.timestamp);
+            assert_eq!(replayed.duration_us, recorded.duration_us);
+            assert_eq!(
+                serde_json::to_value(&replayed.metrics).unwrap(),
+                serde_json::to_value(&recorded.metrics).unwrap()
+            );
+        }
+    }
+}
@@ -1,14 +1,14 @@
 //! NVML (NVIDIA Management Library) energy profiling integration.
 //!
 //! This module provides energy consumption monitoring for NVIDIA GPUs using the NVML library.
-//! It implements the `MetricReader` trait to collect energy metrics from GPU devices and
+//! It implements the `BlockingMetricReader` trait to collect energy metrics from GPU devices and
 //! track energy usage over time.
 
 use std::collections::HashMap;
 
 use joule_profiler_core::{
     sensor::Sensors,
-    source::MetricReader,
+    source::BlockingMetricReader,
     types::{Metric, Metrics},
     unit::{MetricUnit, Unit, UnitPrefix},
 };
@@ -87,12 +87,14 @@ impl Nvml {
     }
 }
 
-impl<H: NvmlHardware + 'static> MetricReader for Nvml<H> {
+impl<H: NvmlHardware + 'static> BlockingMetricReader for Nvml<H> {
     type Type = Phase;
 
     type Error = NvmlError;
 
-    async fn measure(&mut self) -> Result<()> {
+    type Converter = ();
+
+    fn measure(&mut self) -> Result<()> {
         let new_snapshot = self.hardware.read_snapshot()?;
         if self.begin_snapshot.is_none() {
             self.begin_snapshot = Some(new_snapshot);
@@ -102,7 +104,7 @@ impl<H: NvmlHardware + 'static> MetricReader for Nvml<H> {
         Ok(())
     }
 
-    async fn retrieve(&mut self) -> Result<Self::Type> {
+    fn retrieve(&mut self) -> Result<Self::Type> {
         if let Some(begin) = self.begin_snapshot.take()
             && let Some(end) = self.last_snapshot.take()
         {
@@ -117,7 +119,9 @@ impl<H: NvmlHardware + 'static> MetricReader for Nvml<H> {
         self.hardware.get_sensors()
     }
 
-    fn to_metrics(&self, result: Self::Type) -> Result<Metrics> {
+    fn converter(&self) {}
+
+    fn to_metrics((): &(), result: Self::Type) -> Result<Metrics> {
         let diff = Self::compute_energy_diff(&result.end, &result.begin)?;
         Ok(diff
             .gpus_energy
@@ -216,21 +220,21 @@ mod tests {
         assert!(matches!(result, Err(NvmlError::UnknownMetricError(_))));
     }
 
-    #[tokio::test]
-    async fn measure_stores_begin_snapshot() {
+    #[test]
+    fn measure_stores_begin_snapshot() {
         let mut hardware = MockNvmlHardware::new();
         hardware
             .expect_read_snapshot()
             .returning(|| Ok(snapshot(vec![(0, 100)])));
 
         let mut nvml = nvml_with_hardware(hardware);
-        nvml.measure().await.unwrap();
+        nvml.measure().unwrap();
         assert!(nvml.begin_snapshot.is_some());
         assert!(nvml.last_snapshot.is_none());
     }
 
-    #[tokio::test]
-    async fn measure_twice_stores_last_snapshot() {
+    #[test]
+    fn measure_twice_stores_last_snapshot() {
         let mut hardware = MockNvmlHardware::new();
         let mut read_snapshot_call_count = 0u64;
         hardware.expect_read_snapshot().returning(move || {
@@ -239,31 +243,28 @@ mod tests {
         });
 
         let mut nvml = nvml_with_hardware(hardware);
-        nvml.measure().await.unwrap();
-        nvml.measure().await.unwrap();
+        nvml.measure().unwrap();
+        nvml.measure().unwrap();
 
         assert!(nvml.begin_snapshot.is_some());
         assert!(nvml.last_snapshot.is_some());
     }
 
-    #[tokio::test]
-    async fn retrieve_without_enough_snapshots_returns_error() {
+    #[test]
+    fn retrieve_without_enough_snapshots_returns_error() {
         let mut hardware = MockNvmlHardware::new();
         hardware
             .expect_read_snapshot()
             .returning(|| Ok(snapshot(vec![(0, 100)])));
 
         let mut nvml = nvml_with_hardware(hardware);
-        nvml.measure().await.unwrap();
+        nvml.measure().unwrap();
 
-        assert!(matches!(
-            nvml.retrieve().await,
-            Err(NvmlError::NotEnoughSamples)
-        ));
+        assert!(matches!(nvml.retrieve(), Err(NvmlError::NotEnoughSamples)));
     }
 
-    #[tokio::test]
-    async fn retrieve_returns_correct_phase() {
+    #[test]
+    fn retrieve_returns_correct_phase() {
         let mut hardware = MockNvmlHardware::new();
         let mut read_snapshot_call_count = 0;
 
@@ -273,16 +274,16 @@ mod tests {
         });
         let mut nvml = nvml_with_hardware(hardware);
 
-        nvml.measure().await.unwrap();
-        nvml.measure().await.unwrap();
-        let phase = nvml.retrieve().await.unwrap();
+        nvml.measure().unwrap();
+        nvml.measure().unwrap();
+        let phase = nvml.retrieve().unwrap();
 
         assert_eq!(phase.begin.gpus_energy[&0], 100);
         assert_eq!(phase.end.gpus_energy[&0], 200);
     }
 
-    #[tokio::test]
-    async fn retrieve_replace_begin_snapshot_with_end() {
+    #[test]
+    fn retrieve_replace_begin_snapshot_with_end() {
         let mut hardware = MockNvmlHardware::new();
         let mut read_snapshot_call_count = 0u64;
 
@@ -292,16 +293,16 @@ mod tests {
         });
 
         let mut nvml = nvml_with_hardware(hardware);
-        nvml.measure().await.unwrap();
-        nvml.measure().await.unwrap();
-        nvml.retrieve().await.unwrap();
+        nvml.measure().unwrap();
+        nvml.measure().unwrap();
+        nvml.retrieve().unwrap();
 
         assert_eq!(nvml.begin_snapshot.as_ref().unwrap().gpus_energy[&0], 200);
         assert!(nvml.last_snapshot.is_none());
     }
 
-    #[tokio::test]
-    async fn to_metrics_returns_correct_values() {
+    #[test]
+    fn to_metrics_returns_correct_values() {
         let mut hardware = MockNvmlHardware::new();
         let mut read_snapshot_call_count = 0;
 
@@ -314,10 +315,10 @@ mod tests {
         });
 
         let mut nvml = nvml_with_hardware(hardware);
-        nvml.measure().await.unwrap();
-        nvml.measure().await.unwrap();
-        let phase = nvml.retrieve().await.unwrap();
-        let mut metrics = nvml.to_metrics(phase).unwrap();
+        nvml.measure().unwrap();
+        nvml.measure().unwrap();
+        let phase = nvml.retrieve().unwrap();
+        let mut metrics = Nvml::<MockNvmlHardware>::to_metrics(&(), phase).unwrap();
         metrics.sort_by_key(|m| m.name.clone());
 
         assert_eq!(metrics.len(), 2);
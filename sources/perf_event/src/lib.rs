@@ -8,7 +8,7 @@
 
 use joule_profiler_core::{
     sensor::{Sensor, Sensors},
-    source::MetricReader,
+    source::{BlockingMetricReader, MetricScope, SourceCapabilities},
     types::{Metric, Metrics},
     unit::{MetricUnit, Unit, UnitPrefix},
 };
@@ -58,18 +58,19 @@ impl PerfEvent {
     }
 }
 
-impl<H: PerfEventHardware + 'static> MetricReader for PerfEvent<H> {
+impl<H: PerfEventHardware + 'static> BlockingMetricReader for PerfEvent<H> {
     type Type = Phase;
     type Error = PerfEventError;
+    type Converter = ();
 
     /// Initialize counters for a specific process and start monitoring.
-    async fn init(&mut self, pid: i32) -> Result<()> {
+    fn init(&mut self, pid: i32) -> Result<()> {
         info!("Initializing perf_event source for PID {pid}");
         self.hardware.init_counters(pid)
     }
 
     /// Read current counter values and compute delta since last measurement.
-    async fn measure(&mut self) -> Result<()> {
+    fn measure(&mut self) -> Result<()> {
         trace!("Reading perf_event counters");
         let new_snapshot = self.hardware.read_snapshot()?;
         if self.begin_snapshot.is_none() {
@@ -81,7 +82,7 @@ impl<H: PerfEventHardware + 'static> MetricReader for PerfEvent<H> {
     }
 
     /// Retrieve and consume the last measurement snapshot.
-    async fn retrieve(&mut self) -> Result<Self::Type> {
+    fn retrieve(&mut self) -> Result<Self::Type> {
         if let Some(begin) = self.begin_snapshot.take()
             && let Some(end) = self.last_snapshot.take()
         {
@@ -107,8 +108,10 @@ impl<H: PerfEventHardware + 'static> MetricReader for PerfEvent<H> {
         Ok(sensors)
     }
 
+    fn converter(&self) {}
+
     /// Convert raw counter values to metrics with metadata.
-    fn to_metrics(&self, result: Self::Type) -> Result<Metrics> {
+    fn to_metrics((): &(), result: Self::Type) -> Result<Metrics> {
         trace!(
             "Converting {} counters to metrics",
             result.begin.metrics.len()
@@ -123,6 +126,14 @@ impl<H: PerfEventHardware + 'static> MetricReader for PerfEvent<H> {
             .collect())
     }
 
+    /// Counters are filtered on the profiled process and its children.
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            scope: MetricScope::Process,
+            ..Default::default()
+        }
+    }
+
     fn get_name() -> &'static str {
         "perf_event"
     }
@@ -149,22 +160,22 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn measure_stores_begin_snapshot() {
+    #[test]
+    fn measure_stores_begin_snapshot() {
         let mut hardware = MockPerfEventHardware::new();
         hardware
             .expect_read_snapshot()
             .returning(|| Ok(snapshot(vec![(Event::CpuCycles, 100)])));
 
         let mut source = nvml_with_hardware(hardware);
-        source.measure().await.unwrap();
+        source.measure().unwrap();
 
         assert!(source.begin_snapshot.is_some());
         assert!(source.last_snapshot.is_none());
     }
 
-    #[tokio::test]
-    async fn measure_twice_stores_last_snapshot() {
+    #[test]
+    fn measure_twice_stores_last_snapshot() {
         let mut hardware = MockPerfEventHardware::new();
         let mut read_snapshot_call_count = 0u64;
         hardware.expect_read_snapshot().returning(move || {
@@ -176,31 +187,31 @@ mod tests {
         });
 
         let mut source = nvml_with_hardware(hardware);
-        source.measure().await.unwrap();
-        source.measure().await.unwrap();
+        source.measure().unwrap();
+        source.measure().unwrap();
 
         assert!(source.begin_snapshot.is_some());
         assert!(source.last_snapshot.is_some());
     }
 
-    #[tokio::test]
-    async fn retrieve_without_enough_snapshots_returns_error() {
+    #[test]
+    fn retrieve_without_enough_snapshots_returns_error() {
         let mut hardware = MockPerfEventHardware::new();
         hardware
             .expect_read_snapshot()
             .returning(|| Ok(snapshot(vec![(Event::CpuCycles, 100)])));
 
         let mut source = nvml_with_hardware(hardware);
-        source.measure().await.unwrap();
+        source.measure().unwrap();
 
         assert!(matches!(
-            source.retrieve().await,
+            source.retrieve(),
             Err(PerfEventError::NotEnoughSamples)
         ));
     }
 
-    #[tokio::test]
-    async fn retrieve_returns_correct_phase() {
+    #[test]
+    fn retrieve_returns_correct_phase() {
         let mut hardware = MockPerfEventHardware::new();
         let mut read_snapshot_call_count = 0u64;
         hardware.expect_read_snapshot().returning(move || {
@@ -212,16 +223,16 @@ mod tests {
         });
 
         let mut source = nvml_with_hardware(hardware);
-        source.measure().await.unwrap();
-        source.measure().await.unwrap();
-        let phase = source.retrieve().await.unwrap();
+        source.measure().unwrap();
+        source.measure().unwrap();
+        let phase = source.retrieve().unwrap();
 
         assert_eq!(phase.begin.metrics[&Event::CpuCycles], 100);
         assert_eq!(phase.end.metrics[&Event::CpuCycles], 200);
     }
 
-    #[tokio::test]
-    async fn retrieve_rolls_begin_snapshot_to_end() {
+    #[test]
+    fn retrieve_rolls_begin_snapshot_to_end() {
         let mut hardware = MockPerfEventHardware::new();
         let mut read_snapshot_call_count = 0u64;
         hardware.expect_read_snapshot().returning(move || {
@@ -233,9 +244,9 @@ mod tests {
         });
 
         let mut source = nvml_with_hardware(hardware);
-        source.measure().await.unwrap();
-        source.measure().await.unwrap();
-        source.retrieve().await.unwrap();
+        source.measure().unwrap();
+        source.measure().unwrap();
+        source.retrieve().unwrap();
         assert_eq!(
             source.begin_snapshot.as_ref().unwrap().metrics[&Event::CpuCycles],
             200
@@ -243,8 +254,8 @@ mod tests {
         assert!(source.last_snapshot.is_none());
     }
 
-    #[tokio::test]
-    async fn to_metrics_returns_correct_values() {
+    #[test]
+    fn to_metrics_returns_correct_values() {
         let mut hardware = MockPerfEventHardware::new();
         let mut read_snapshot_call_count = 0;
         hardware.expect_read_snapshot().returning(move || {
@@ -256,10 +267,10 @@ mod tests {
         });
 
         let mut source = nvml_with_hardware(hardware);
-        source.measure().await.unwrap();
-        source.measure().await.unwrap();
-        let phase = source.retrieve().await.unwrap();
-        let metrics = source.to_metrics(phase).unwrap();
+        source.measure().unwrap();
+        source.measure().unwrap();
+        let phase = source.retrieve().unwrap();
+        let metrics = PerfEvent::<MockPerfEventHardware>::to_metrics(&(), phase).unwrap();
         let cycles = metrics
             .iter()
             .find(|m| m.name == Event::CpuCycles.to_string())
@@ -268,4 +279,10 @@ mod tests {
         assert_eq!(cycles.value, MetricValue::UnsignedInteger(500));
         assert_eq!(cycles.unit, PERF_EVENT_METRIC_UNIT);
     }
+
+    #[test]
+    fn capabilities_are_process_scoped() {
+        let source = nvml_with_hardware(MockPerfEventHardware::new());
+        assert_eq!(source.capabilities().scope, MetricScope::Process);
+    }
 }
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Errors that can occur when using the proc source.
+#[derive(Debug, Error)]
+pub enum ProcError {
+    /// I/O error while reading procfs.
+    #[error("{0}")]
+    IoError(
+        #[from]
+        #[source]
+        std::io::Error,
+    ),
+
+    /// A procfs file does not have the expected format.
+    #[error("Cannot parse {0}")]
+    InvalidProcFile(PathBuf),
+
+    /// The system clock tick rate cannot be retrieved.
+    #[error("Cannot retrieve the system clock tick rate")]
+    InvalidClockTicks,
+
+    /// Not enough snapshots have been taken to compute the delta between two measures.
+    #[error("Not enough measures to compute CPU time differences")]
+    NotEnoughSamples,
+}
@@ -0,0 +1,226 @@
+//! procfs source measuring the CPU time of the profiled process.
+//!
+//! Samples the CPU time of the profiled process and all its descendants from `/proc/<pid>/stat`,
+//! and the busy CPU time of the whole machine from `/proc/stat`. The profiler uses their ratio to
+//! attribute to the process its share of the machine-wide energy.
+//!
+//! Reading procfs blocks the calling thread, the reader is thus meant to be wrapped into a
+//! [`BlockingAdapter`](`joule_profiler_core::source::BlockingAdapter`).
+
+use std::path::{Path, PathBuf};
+
+use joule_profiler_core::{
+    sensor::{Sensor, Sensors},
+    source::{BlockingMetricReader, MetricScope, SourceCapabilities},
+    types::{Metric, Metrics},
+    unit::{MetricUnit, Unit, UnitPrefix},
+};
+use log::{debug, info, trace};
+
+use crate::snapshot::{Phase, Snapshot, read_snapshot};
+
+mod error;
+mod snapshot;
+
+pub use error::ProcError;
+
+type Result<T> = std::result::Result<T, ProcError>;
+
+/// Default mount point of procfs.
+const DEFAULT_PROCFS_ROOT: &str = "/proc";
+
+/// Name of the metric holding the CPU time of the profiled process.
+const PROCESS_CPU_TIME: &str = "PROCESS_CPU_TIME";
+
+/// Name of the metric holding the busy CPU time of the machine.
+const SYSTEM_CPU_TIME: &str = "SYSTEM_CPU_TIME";
+
+const CPU_TIME_METRIC_UNIT: MetricUnit = MetricUnit {
+    prefix: UnitPrefix::Milli,
+    unit: Unit::Second,
+};
+
+/// CPU time source reading procfs.
+///
+/// Reports the `PROCESS_CPU_TIME` of the profiled process tree and the `SYSTEM_CPU_TIME`
+/// of the machine, both in milliseconds and summed over all CPUs.
+pub struct CpuTime {
+    root: PathBuf,
+    clock_ticks: u64,
+    pid: Option<i32>,
+    begin_snapshot: Option<Snapshot>,
+    last_snapshot: Option<Snapshot>,
+}
+
+impl CpuTime {
+    /// Creates a new uninitialized CPU time source reading `/proc`.
+    pub fn new() -> Result<Self> {
+        Self::with_procfs_root(DEFAULT_PROCFS_ROOT)
+    }
+
+    /// Creates a new uninitialized CPU time source reading the procfs mounted at `root`.
+    pub fn with_procfs_root<P: AsRef<Path>>(root: P) -> Result<Self> {
+        // SAFETY: `sysconf` has no preconditions, it only reads a system configuration value and
+        // returns -1 when the name is not supported.
+        let clock_ticks = u64::try_from(unsafe { libc::sysconf(libc::_SC_CLK_TCK) })
+            .ok()
+            .filter(|&ticks| ticks > 0)
+            .ok_or(ProcError::InvalidClockTicks)?;
+
+        debug!("Creating new proc source with {clock_ticks} clock ticks per second");
+        Ok(Self {
+            root: root.as_ref().to_path_buf(),
+            clock_ticks,
+            pid: None,
+            begin_snapshot: None,
+            last_snapshot: None,
+        })
+    }
+}
+
+/// Converts clock ticks to milliseconds.
+fn ticks_to_millis(ticks: u64, clock_ticks: u64) -> u64 {
+    ticks.saturating_mul(1000) / clock_ticks
+}
+
+impl BlockingMetricReader for CpuTime {
+    type Type = Phase;
+    type Error = ProcError;
+
+    /// Clock ticks per second.
+    type Converter = u64;
+
+    fn init(&mut self, pid: i32) -> Result<()> {
+        info!("Initializing proc source for PID {pid}");
+        self.pid = Some(pid);
+        Ok(())
+    }
+
+    /// Read the current CPU times, the machine ones only before initialization.
+    fn measure(&mut self) -> Result<()> {
+        trace!("Reading CPU times from {}", self.root.display());
+        let new_snapshot = read_snapshot(&self.root, self.pid.unwrap_or_default())?;
+        if self.begin_snapshot.is_none() {
+            self.begin_snapshot = Some(new_snapshot);
+        } else {
+            self.last_snapshot = Some(new_snapshot);
+        }
+        Ok(())
+    }
+
+    /// Retrieve and consume the last measurement snapshot.
+    fn retrieve(&mut self) -> Result<Self::Type> {
+        if let Some(begin) = self.begin_snapshot.take()
+            && let Some(end) = self.last_snapshot.take()
+        {
+            self.begin_snapshot = Some(end);
+            Ok(Phase { begin, end })
+        } else {
+            Err(ProcError::NotEnoughSamples)
+        }
+    }
+
+    fn get_sensors(&self) -> Result<Sensors> {
+        Ok(vec![
+            Sensor::new(PROCESS_CPU_TIME, CPU_TIME_METRIC_UNIT, Self::get_name()),
+            Sensor::new(SYSTEM_CPU_TIME, CPU_TIME_METRIC_UNIT, Self::get_name()),
+        ])
+    }
+
+    fn converter(&self) -> u64 {
+        self.clock_ticks
+    }
+
+    fn to_metrics(clock_ticks: &u64, result: Self::Type) -> Result<Metrics> {
+        let diff = result.diff();
+        Ok(vec![
+            Metric::new(
+                PROCESS_CPU_TIME,
+                ticks_to_millis(diff.process_ticks, *clock_ticks),
+                CPU_TIME_METRIC_UNIT,
+                Self::get_name(),
+            ),
+            Metric::new(
+                SYSTEM_CPU_TIME,
+                ticks_to_millis(diff.system_ticks, *clock_ticks),
+                CPU_TIME_METRIC_UNIT,
+                Self::get_name(),
+            ),
+        ])
+    }
+
+    /// The process time is filtered on the profiled process and its descendants.
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            scope: MetricScope::Process,
+            ..Default::default()
+        }
+    }
+
+    fn get_name() -> &'static str {
+        "proc"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use joule_profiler_core::types::MetricValue;
+
+    use super::*;
+    use crate::snapshot::tests::{stat, write_process};
+
+    fn write_times(root: &Path, process_ticks: u64, system_ticks: u64) {
+        fs::write(
+            root.join("stat"),
+            format!("cpu  {system_ticks} 0 0 0 0 0 0 0 0 0\n"),
+        )
+        .unwrap();
+        write_process(root, "self", &stat(1, "joule-profiler", 0, [0; 4]));
+        write_process(root, "2", &stat(2, "sleep", 1, [process_ticks, 0, 0, 0]));
+    }
+
+    #[test]
+    fn retrieve_without_enough_snapshots_returns_error() {
+        let dir = tempfile::tempdir().unwrap();
+        write_times(dir.path(), 0, 0);
+
+        let mut source = CpuTime::with_procfs_root(dir.path()).unwrap();
+        source.init(2).unwrap();
+        source.measure().unwrap();
+        assert!(matches!(
+            source.retrieve(),
+            Err(ProcError::NotEnoughSamples)
+        ));
+    }
+
+    #[test]
+    fn to_metrics_returns_elapsed_times() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut source = CpuTime::with_procfs_root(dir.path()).unwrap();
+        source.init(2).unwrap();
+
+        write_times(dir.path(), 10, 100);
+        source.measure().unwrap();
+        write_times(
+            dir.path(),
+            10 + source.clock_ticks,
+            100 + 4 * source.clock_ticks,
+        );
+        source.measure().unwrap();
+
+        let phase = source.retrieve().unwrap();
+        let metrics = CpuTime::to_metrics(&source.converter(), phase).unwrap();
+        assert_eq!(metrics[0].name, PROCESS_CPU_TIME);
+        assert_eq!(metrics[0].value, MetricValue::UnsignedInteger(1000));
+        assert_eq!(metrics[1].name, SYSTEM_CPU_TIME);
+        assert_eq!(metrics[1].value, MetricValue::UnsignedInteger(4000));
+    }
+
+    #[test]
+    fn capabilities_are_process_scoped() {
+        let source = CpuTime::new().unwrap();
+        assert_eq!(source.capabilities().scope, MetricScope::Process);
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::error::ProcError;
+
+type Result<T> = std::result::Result<T, ProcError>;
+
+/// Index of the parent pid in a `/proc/<pid>/stat` file, counted after the command name.
+const PPID_FIELD: usize = 1;
+
+/// Index of the user time in a `/proc/<pid>/stat` file, counted after the command name.
+///
+/// It is followed by the system time, then the user and system times of the waited-for children.
+const UTIME_FIELD: usize = 11;
+
+/// Indexes of the `/proc/stat` CPU fields counted as busy time: user, nice, system, then irq,
+/// softirq and steal, skipping idle and iowait. Guest times are already included in the user times.
+const BUSY_FIELDS: [usize; 6] = [0, 1, 2, 5, 6, 7];
+
+/// CPU times in clock ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// CPU time of the profiled process and its descendants.
+    pub process_ticks: u64,
+
+    /// Busy CPU time of the whole machine, summed over all CPUs.
+    pub system_ticks: u64,
+}
+
+/// A pair of snapshots delimiting a phase.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Phase {
+    /// The snapshot made at the start of a phase.
+    pub begin: Snapshot,
+
+    /// End snapshot of the phase.
+    pub end: Snapshot,
+}
+
+impl Phase {
+    /// Computes the CPU times elapsed between begin and end.
+    ///
+    /// The process time may decrease when a descendant is reparented out of the process tree,
+    /// in which case the delta is zero.
+    pub fn diff(&self) -> Snapshot {
+        Snapshot {
+            process_ticks: self
+                .end
+                .process_ticks
+                .saturating_sub(self.begin.process_ticks),
+            system_ticks: self
+                .end
+                .system_ticks
+                .saturating_sub(self.begin.system_ticks),
+        }
+    }
+}
+
+/// CPU times of a process read from its stat file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ProcessStat {
+    pid: i32,
+
+    ppid: i32,
+
+    /// User and system times of the process.
+    ticks: u64,
+
+    /// User and system times of its waited-for children.
+    children_ticks: u64,
+}
+
+/// Reads the CPU times of the process `pid` and of the machine from the procfs mounted at `root`.
+///
+/// The process time is the sum of the times of the live processes of its tree, plus the times of
+/// the descendants already waited for. Once the profiled process itself has been waited for by the
+/// profiler, its times are accounted in the children times of the profiler (`/proc/self`).
+/// When the profiled process is the profiler itself, as in a session, these children times are
+/// already part of its tree and are not counted twice.
+///
+/// The tree is walked through the `/proc/<pid>/task/<tid>/children` files when the kernel provides
+/// them, only reading the stat files of the profiled processes. Otherwise, the stat files of all
+/// the processes are read to find the parent of each one.
+pub fn read_snapshot(root: &Path, pid: i32) -> Result<Snapshot> {
+    let profiler = read_process_stat(&root.join("self").join("stat"))?;
+    let tree = if root.join("thread-self").join("children").is_file() {
+        read_tree(root, pid)?
+    } else {
+        scan_tree(root, pid)?
+    };
+
+    let inherited_ticks = if profiler.pid == pid {
+        0
+    } else {
+        profiler.children_ticks
+    };
+    let process_ticks = inherited_ticks
+        + tree
+            .iter()
+            .map(|stat| stat.ticks + stat.children_ticks)
+            .sum::<u64>();
+
+    Ok(Snapshot {
+        process_ticks,
+        system_ticks: read_system_ticks(&root.join("stat"))?,
+    })
+}
+
+/// Reads the stat files of the process tree, following the children files of its threads.
+fn read_tree(root: &Path, pid: i32) -> Result<Vec<ProcessStat>> {
+    let mut tree = Vec::new();
+    let mut pending = vec![pid];
+    while let Some(current) = pending.pop() {
+        let dir = root.join(current.to_string());
+        let Some(stat) = skip_exited(read_process_stat(&dir.join("stat")))? else {
+            continue;
+        };
+        tree.push(stat);
+
+        let Some(tasks) = skip_exited(fs::read_dir(dir.join("task")).map_err(ProcError::from))?
+        else {
+            continue;
+        };
+        for task in tasks {
+            let children = task.and_then(|task| fs::read_to_string(task.path().join("children")));
+            let Some(children) = skip_exited(children.map_err(ProcError::from))? else {
+                continue;
+            };
+            pending.extend(
+                children
+                    .split_whitespace()
+                    .filter_map(|child| child.parse::<i32>().ok()),
+            );
+        }
+    }
+    Ok(tree)
+}
+
+/// Reads the stat files of the process tree, found among the stat files of all the processes.
+fn scan_tree(root: &Path, pid: i32) -> Result<Vec<ProcessStat>> {
+    let mut processes = read_processes(root)?;
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for stat in processes.values() {
+        children.entry(stat.ppid).or_default().push(stat.pid);
+    }
+
+    let mut tree = Vec::new();
+    let mut pending = vec![pid];
+    while let Some(current) = pending.pop() {
+        if let Some(stat) = processes.remove(&current) {
+            tree.push(stat);
+        }
+        if let Some(children) = children.remove(&current) {
+            pending.extend(children);
+        }
+    }
+    Ok(tree)
+}
+
+/// Reads the stat files of all the processes, skipping the ones exiting meanwhile.
+fn read_processes(root: &Path) -> Result<HashMap<i32, ProcessStat>> {
+    let mut processes = HashMap::new();
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<i32>().ok())
+        else {
+            continue;
+        };
+
+        if let Some(stat) = skip_exited(read_process_stat(&entry.path().join("stat")))? {
+            processes.insert(pid, stat);
+        }
+    }
+    Ok(processes)
+}
+
+/// Turns the error of a procfs file of an exited process or thread into `None`.
+fn skip_exited<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ProcError::IoError(err))
+            if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::InvalidInput)
+                || err.raw_os_error() == Some(libc::ESRCH) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn read_process_stat(path: &Path) -> Result<ProcessStat> {
+    let content = fs::read_to_string(path)?;
+    parse_process_stat(&content).ok_or_else(|| ProcError::InvalidProcFile(path.to_path_buf()))
+}
+
+/// Parses a `/proc/<pid>/stat` file, whose command name may contain spaces and parentheses.
+fn parse_process_stat(content: &str) -> Option<ProcessStat> {
+    let (pid, _) = content.split_once(" (")?;
+    let (_, fields) = content.rsplit_once(')')?;
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+
+    Some(ProcessStat {
+        pid: pid.trim().parse().ok()?,
+        ppid: fields.get(PPID_FIELD)?.parse().ok()?,
+        ticks: field(UTIME_FIELD)? + field(UTIME_FIELD + 1)?,
+        children_ticks: field(UTIME_FIELD + 2)? + field(UTIME_FIELD + 3)?,
+    })
+}
+
+fn read_system_ticks(path: &Path) -> Result<u64> {
+    let content = fs::read_to_string(path)?;
+    parse_system_ticks(&content).ok_or_else(|| ProcError::InvalidProcFile(path.to_path_buf()))
+}
+
+/// Parses the aggregated `cpu` line of `/proc/stat`.
+fn parse_system_ticks(content: &str) -> Option<u64> {
+    let line = content.lines().find(|line| line.starts_with("cpu "))?;
+    let fields = line
+        .split_whitespace()
+        .skip(1)
+        .map(|field| field.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    BUSY_FIELDS
+        .iter()
+        .map(|&index| fields.get(index).copied())
+        .sum()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a stat file content with the given parent and times.
+    pub(crate) fn stat(pid: i32, comm: &str, parent: i32, times: [u64; 4]) -> String {
+        let [user, system, children_user, children_system] = times;
+        format!(
+            "{pid} ({comm}) S {parent} {pid} {pid} 0 -1 4194560 100 0 0 0 {user} {system} {children_user} {children_system} 20 0 1 0 100 1000 10"
+        )
+    }
+
+    pub(crate) fn write_process(root: &Path, name: &str, content: &str) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("stat"), content).unwrap();
+    }
+
+    fn write_children(root: &Path, pid: i32, tid: i32, children: &[i32]) {
+        let dir = root
+            .join(pid.to_string())
+            .join("task")
+            .join(tid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let children: Vec<String> = children.iter().map(ToString::to_string).collect();
+        fs::write(dir.join("children"), children.join(" ")).unwrap();
+    }
+
+    #[test]
+    fn parse_process_stat_with_tricky_command_name() {
+        let content = stat(42, "weird) name (", 1, [10, 5, 3, 2]);
+        assert_eq!(
+            parse_process_stat(&content),
+            Some(ProcessStat {
+                pid: 42,
+                ppid: 1,
+                ticks: 15,
+                children_ticks: 5
+            })
+        );
+    }
+
+    #[test]
+    fn parse_truncated_process_stat_fails() {
+        assert!(parse_process_stat("42 (sleep) S 1 42").is_none());
+    }
+
+    #[test]
+    fn parse_system_ticks_sums_busy_fields() {
+        let content = "cpu  100 10 50 1000 20 5 5 2 40 0\ncpu0 50 5 25 500 10 2 2 1 20 0\n";
+        assert_eq!(parse_system_ticks(content), Some(172));
+    }
+
+    #[test]
+    fn read_snapshot_sums_process_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("stat"), "cpu  100 0 100 1000 0 0 0 0 0 0\n").unwrap();
+        write_process(root, "self", &stat(10, "joule-profiler", 1, [1, 1, 7, 3]));
+        write_process(root, "20", &stat(20, "sh", 10, [4, 1, 2, 0]));
+        write_process(root, "21", &stat(21, "python", 20, [30, 10, 0, 0]));
+        write_process(root, "22", &stat(22, "worker", 21, [5, 0, 0, 0]));
+        write_process(root, "30", &stat(30, "other", 1, [1000, 0, 0, 0]));
+
+        let snapshot = read_snapshot(root, 20).unwrap();
+        assert_eq!(snapshot.process_ticks, 10 + 7 + 40 + 5);
+        assert_eq!(snapshot.system_ticks, 200);
+    }
+
+    #[test]
+    fn read_snapshot_follows_children_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("stat"), "cpu  100 0 100 1000 0 0 0 0 0 0\n").unwrap();
+        fs::create_dir_all(root.join("thread-self")).unwrap();
+        fs::write(root.join("thread-self").join("children"), "").unwrap();
+        write_process(root, "self", &stat(10, "joule-profiler", 1, [1, 1, 7, 3]));
+        write_process(root, "20", &stat(20, "sh", 10, [4, 1, 2, 0]));
+        write_children(root, 20, 20, &[21]);
+        write_process(root, "21", &stat(21, "python", 20, [30, 10, 0, 0]));
+        write_children(root, 21, 21, &[]);
+        // Thread spawning a worker, and a process exited meanwhile.
+        write_children(root, 21, 23, &[22, 24]);
+        write_process(root, "22", &stat(22, "worker", 21, [5, 0, 0, 0]));
+        // Child of the tree in its stat file only: the children files are trusted.
+        write_process(root, "30", &stat(30, "other", 20, [1000, 0, 0, 0]));
+
+        let snapshot = read_snapshot(root, 20).unwrap();
+        assert_eq!(snapshot.process_ticks, 10 + 7 + 40 + 5);
+    }
+
+    #[test]
+    fn read_snapshot_of_profiler_counts_its_children_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("stat"), "cpu  100 0 100 1000 0 0 0 0 0 0\n").unwrap();
+        let profiler = stat(10, "joule-profiler", 1, [1, 1, 7, 3]);
+        write_process(root, "self", &profiler);
+        write_process(root, "10", &profiler);
+        write_process(root, "20", &stat(20, "worker", 10, [4, 1, 0, 0]));
+
+        let snapshot = read_snapshot(root, 10).unwrap();
+        assert_eq!(snapshot.process_ticks, 2 + 10 + 5);
+    }
+
+    #[test]
+    fn diff_saturates_when_process_time_decreases() {
+        let phase = Phase {
+            begin: Snapshot {
+                process_ticks: 50,
+                system_ticks: 100,
+            },
+            end: Snapshot {
+                process_ticks: 40,
+                system_ticks: 300,
+            },
+        };
+        assert_eq!(
+            phase.diff(),
+            Snapshot {
+                process_ticks: 0,
+                system_ticks: 200
+            }
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! Intel RAPL metric source for Joule Profiler.
 //!
-//! This module provides several implementations of [`MetricReader`] for
+//! This module provides several implementations of [`BlockingMetricReader`] for
 //! collecting energy metrics from Intel RAPL (Running Average Power Limit) domains.
 //!
 //! # Backends
@@ -9,6 +9,8 @@
 //! - [`powercap`] — uses the Linux `powercap` interface for energy readings.
 //! - [`perf`] — uses `perf_event` counters (`perf_event_open`) for RAPL domains.
 
+use std::time::Duration;
+
 use joule_profiler_core::unit::{MetricUnit, Unit, UnitPrefix};
 
 mod domain_type;
@@ -27,3 +29,6 @@ const MICRO_JOULE_UNIT: MetricUnit = MetricUnit {
     prefix: UnitPrefix::Micro,
     unit: Unit::Joule,
 };
+
+/// Update interval of the RAPL counters, measures closer than this are not meaningful.
+const RAPL_UPDATE_INTERVAL: Duration = Duration::from_millis(1);
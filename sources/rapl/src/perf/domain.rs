@@ -102,6 +102,7 @@ pub fn discover_domains_and_open_counters(
 }
 
 #[cfg(test)]
+#[allow(clippy::float_cmp)]
 mod tests {
     use super::*;
     use crate::domain_type::RaplDomainType;
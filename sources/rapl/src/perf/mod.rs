@@ -6,21 +6,22 @@ use std::{
 
 use joule_profiler_core::{
     sensor::{Sensor, Sensors},
-    source::MetricReader,
+    source::{BlockingMetricReader, SourceCapabilities},
     types::{Metric, Metrics},
 };
 use log::{info, trace};
 use perf_event::GroupData;
 
 use crate::{
-    MICRO_JOULE_UNIT, Result,
+    MICRO_JOULE_UNIT, RAPL_UPDATE_INTERVAL, Result,
+    domain_type::RaplDomainIndex,
     error::{PerfParanoidError, RaplError},
     perf::{
         compute::{compute_measurement_from_snapshots, joules_to_micro_joules},
         domain::discover_domains_and_open_counters,
         socket::Socket,
     },
-    snapshot::{Phase, Snapshot},
+    snapshot::Snapshot,
     util::check_os,
 };
 
@@ -117,14 +118,33 @@ impl Rapl {
 
         Ok(Snapshot { metrics })
     }
+
+    /// Scales the raw counter differences of the domains to microjoules.
+    fn scale_to_micro_joules(&self, diff: &HashMap<RaplDomainIndex, u64>) -> Snapshot {
+        let metrics = self
+            .sockets
+            .iter()
+            .flat_map(|socket| {
+                socket.domains.iter().filter_map(|domain| {
+                    let domain_index = (domain.domain_type, socket.id);
+                    diff.get(&domain_index).map(|counter| {
+                        let joules = domain.compute_scale(*counter);
+                        (domain_index, joules_to_micro_joules(joules))
+                    })
+                })
+            })
+            .collect();
+        Snapshot { metrics }
+    }
 }
 
-impl MetricReader for Rapl {
-    type Type = Phase;
+impl BlockingMetricReader for Rapl {
+    type Type = Snapshot;
     type Error = RaplError;
+    type Converter = ();
 
     /// Enable the `perf_event` counters.
-    async fn init(&mut self, _: i32) -> Result<()> {
+    fn init(&mut self, _: i32) -> Result<()> {
         self.sockets
             .iter_mut()
             .try_for_each(|socket| socket.group.enable().map_err(RaplError::from))?;
@@ -134,7 +154,7 @@ impl MetricReader for Rapl {
     /// Perform a measurement and accumulate metrics.
     ///
     /// Computes delta between last and current snapshot.
-    async fn measure(&mut self) -> Result<()> {
+    fn measure(&mut self) -> Result<()> {
         let new_snapshot = self.read_domains_counter()?;
         if self.begin_snapshot.is_none() {
             self.begin_snapshot = Some(new_snapshot);
@@ -144,15 +164,16 @@ impl MetricReader for Rapl {
         Ok(())
     }
 
-    /// Retrieve accumulated metrics since the last reset.
-    async fn retrieve(&mut self) -> Result<Self::Type> {
+    /// Retrieve the energy of each domain in microjoules since the last retrieval.
+    fn retrieve(&mut self) -> Result<Self::Type> {
         trace!("Retrieving RAPL counters");
 
         if let Some(begin) = self.begin_snapshot.take()
             && let Some(end) = self.end_snapshot.take()
         {
-            self.begin_snapshot = Some(end.clone());
-            Ok(Phase { begin, end })
+            let diff = compute_measurement_from_snapshots(&self.sockets, &begin, &end)?;
+            self.begin_snapshot = Some(end);
+            Ok(self.scale_to_micro_joules(&diff))
         } else {
             Err(RaplError::NotEnoughSamples)
         }
@@ -177,31 +198,30 @@ impl MetricReader for Rapl {
         Ok(sensors)
     }
 
-    fn to_metrics(&self, snapshot: Self::Type) -> Result<Metrics> {
-        let diff =
-            compute_measurement_from_snapshots(&self.sockets, &snapshot.begin, &snapshot.end)?;
-        let result = self
-            .sockets
-            .iter()
-            .flat_map(|socket| {
-                socket.domains.iter().filter_map(|domain| {
-                    let domain_index = (domain.domain_type, socket.id);
-                    if let Some(metric) = diff.get(&domain_index) {
-                        let joules = domain.compute_scale(*metric);
-                        let micro_joules = joules_to_micro_joules(joules);
-                        Some(Metric::new(
-                            domain.domain_type.to_string_socket(socket.id),
-                            micro_joules,
-                            MICRO_JOULE_UNIT,
-                            Self::get_name(),
-                        ))
-                    } else {
-                        None
-                    }
-                })
+    fn converter(&self) {}
+
+    fn to_metrics((): &(), snapshot: Self::Type) -> Result<Metrics> {
+        Ok(snapshot
+            .metrics
+            .into_iter()
+            .map(|((domain, socket), value)| {
+                Metric::new(
+                    domain.to_string_socket(socket),
+                    value,
+                    MICRO_JOULE_UNIT,
+                    Self::get_name(),
+                )
             })
-            .collect();
-        Ok(result)
+            .collect())
+    }
+
+    /// The kernel handles the RAPL counters wraps with 64-bit perf counters.
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            supports_polling: true,
+            min_sampling_interval: Some(RAPL_UPDATE_INTERVAL),
+            ..Default::default()
+        }
     }
 
     fn get_name() -> &'static str {
@@ -1,10 +1,11 @@
 //! Module `rapl` — Intel RAPL metric source.
 //!
-//! This module provides an implementation of a [`MetricReader`] for
+//! This module provides an implementation of a [`BlockingMetricReader`] for
 //! collecting energy metrics from Intel RAPL (Running Average Power Limit) domains.
 //!
-//! The `Rapl` struct manages RAPL domains, reads energy counters,
-//! and optionally supports periodic polling for continuous measurement.
+//! The `Rapl` struct manages RAPL domains and reads energy counters. Periodic polling, protecting
+//! the counters from wrapping during long phases, is left to the profiler runtime (see
+//! `JouleProfiler::add_source_with_polling`).
 //!
 //! # Features
 //!
@@ -12,30 +13,30 @@
 //! - Read instantaneous energy consumption snapshots.
 //! - Compute energy usage between consecutive snapshots.
 //! - Provide sensors information for integration with the profiler.
-//! - Optional async scheduler for periodic measurement.
 //!
 //! # Usage
 //!
 //! ```no_run
 //! use joule_profiler_source_rapl::powercap;
-//! use joule_profiler_core::source::MetricReader;
+//! use joule_profiler_core::source::BlockingMetricReader;
 //!
-//! #[tokio::main]
-//! async fn main() {
-//!     // Initialize a RAPL reader (no polling, monitoring all sockets)
-//!     let mut rapl = powercap::Rapl::try_default().unwrap();
+//! // Initialize a RAPL reader monitoring all sockets
+//! let mut rapl = powercap::Rapl::try_default().unwrap();
 //!
-//!     // Measure and update internal counters
-//!     rapl.measure().await.unwrap();
+//! // Measure and update internal counters
+//! rapl.measure().unwrap();
 //!
-//!     // Retrieve available sensors
-//!     let sensors = rapl.get_sensors().unwrap();
+//! // Retrieve available sensors
+//! let sensors = rapl.get_sensors().unwrap();
 //!
-//!     // Retrieve collected counters
-//!     let counters = rapl.retrieve().await.unwrap();
-//! }
+//! // Retrieve collected counters
+//! let counters = rapl.retrieve().unwrap();
 //! ```
 //!
+//! The profiler runs the reader through a
+//! [`BlockingAdapter`](joule_profiler_core::source::BlockingAdapter), so that reading the
+//! powercap files never blocks its runtime.
+//!
 //! # Errors
 //!
 //! All RAPL operations return a [`RaplError`]. Possible errors include:
@@ -44,26 +45,21 @@
 //! - [`RaplError::UnsupportedOS`] - only Linux is supported.
 //! - [`RaplError::RaplReadError`] or [`RaplError::InvalidRaplPath`] - problems reading counters or invalid paths.
 
-use crate::MICRO_JOULE_UNIT;
 use crate::error::RaplError;
 use crate::powercap::compute::compute_measurement_from_snapshots;
 use crate::powercap::domain::{RaplDomain, get_domains, read_energy};
 use crate::snapshot::Snapshot;
 use crate::util::check_os;
-use futures::StreamExt;
+use crate::{MICRO_JOULE_UNIT, RAPL_UPDATE_INTERVAL};
 use joule_profiler_core::sensor::{Sensor, Sensors};
-use joule_profiler_core::source::MetricReader;
+use joule_profiler_core::source::{BlockingMetricReader, SourceCapabilities};
 use joule_profiler_core::types::{Metric, Metrics};
 use log::{debug, error, info, trace};
 use std::collections::HashSet;
 use std::fs;
 use std::io::ErrorKind;
 use std::path::Path;
-use std::sync::Arc;
 use std::{collections::HashMap, env, time::Duration};
-use tokio::sync::Mutex;
-use tokio::task::JoinHandle;
-use tokio_timerfd::Interval;
 
 mod compute;
 mod domain;
@@ -72,6 +68,9 @@ mod socket;
 const DEFAULT_RAPL_PATH: &str = "/sys/devices/virtual/powercap/intel-rapl";
 const POWERCAP_SOURCE_NAME: &str = "RAPL (Powercap)";
 
+/// Upper bound of a RAPL domain power draw in watts, used to estimate the shortest counter wrap period.
+const MAX_DOMAIN_POWER_W: f64 = 500.0;
+
 /// Custom result type for Rapl
 type Result<T> = std::result::Result<T, RaplError>;
 
@@ -82,16 +81,11 @@ pub struct Rapl {
     /// Managed RAPL domains discovered under the base path. Each domain corresponds.
     domains: Vec<RaplDomain>,
 
-    poll_interval: Option<Duration>,
-
     /// Current energy counters.
-    current_counters: Arc<Mutex<Snapshot>>,
+    current_counters: Snapshot,
 
     /// Latest energy counters collected by this reader.
-    last_snapshot: Arc<Mutex<Option<Snapshot>>>,
-
-    /// The handle to the polling task for joining if polling is active.
-    handle: Option<JoinHandle<Result<()>>>,
+    last_snapshot: Option<Snapshot>,
 }
 
 impl Rapl {
@@ -99,7 +93,6 @@ impl Rapl {
     ///
     /// `rapl_path` - base path to RAPL domains (e.g., `/sys/devices/virtual/powercap/intel-rapl`)
     /// `sockets` - optional set of CPU sockets to monitor
-    /// `polling_rate_s` - optional interval in seconds for periodic measurement
     ///
     /// # Errors
     ///
@@ -107,11 +100,7 @@ impl Rapl {
     /// - RAPL interface is unavailable
     /// - Path is invalid
     /// - Permissions are insufficient
-    pub fn new(
-        rapl_path: Option<&str>,
-        sockets_spec: Option<&HashSet<u32>>,
-        polling_rate_s: Option<f64>,
-    ) -> Result<Self> {
+    pub fn new(rapl_path: Option<&str>, sockets_spec: Option<&HashSet<u32>>) -> Result<Self> {
         let rapl_path = rapl_base_path(rapl_path);
 
         trace!(
@@ -129,27 +118,35 @@ impl Rapl {
 
         info!("Discovered {} RAPL domain(s)", domains.len());
 
-        let poll_interval = polling_rate_s.map(Duration::from_secs_f64);
-
-        trace!(
-            "Creating Rapl instance (domains={}, ticker={})",
-            domains.len(),
-            poll_interval.is_some()
-        );
+        trace!("Creating Rapl instance (domains={})", domains.len());
 
         Ok(Rapl {
             rapl_path,
             domains,
-            poll_interval,
-            current_counters: Arc::default(),
-            last_snapshot: Arc::default(),
-            handle: None,
+            current_counters: Snapshot::default(),
+            last_snapshot: None,
         })
     }
 
     /// Initializes an Rapl source with default configuration.
     pub fn try_default() -> Result<Self> {
-        Rapl::new(None, None, None)
+        Rapl::new(None, None)
+    }
+
+    /// Estimates the shortest duration after which a domain counter can wrap around.
+    ///
+    /// The estimation assumes the domain draws [`MAX_DOMAIN_POWER_W`], making it conservative.
+    fn counter_wrap_period(&self) -> Option<Duration> {
+        self.domains
+            .iter()
+            .map(|domain| domain.max_energy_uj)
+            .filter(|max_energy_uj| *max_energy_uj > 0)
+            .min()
+            .map(|max_energy_uj| {
+                #[allow(clippy::cast_precision_loss)]
+                let max_energy_j = max_energy_uj as f64 / 1_000_000.0;
+                Duration::from_secs_f64(max_energy_j / MAX_DOMAIN_POWER_W)
+            })
     }
 
     /// Reads a snapshot of current energy counters for all domains.
@@ -179,91 +176,29 @@ impl Rapl {
     }
 }
 
-impl MetricReader for Rapl {
+impl BlockingMetricReader for Rapl {
     type Type = Snapshot;
     type Error = RaplError;
+    type Converter = ();
 
-    async fn init(&mut self, _: i32) -> Result<()> {
-        check_rapl_access(&self.rapl_path)?;
-
-        let mut ticker = if let Some(interval) = self.poll_interval {
-            Interval::new_interval(interval)?
-        } else {
-            return Ok(());
-        };
-
-        let domains = self.domains.clone();
-        let last_snapshot = self.last_snapshot.clone();
-        let current_counters = self.current_counters.clone();
-
-        let handle = tokio::spawn(async move {
-            loop {
-                ticker.next().await;
-                trace!("Rapl polled");
-
-                let Ok(mut last_guard) = last_snapshot.try_lock() else {
-                    trace!("RAPL measure skipped: previous measurement still running");
-                    continue;
-                };
-
-                let new_snapshot = {
-                    let mut map = HashMap::with_capacity(domains.len());
-                    for domain in &domains {
-                        let domain_index = (domain.domain_type, domain.socket);
-                        let val_uj = read_energy(domain)?;
-                        map.insert(domain_index, val_uj);
-                    }
-                    Snapshot { metrics: map }
-                };
-
-                if let Some(prev) = last_guard.as_ref() {
-                    let metrics =
-                        compute_measurement_from_snapshots(&domains, prev, &new_snapshot)?;
-
-                    let mut counters = current_counters.lock().await;
-                    *counters += metrics;
-                }
-
-                *last_guard = Some(new_snapshot);
-            }
-        });
-
-        self.handle = Some(handle);
-        Ok(())
-    }
-
-    async fn join(&mut self) -> Result<()> {
-        if let Some(handle) = self.handle.take() {
-            if handle.is_finished() {
-                if let Ok(res) = handle.await {
-                    return res;
-                }
-            } else {
-                handle.abort();
-            }
-        }
-        Ok(())
+    fn init(&mut self, _: i32) -> Result<()> {
+        check_rapl_access(&self.rapl_path)
     }
 
-    async fn measure(&mut self) -> Result<()> {
+    fn measure(&mut self) -> Result<()> {
         let new_snapshot = self.read_snapshot()?;
 
-        let mut last = self.last_snapshot.lock().await;
-
-        if let Some(prev) = last.as_ref() {
+        if let Some(prev) = &self.last_snapshot {
             let metrics = compute_measurement_from_snapshots(&self.domains, prev, &new_snapshot)?;
-
-            let mut counters = self.current_counters.lock().await;
-            *counters += metrics;
+            self.current_counters += metrics;
         }
 
-        *last = Some(new_snapshot);
+        self.last_snapshot = Some(new_snapshot);
         Ok(())
     }
 
-    async fn retrieve(&mut self) -> Result<Snapshot> {
-        let mut lock = self.current_counters.lock().await;
-        Ok(std::mem::take(&mut *lock))
+    fn retrieve(&mut self) -> Result<Snapshot> {
+        Ok(std::mem::take(&mut self.current_counters))
     }
 
     fn get_sensors(&self) -> Result<Sensors> {
@@ -281,7 +216,9 @@ impl MetricReader for Rapl {
         Ok(sensors)
     }
 
-    fn to_metrics(&self, snapshot: Self::Type) -> Result<Metrics> {
+    fn converter(&self) {}
+
+    fn to_metrics((): &(), snapshot: Self::Type) -> Result<Metrics> {
         Ok(snapshot
             .metrics
             .into_iter()
@@ -296,6 +233,15 @@ impl MetricReader for Rapl {
             .collect())
     }
 
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            supports_polling: true,
+            counter_wrap_period: self.counter_wrap_period(),
+            min_sampling_interval: Some(RAPL_UPDATE_INTERVAL),
+            ..Default::default()
+        }
+    }
+
     fn get_name() -> &'static str {
         POWERCAP_SOURCE_NAME
     }
@@ -15,13 +15,3 @@ impl AddAssign<HashMap<RaplDomainIndex, u64>> for Snapshot {
         }
     }
 }
-
-/// A pair of snapshots delimiting a phase.
-#[derive(Debug, Clone, Default)]
-pub struct Phase {
-    /// The snapshot made at the start of a phase.
-    pub begin: Snapshot,
-
-    /// End snapshot of the phase.
-    pub end: Snapshot,
-}